@@ -256,6 +256,7 @@ fn row_to_py(py: Python, row: &limbo_core::Row) -> PyObject {
             limbo_core::Value::Float(f) => f.to_object(py),
             limbo_core::Value::Text(s) => s.to_object(py),
             limbo_core::Value::Blob(b) => b.to_object(py),
+            limbo_core::Value::Record(_) => value.to_string().to_object(py),
         })
         .collect();
 
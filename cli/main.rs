@@ -277,11 +277,12 @@ fn query(
                             match value {
                                 Value::Null => print!(""),
                                 Value::Integer(i) => print!("{}", i),
-                                Value::Float(f) => print!("{:?}", f),
+                                Value::Float(_) => print!("{}", value),
                                 Value::Text(s) => print!("{}", s),
                                 Value::Blob(b) => {
                                     print!("{}", String::from_utf8_lossy(b))
                                 }
+                                Value::Record(_) => print!("{}", value),
                             }
                         }
                         println!();
@@ -313,11 +314,12 @@ fn query(
                                     .map(|value| match value {
                                         Value::Null => "".cell(),
                                         Value::Integer(i) => i.to_string().cell(),
-                                        Value::Float(f) => f.to_string().cell(),
+                                        Value::Float(_) => value.to_string().cell(),
                                         Value::Text(s) => s.cell(),
                                         Value::Blob(b) => {
                                             format!("{}", String::from_utf8_lossy(b)).cell()
                                         }
+                                        Value::Record(_) => value.to_string().cell(),
                                     })
                                     .collect(),
                             );
@@ -1,4 +1,4 @@
-use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use limbo_core::{Database, PlatformIO, IO};
 use pprof::criterion::{Output, PProfProfiler};
 use std::sync::Arc;
@@ -99,6 +99,56 @@ fn limbo_bench(criterion: &mut Criterion) {
             });
         },
     );
+
+    // Materializes every text cell, so this tracks the per-value cost of text storage.
+    let mut stmt = conn
+        .prepare("SELECT first_name, last_name, email FROM users LIMIT 100")
+        .unwrap();
+    group.bench_function(
+        "Execute prepared statement: 'SELECT first_name, last_name, email FROM users LIMIT 100'",
+        |b| {
+            let io = io.clone();
+            b.iter(|| {
+                let mut rows = stmt.query().unwrap();
+                loop {
+                    match rows.next_row().unwrap() {
+                        limbo_core::RowResult::Row(row) => {
+                            black_box(row.get::<&str>(0).unwrap());
+                        }
+                        limbo_core::RowResult::IO => {
+                            io.run_once().unwrap();
+                        }
+                        limbo_core::RowResult::Done => break,
+                    }
+                }
+                stmt.reset();
+            });
+        },
+    );
+
+    // Seeks age_idx, probing index cells that mostly differ from the key in their first column.
+    let mut stmt = conn.prepare("SELECT id FROM users WHERE age = 30").unwrap();
+    group.bench_function(
+        "Execute prepared statement: 'SELECT id FROM users WHERE age = 30'",
+        |b| {
+            let io = io.clone();
+            b.iter(|| {
+                let mut rows = stmt.query().unwrap();
+                loop {
+                    match rows.next_row().unwrap() {
+                        limbo_core::RowResult::Row(row) => {
+                            black_box(row.get::<i64>(0).unwrap());
+                        }
+                        limbo_core::RowResult::IO => {
+                            io.run_once().unwrap();
+                        }
+                        limbo_core::RowResult::Done => break,
+                    }
+                }
+                stmt.reset();
+            });
+        },
+    );
 }
 
 fn rusqlite_bench(criterion: &mut Criterion) {
@@ -156,6 +206,36 @@ fn rusqlite_bench(criterion: &mut Criterion) {
             });
         },
     );
+
+    let mut stmt = conn
+        .prepare("SELECT first_name, last_name, email FROM users LIMIT 100")
+        .unwrap();
+    group.bench_function(
+        "Execute prepared statement: 'SELECT first_name, last_name, email FROM users LIMIT 100'",
+        |b| {
+            b.iter(|| {
+                let mut rows = stmt.query(()).unwrap();
+                while let Some(row) = rows.next().unwrap() {
+                    let first_name: String = row.get(0).unwrap();
+                    black_box(first_name);
+                }
+            });
+        },
+    );
+
+    let mut stmt = conn.prepare("SELECT id FROM users WHERE age = 30").unwrap();
+    group.bench_function(
+        "Execute prepared statement: 'SELECT id FROM users WHERE age = 30'",
+        |b| {
+            b.iter(|| {
+                let mut rows = stmt.query(()).unwrap();
+                while let Some(row) = rows.next().unwrap() {
+                    let id: i64 = row.get(0).unwrap();
+                    black_box(id);
+                }
+            });
+        },
+    );
 }
 
 criterion_group! {
@@ -14,6 +14,13 @@ pub enum LimboError {
     LexerError(#[from] sqlite3_parser::lexer::sql::Error),
     #[error("Conversion error: {0}")]
     ConversionError(String),
+    #[error("Conversion error: Expected {expected} value, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("Column index out of range: {0}")]
+    ColumnIndexOutOfRange(usize),
     #[error("Env variable error: {0}")]
     EnvVarError(#[from] std::env::VarError),
     #[error("I/O error: {0}")]
@@ -38,6 +45,10 @@ pub enum LimboError {
     InvalidModifier(String),
     #[error("Runtime error: {0}")]
     Constraint(String),
+    #[error("Runtime error: integer overflow")]
+    IntegerOverflow,
+    #[error("Parameter error: {0}")]
+    ParameterError(String),
 }
 
 #[macro_export]
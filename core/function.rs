@@ -1,3 +1,4 @@
+use crate::types::VarianceKind;
 use std::fmt;
 use std::fmt::Display;
 
@@ -30,6 +31,7 @@ pub enum AggFunc {
     StringAgg,
     Sum,
     Total,
+    Variance(VarianceKind),
 }
 
 impl AggFunc {
@@ -43,6 +45,10 @@ impl AggFunc {
             Self::StringAgg => "string_agg",
             Self::Sum => "sum",
             Self::Total => "total",
+            Self::Variance(VarianceKind::VarPop) => "var_pop",
+            Self::Variance(VarianceKind::VarSamp) => "var_samp",
+            Self::Variance(VarianceKind::StdDevPop) => "stddev_pop",
+            Self::Variance(VarianceKind::StdDevSamp) => "stddev_samp",
         }
     }
 }
@@ -72,6 +78,7 @@ pub enum ScalarFunc {
     Max,
     Nullif,
     Sign,
+    Trunc,
     Substr,
     Substring,
     Date,
@@ -79,6 +86,7 @@ pub enum ScalarFunc {
     Typeof,
     Unicode,
     Quote,
+    Replace,
     SqliteVersion,
     UnixEpoch,
     Hex,
@@ -112,6 +120,7 @@ impl Display for ScalarFunc {
             Self::Max => "max",
             Self::Nullif => "nullif",
             Self::Sign => "sign",
+            Self::Trunc => "trunc",
             Self::Substr => "substr",
             Self::Substring => "substring",
             Self::Date => "date",
@@ -119,6 +128,7 @@ impl Display for ScalarFunc {
             Self::Typeof => "typeof",
             Self::Unicode => "unicode",
             Self::Quote => "quote",
+            Self::Replace => "replace",
             Self::SqliteVersion => "sqlite_version",
             Self::UnixEpoch => "unixepoch",
             Self::Hex => "hex",
@@ -168,6 +178,10 @@ impl Func {
             "string_agg" => Self::Agg(AggFunc::StringAgg),
             "sum" => Self::Agg(AggFunc::Sum),
             "total" => Self::Agg(AggFunc::Total),
+            "variance" | "var_samp" => Self::Agg(AggFunc::Variance(VarianceKind::VarSamp)),
+            "var_pop" => Self::Agg(AggFunc::Variance(VarianceKind::VarPop)),
+            "stddev" | "stddev_samp" => Self::Agg(AggFunc::Variance(VarianceKind::StdDevSamp)),
+            "stddev_pop" => Self::Agg(AggFunc::Variance(VarianceKind::StdDevPop)),
             "char" => Self::Scalar(ScalarFunc::Char),
             "coalesce" => Self::Scalar(ScalarFunc::Coalesce),
             "concat" => Self::Scalar(ScalarFunc::Concat),
@@ -187,6 +201,7 @@ impl Func {
             "round" => Self::Scalar(ScalarFunc::Round),
             "length" => Self::Scalar(ScalarFunc::Length),
             "sign" => Self::Scalar(ScalarFunc::Sign),
+            "trunc" => Self::Scalar(ScalarFunc::Trunc),
             "substr" => Self::Scalar(ScalarFunc::Substr),
             "substring" => Self::Scalar(ScalarFunc::Substring),
             "date" => Self::Scalar(ScalarFunc::Date),
@@ -194,6 +209,7 @@ impl Func {
             "typeof" => Self::Scalar(ScalarFunc::Typeof),
             "unicode" => Self::Scalar(ScalarFunc::Unicode),
             "quote" => Self::Scalar(ScalarFunc::Quote),
+            "replace" => Self::Scalar(ScalarFunc::Replace),
             "sqlite_version" => Self::Scalar(ScalarFunc::SqliteVersion),
             #[cfg(feature = "json")]
             "json" => Self::Json(JsonFunc::Json),
@@ -1,6 +1,7 @@
 mod de;
 mod error;
 mod ser;
+mod strict;
 
 use std::rc::Rc;
 
@@ -27,7 +28,7 @@ pub fn get_json(json_value: &OwnedValue) -> crate::Result<OwnedValue> {
         OwnedValue::Text(ref t) => match crate::json::from_str::<Val>(t) {
             Ok(json) => {
                 let json = crate::json::to_string(&json).unwrap();
-                Ok(OwnedValue::Text(Rc::new(json)))
+                Ok(OwnedValue::Text(Rc::from(json)))
             }
             Err(_) => {
                 crate::bail_parse_error!("malformed JSON")
@@ -35,7 +36,7 @@ pub fn get_json(json_value: &OwnedValue) -> crate::Result<OwnedValue> {
         },
         OwnedValue::Blob(b) => {
             if let Ok(json) = jsonb::from_slice(b) {
-                Ok(OwnedValue::Text(Rc::new(json.to_string())))
+                Ok(OwnedValue::Text(Rc::from(json.to_string())))
             } else {
                 crate::bail_parse_error!("malformed JSON");
             }
@@ -44,6 +45,109 @@ pub fn get_json(json_value: &OwnedValue) -> crate::Result<OwnedValue> {
     }
 }
 
+/// Whether the value is text or a number holding a JSON document as RFC 8259
+/// defines it. Like in SQLite, JSON5 extensions make the document invalid.
+pub fn json_valid(json_value: &OwnedValue) -> bool {
+    match json_value {
+        OwnedValue::Text(t) => strict::is_valid(t),
+        OwnedValue::Integer(_) | OwnedValue::Float(_) => strict::is_valid(&json_value.text_lossy()),
+        _ => false,
+    }
+}
+
+/// Extracts the element at `path`, like `$.a.b[0]`, from the JSON text.
+/// Strings and numbers become text, integers and reals, `null` and missing
+/// elements NULL, booleans 1 or 0, and arrays and objects their JSON text.
+/// Numbers are read as the JSON text they print as. NULL input gives NULL.
+pub fn json_extract(json_value: &OwnedValue, path: &str) -> crate::Result<OwnedValue> {
+    let text = match json_value {
+        OwnedValue::Null => return Ok(OwnedValue::Null),
+        OwnedValue::Text(_) | OwnedValue::Integer(_) | OwnedValue::Float(_) => {
+            json_value.text_lossy()
+        }
+        _ => crate::bail_parse_error!("malformed JSON"),
+    };
+    let json = match crate::json::from_str::<Val>(&text) {
+        Ok(json) => json,
+        Err(_) => crate::bail_parse_error!("malformed JSON"),
+    };
+    let mut current = &json;
+    for step in parse_json_path(path)? {
+        let next = match (step, current) {
+            (PathStep::Key(key), Val::Object(map)) => map.get(&key),
+            (PathStep::Index(idx), Val::Array(items)) => items.get(idx),
+            (PathStep::FromEnd(back), Val::Array(items)) => {
+                items.len().checked_sub(back).and_then(|idx| items.get(idx))
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(OwnedValue::Null),
+        }
+    }
+    Ok(match current {
+        Val::Null => OwnedValue::Null,
+        Val::Bool(b) => OwnedValue::Integer(*b as i64),
+        Val::Integer(i) => OwnedValue::Integer(*i),
+        Val::Float(f) => OwnedValue::Float(*f),
+        Val::String(s) => OwnedValue::Text(Rc::from(s.as_str())),
+        Val::Array(_) | Val::Object(_) => {
+            OwnedValue::Text(Rc::from(crate::json::to_string(current).unwrap()))
+        }
+    })
+}
+
+enum PathStep {
+    Key(String),
+    Index(usize),
+    /// `[#-N]`, the N-th element from the end of an array
+    FromEnd(usize),
+}
+
+fn parse_json_path(path: &str) -> crate::Result<Vec<PathStep>> {
+    let Some(mut rest) = path.strip_prefix('$') else {
+        crate::bail_parse_error!("bad JSON path: {}", path);
+    };
+    let mut steps = vec![];
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let (key, after_key) = match after_dot.strip_prefix('"') {
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                    None => crate::bail_parse_error!("bad JSON path: {}", path),
+                },
+                None => {
+                    let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                    (&after_dot[..end], &after_dot[end..])
+                }
+            };
+            if key.is_empty() && !after_dot.starts_with('"') {
+                crate::bail_parse_error!("bad JSON path: {}", path);
+            }
+            steps.push(PathStep::Key(key.to_string()));
+            rest = after_key;
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let Some(end) = after_bracket.find(']') else {
+                crate::bail_parse_error!("bad JSON path: {}", path);
+            };
+            let index = &after_bracket[..end];
+            let step = match index.strip_prefix("#-") {
+                Some(back) => back.parse().ok().map(PathStep::FromEnd),
+                None => index.parse().ok().map(PathStep::Index),
+            };
+            let Some(step) = step else {
+                crate::bail_parse_error!("bad JSON path: {}", path);
+            };
+            steps.push(step);
+            rest = &after_bracket[end + 1..];
+        } else {
+            crate::bail_parse_error!("bad JSON path: {}", path);
+        }
+    }
+    Ok(steps)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,7 +155,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_json5() {
-        let input = OwnedValue::Text(Rc::new("{ key: 'value' }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ key: 'value' }"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("\"key\":\"value\""));
@@ -62,7 +166,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_json5_double_single_quotes() {
-        let input = OwnedValue::Text(Rc::new("{ key: ''value'' }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ key: ''value'' }"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("\"key\":\"value\""));
@@ -73,7 +177,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_json5_infinity() {
-        let input = OwnedValue::Text(Rc::new("{ \"key\": Infinity }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ \"key\": Infinity }"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("{\"key\":9e999}"));
@@ -84,7 +188,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_json5_negative_infinity() {
-        let input = OwnedValue::Text(Rc::new("{ \"key\": -Infinity }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ \"key\": -Infinity }"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("{\"key\":-9e999}"));
@@ -95,7 +199,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_json5_nan() {
-        let input = OwnedValue::Text(Rc::new("{ \"key\": NaN }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ \"key\": NaN }"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("{\"key\":null}"));
@@ -106,7 +210,7 @@ mod tests {
 
     #[test]
     fn test_get_json_invalid_json5() {
-        let input = OwnedValue::Text(Rc::new("{ key: value }".to_string()));
+        let input = OwnedValue::Text(Rc::from("{ key: value }"));
         let result = get_json(&input);
         match result {
             Ok(_) => panic!("Expected error for malformed JSON"),
@@ -116,7 +220,7 @@ mod tests {
 
     #[test]
     fn test_get_json_valid_jsonb() {
-        let input = OwnedValue::Text(Rc::new("{\"key\":\"value\"}".to_string()));
+        let input = OwnedValue::Text(Rc::from("{\"key\":\"value\"}"));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("\"key\":\"value\""));
@@ -127,7 +231,7 @@ mod tests {
 
     #[test]
     fn test_get_json_invalid_jsonb() {
-        let input = OwnedValue::Text(Rc::new("{key:\"value\"".to_string()));
+        let input = OwnedValue::Text(Rc::from("{key:\"value\""));
         let result = get_json(&input);
         match result {
             Ok(_) => panic!("Expected error for malformed JSON"),
@@ -138,7 +242,7 @@ mod tests {
     #[test]
     fn test_get_json_blob_valid_jsonb() {
         let binary_json = b"\x40\0\0\x01\x10\0\0\x03\x10\0\0\x03\x61\x73\x64\x61\x64\x66".to_vec();
-        let input = OwnedValue::Blob(Rc::new(binary_json));
+        let input = OwnedValue::Blob(Rc::from(binary_json));
         let result = get_json(&input).unwrap();
         if let OwnedValue::Text(result_str) = result {
             assert!(result_str.contains("\"asd\":\"adf\""));
@@ -150,7 +254,7 @@ mod tests {
     #[test]
     fn test_get_json_blob_invalid_jsonb() {
         let binary_json: Vec<u8> = vec![0xA2, 0x62, 0x6B, 0x31, 0x62, 0x76]; // Incomplete binary JSON
-        let input = OwnedValue::Blob(Rc::new(binary_json));
+        let input = OwnedValue::Blob(Rc::from(binary_json));
         let result = get_json(&input);
         match result {
             Ok(_) => panic!("Expected error for malformed JSON"),
@@ -168,4 +272,63 @@ mod tests {
             panic!("Expected OwnedValue::Null");
         }
     }
+
+    #[test]
+    fn test_json_valid() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert!(json_valid(&text(r#"{"a": [1, 2.5, "x"]}"#)));
+        assert!(json_valid(&text("[]")));
+        assert!(json_valid(&text(r#" "a\"b\u00e9" "#)));
+        assert!(json_valid(&text("-0.5e+3")));
+        assert!(json_valid(&OwnedValue::Integer(123)));
+        assert!(json_valid(&OwnedValue::Float(1.5)));
+        assert!(!json_valid(&text("{ key: 'value' }")));
+        assert!(!json_valid(&text("[1, 2,]")));
+        assert!(!json_valid(&text("0x10")));
+        assert!(!json_valid(&text("01")));
+        assert!(!json_valid(&text(r#"{"a": "#)));
+        assert!(!json_valid(&OwnedValue::Null));
+        assert!(!json_valid(&OwnedValue::Blob(Rc::from(b"1".as_slice()))));
+    }
+
+    #[test]
+    fn test_json_extract() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let doc = text(r#"{"a": {"b": [10, 2.5, "x", null, true]}, "c d": "e"}"#);
+        let extract = |path: &str| json_extract(&doc, path).unwrap();
+        assert_eq!(extract("$.a.b[0]"), OwnedValue::Integer(10));
+        assert_eq!(extract("$.a.b[1]"), OwnedValue::Float(2.5));
+        assert_eq!(extract("$.a.b[2]"), text("x"));
+        assert_eq!(extract("$.a.b[3]"), OwnedValue::Null);
+        assert_eq!(extract("$.a.b[4]"), OwnedValue::Integer(1));
+        assert_eq!(extract("$.a.b[#-1]"), OwnedValue::Integer(1));
+        assert_eq!(extract("$.a.b[5]"), OwnedValue::Null);
+        assert_eq!(extract("$.a.b[#-6]"), OwnedValue::Null);
+        assert_eq!(extract(r#"$."c d""#), text("e"));
+        assert_eq!(extract("$.missing.b"), OwnedValue::Null);
+        assert_eq!(extract("$.a"), text(r#"{"b":[10,2.5,"x",null,true]}"#));
+        assert_eq!(
+            extract("$"),
+            text(r#"{"a":{"b":[10,2.5,"x",null,true]},"c d":"e"}"#)
+        );
+        assert_eq!(
+            json_extract(&OwnedValue::Null, "$.a").unwrap(),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            json_extract(&OwnedValue::Integer(5), "$").unwrap(),
+            OwnedValue::Integer(5)
+        );
+        assert_eq!(
+            json_extract(&OwnedValue::Float(2.5), "$").unwrap(),
+            OwnedValue::Float(2.5)
+        );
+        assert_eq!(
+            json_extract(&OwnedValue::Integer(5), "$.a").unwrap(),
+            OwnedValue::Null
+        );
+        assert!(json_extract(&doc, "a.b").is_err());
+        assert!(json_extract(&doc, "$.a[x]").is_err());
+        assert!(json_extract(&text("{"), "$").is_err());
+    }
 }
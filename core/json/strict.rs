@@ -0,0 +1,39 @@
+use pest::Parser as P;
+use pest_derive::Parser;
+
+#[derive(Parser)]
+#[grammar_inline = r#"
+// see https://www.rfc-editor.org/rfc/rfc8259#section-2
+
+WHITESPACE = _{ " " | "\t" | "\n" | "\r" }
+
+text = _{ SOI ~ value ~ EOI }
+
+value = _{ object | array | string | number | "true" | "false" | "null" }
+
+object = { "{" ~ (member ~ ("," ~ member)*)? ~ "}" }
+
+member = _{ string ~ ":" ~ value }
+
+array = { "[" ~ (value ~ ("," ~ value)*)? ~ "]" }
+
+string = @{ "\"" ~ char* ~ "\"" }
+
+char = _{
+  "\\" ~ ("\"" | "\\" | "/" | "b" | "f" | "n" | "r" | "t" | "u" ~ ASCII_HEX_DIGIT{4}) |
+  !("\"" | "\\" | '\u{00}'..'\u{1F}') ~ ANY
+}
+
+number = @{
+  "-"? ~ ("0" | ASCII_NONZERO_DIGIT ~ ASCII_DIGIT*) ~
+  ("." ~ ASCII_DIGIT+)? ~
+  (^"e" ~ ("+" | "-")? ~ ASCII_DIGIT+)?
+}
+"#]
+struct Parser;
+
+/// Whether `text` is a JSON document as RFC 8259 defines it, without the
+/// JSON5 extensions [`crate::json::from_str`] accepts.
+pub fn is_valid(text: &str) -> bool {
+    Parser::parse(Rule::text, text).is_ok()
+}
@@ -47,6 +47,7 @@ pub use storage::pager::Page;
 pub use storage::pager::Pager;
 pub use storage::wal::CheckpointStatus;
 pub use storage::wal::Wal;
+pub use types::Params;
 pub use types::Value;
 
 pub static DATABASE_VERSION: OnceLock<String> = OnceLock::new();
@@ -360,6 +361,23 @@ impl<'a> Row<'a> {
         let value = &self.values[idx];
         T::from_value(value)
     }
+
+    /// Like `get`, but converting between types the way SQLite's column
+    /// accessors do, see `FromValueCoerced`.
+    pub fn get_coerced<T: crate::types::FromValueCoerced<'a> + 'a>(&self, idx: usize) -> Result<T> {
+        let value = &self.values[idx];
+        T::from_value_coerced(value)
+    }
+
+    /// Like `get_coerced`, but also returning whether the conversion lost
+    /// information, e.g. a real with a fractional part read as an integer.
+    pub fn get_coerced_lossy<T: crate::types::FromValueCoerced<'a> + 'a>(
+        &self,
+        idx: usize,
+    ) -> Result<(T, bool)> {
+        let value = &self.values[idx];
+        T::from_value_coerced_lossy(value)
+    }
 }
 
 pub struct Rows {
@@ -75,6 +75,27 @@ impl Cursor for PseudoCursor {
         Ok(CursorResult::Ok(()))
     }
 
+    fn insert_batch(&mut self, rows: &[(OwnedValue, OwnedRecord)]) -> Result<CursorResult<()>> {
+        if let Some((_, record)) = rows.last() {
+            *self.current.borrow_mut() = Some(record.clone());
+        }
+        Ok(CursorResult::Ok(()))
+    }
+
+    fn delete(&mut self) -> Result<CursorResult<()>> {
+        *self.current.borrow_mut() = None;
+        Ok(CursorResult::Ok(()))
+    }
+
+    fn update(&mut self, record: &OwnedRecord) -> Result<CursorResult<()>> {
+        *self.current.borrow_mut() = Some(record.clone());
+        Ok(CursorResult::Ok(()))
+    }
+
+    fn count(&mut self) -> Result<CursorResult<u64>> {
+        Ok(CursorResult::Ok(self.current.borrow().is_some() as u64))
+    }
+
     fn get_null_flag(&self) -> bool {
         false
     }
@@ -1,3 +1,4 @@
+use crate::types::{Collation, SortColumn};
 use crate::{util::normalize_ident, Result};
 use core::fmt;
 use fallible_iterator::FallibleIterator;
@@ -428,6 +429,7 @@ pub struct Index {
 pub struct IndexColumn {
     pub name: String,
     pub order: Order,
+    pub collation: Collation,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -451,15 +453,30 @@ impl Index {
                 let index_name = normalize_ident(&idx_name.name.0);
                 let index_columns = columns
                     .into_iter()
-                    .map(|col| IndexColumn {
-                        name: normalize_ident(&col.expr.to_string()),
-                        order: match col.order {
-                            Some(sqlite3_parser::ast::SortOrder::Asc) => Order::Ascending,
-                            Some(sqlite3_parser::ast::SortOrder::Desc) => Order::Descending,
-                            None => Order::Ascending,
-                        },
+                    .map(|col| {
+                        let (expr, collation) = match col.expr {
+                            Expr::Collate(expr, name) => {
+                                let collation = Collation::from_name(&name).ok_or_else(|| {
+                                    crate::LimboError::ParseError(format!(
+                                        "no such collation sequence: {}",
+                                        name
+                                    ))
+                                })?;
+                                (*expr, collation)
+                            }
+                            expr => (expr, Collation::Binary),
+                        };
+                        Ok(IndexColumn {
+                            name: normalize_ident(&expr.to_string()),
+                            order: match col.order {
+                                Some(sqlite3_parser::ast::SortOrder::Asc) => Order::Ascending,
+                                Some(sqlite3_parser::ast::SortOrder::Desc) => Order::Descending,
+                                None => Order::Ascending,
+                            },
+                            collation,
+                        })
                     })
-                    .collect();
+                    .collect::<Result<_>>()?;
                 Ok(Index {
                     name: index_name,
                     table_name: normalize_ident(&tbl_name.0),
@@ -471,6 +488,17 @@ impl Index {
             _ => todo!("Expected create index statement"),
         }
     }
+
+    /// Sort order and collation of each key column, for comparing index records.
+    pub fn key_info(&self) -> Vec<SortColumn> {
+        self.columns
+            .iter()
+            .map(|column| SortColumn {
+                order: column.order.clone(),
+                collation: column.collation,
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -698,4 +726,25 @@ mod tests {
         let actual = sqlite_schema_table().to_sql();
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    pub fn test_index_key_info() -> Result<()> {
+        let sql = r#"CREATE INDEX idx ON t1 (a COLLATE NOCASE DESC, b);"#;
+        let index = Index::from_sql(sql, 0)?;
+        assert_eq!(index.columns[0].name, "a");
+        assert_eq!(
+            index.key_info(),
+            vec![
+                SortColumn {
+                    order: Order::Descending,
+                    collation: Collation::NoCase,
+                },
+                SortColumn {
+                    order: Order::Ascending,
+                    collation: Collation::Binary,
+                },
+            ]
+        );
+        Ok(())
+    }
 }
@@ -2,18 +2,21 @@ use log::debug;
 
 use crate::storage::pager::{Page, Pager};
 use crate::storage::sqlite3_ondisk::{
-    read_btree_cell, read_varint, write_varint, BTreeCell, DatabaseHeader, PageContent, PageType,
-    TableInteriorCell, TableLeafCell,
+    compare_record_prefix, read_btree_cell, read_record, read_varint, write_varint, BTreeCell,
+    DatabaseHeader, PageContent, PageType, TableInteriorCell, TableLeafCell,
 };
-use crate::types::{Cursor, CursorResult, OwnedRecord, OwnedValue, SeekKey, SeekOp};
-use crate::Result;
+use crate::types::{
+    Cursor, CursorResult, OwnedRecord, OwnedValue, PendingIo, SeekKey, SeekOp, SortColumn,
+};
+use crate::{LimboError, Result};
 
 use std::cell::{Ref, RefCell};
 use std::pin::Pin;
 use std::rc::Rc;
 
 use super::sqlite3_ondisk::{
-    write_varint_to_vec, IndexInteriorCell, IndexLeafCell, OverflowCell, DATABASE_HEADER_SIZE,
+    payload_overflows, write_varint_to_vec, IndexInteriorCell, IndexLeafCell, OverflowCell,
+    DATABASE_HEADER_SIZE,
 };
 
 /*
@@ -42,7 +45,7 @@ macro_rules! return_if_io {
     ($expr:expr) => {
         match $expr? {
             CursorResult::Ok(v) => v,
-            CursorResult::IO => return Ok(CursorResult::IO),
+            CursorResult::IO(pending) => return Ok(CursorResult::IO(pending)),
         }
     };
 }
@@ -50,12 +53,20 @@ macro_rules! return_if_io {
 /// Check if the page is unlocked, if not return IO.
 macro_rules! return_if_locked {
     ($expr:expr) => {{
-        if $expr.is_locked() {
-            return Ok(CursorResult::IO);
+        let page = &$expr;
+        if page.is_locked() {
+            return Ok(CursorResult::IO(PendingIo::page(page.id)));
         }
     }};
 }
 
+/// The direction the cursor last moved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IterationDirection {
+    Forwards,
+    Backwards,
+}
+
 #[derive(Debug)]
 enum WriteState {
     Start,
@@ -71,6 +82,8 @@ struct WriteInfo {
     scratch_cells: RefCell<Vec<&'static [u8]>>,
     rightmost_pointer: RefCell<Option<u32>>,
     page_copy: RefCell<Option<PageContent>>, // this holds the copy a of a page needed for buffer references
+    /// Rows of the current `insert_batch` call already inserted.
+    batch_idx: usize,
 }
 
 pub struct BTreeCursor {
@@ -86,12 +99,18 @@ pub struct BTreeCursor {
     /// we just moved to a parent page and the parent page is an internal index page which requires
     /// to be consumed.
     going_upwards: bool,
+    /// Cell indices in the page stack and `going_upwards` mean different things
+    /// when moving forwards and backwards, so changing direction seeks the
+    /// current entry again instead of reusing them.
+    direction: IterationDirection,
     /// Write information kept in case of write yields due to I/O. Needs to be stored somewhere
     /// right :).
     write_info: WriteInfo,
     /// Page stack used to traverse the btree.
     /// Each cursor has a stack because each cursor traverses the btree independently.
     stack: PageStack,
+    /// Sort order and collation of the key columns when the btree is an index.
+    key_info: Vec<SortColumn>,
 }
 
 /// Stack of pages representing the tree traversal order.
@@ -126,21 +145,30 @@ impl BTreeCursor {
             null_flag: false,
             database_header,
             going_upwards: false,
+            direction: IterationDirection::Forwards,
             write_info: WriteInfo {
                 state: WriteState::Start,
                 new_pages: RefCell::new(Vec::with_capacity(4)),
                 scratch_cells: RefCell::new(vec![]),
                 rightmost_pointer: None.into(),
                 page_copy: None.into(),
+                batch_idx: 0,
             },
             stack: PageStack {
                 current_page: RefCell::new(-1),
                 cell_indices: RefCell::new([0; BTCURSOR_MAX_DEPTH + 1]),
                 stack: RefCell::new([const { None }; BTCURSOR_MAX_DEPTH + 1]),
             },
+            key_info: Vec::new(),
         }
     }
 
+    /// Sets how index records are ordered. Without it, records are compared with
+    /// `OwnedRecord`'s derived ordering.
+    pub fn set_key_info(&mut self, key_info: Vec<SortColumn>) {
+        self.key_info = key_info;
+    }
+
     fn is_empty_table(&mut self) -> Result<CursorResult<bool>> {
         let page = self.pager.read_page(self.root_page)?;
         let page = RefCell::borrow(&page);
@@ -170,6 +198,8 @@ impl BTreeCursor {
                         return Ok(CursorResult::Ok((None, None)));
                     }
                 }
+                // the cell we moved to in an index interior page comes before the child we left
+                self.going_upwards = true;
                 // continue to next loop to get record from the new page
                 continue;
             }
@@ -180,17 +210,24 @@ impl BTreeCursor {
                 mem_page_rc.borrow().id,
                 cell_idx
             );
-            if mem_page_rc.borrow().is_locked() {
-                return Ok(CursorResult::IO);
-            }
+            return_if_locked!(mem_page_rc.borrow());
             if !mem_page_rc.borrow().is_loaded() {
                 self.pager.load_page(mem_page_rc.clone())?;
-                return Ok(CursorResult::IO);
+                return Ok(CursorResult::IO(PendingIo::page(mem_page_rc.borrow().id)));
             }
             let mem_page = mem_page_rc.borrow();
             let contents = mem_page.contents.as_ref().unwrap();
 
             let cell_count = contents.cell_count();
+            if cell_idx == i32::MAX as usize && !contents.is_leaf() {
+                // we just moved into this page from its parent, start from its rightmost child
+                let rightmost_pointer = contents.rightmost_pointer().unwrap();
+                self.stack.set_cell_index(cell_count as i32 + 1);
+                let mem_page = self.pager.read_page(rightmost_pointer as usize)?;
+                self.stack.push(mem_page);
+                self.stack.set_cell_index(i32::MAX);
+                continue;
+            }
             let cell_idx = if cell_idx >= cell_count {
                 self.stack.set_cell_index(cell_count as i32 - 1);
                 cell_count - 1
@@ -225,8 +262,36 @@ impl BTreeCursor {
                         crate::storage::sqlite3_ondisk::read_record(&_payload)?;
                     return Ok(CursorResult::Ok((Some(_rowid), Some(record))));
                 }
-                BTreeCell::IndexInteriorCell(_) => todo!(),
-                BTreeCell::IndexLeafCell(_) => todo!(),
+                BTreeCell::IndexInteriorCell(IndexInteriorCell {
+                    payload,
+                    left_child_page,
+                    ..
+                }) => {
+                    // Index interior cells hold records too. Going backwards, a cell comes
+                    // right after the child to its right and right before its left child.
+                    if self.going_upwards {
+                        self.going_upwards = false;
+                        let record = crate::storage::sqlite3_ondisk::read_record(&payload)?;
+                        let rowid = match record.values.last() {
+                            Some(OwnedValue::Integer(rowid)) => *rowid as u64,
+                            _ => unreachable!("index cells should have an integer rowid"),
+                        };
+                        return Ok(CursorResult::Ok((Some(rowid), Some(record))));
+                    }
+                    let mem_page = self.pager.read_page(left_child_page as usize)?;
+                    self.stack.push(mem_page);
+                    self.stack.set_cell_index(i32::MAX);
+                    continue;
+                }
+                BTreeCell::IndexLeafCell(IndexLeafCell { payload, .. }) => {
+                    self.stack.retreat();
+                    let record = crate::storage::sqlite3_ondisk::read_record(&payload)?;
+                    let rowid = match record.values.last() {
+                        Some(OwnedValue::Integer(rowid)) => *rowid as u64,
+                        _ => unreachable!("index cells should have an integer rowid"),
+                    };
+                    return Ok(CursorResult::Ok((Some(rowid), Some(record))));
+                }
             }
         }
     }
@@ -243,7 +308,7 @@ impl BTreeCursor {
             return_if_locked!(mem_page_rc.borrow());
             if !mem_page_rc.borrow().is_loaded() {
                 self.pager.load_page(mem_page_rc.clone())?;
-                return Ok(CursorResult::IO);
+                return Ok(CursorResult::IO(PendingIo::page(mem_page_rc.borrow().id)));
             }
             let mem_page = mem_page_rc.borrow();
 
@@ -328,8 +393,8 @@ impl BTreeCursor {
                     self.going_upwards = false;
                     self.stack.advance();
 
-                    let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                     if predicate.is_none() {
+                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                         let rowid = match record.values.last() {
                             Some(OwnedValue::Integer(rowid)) => *rowid as u64,
                             _ => unreachable!("index cells should have an integer rowid"),
@@ -338,15 +403,17 @@ impl BTreeCursor {
                     }
 
                     let (key, op) = predicate.as_ref().unwrap();
-                    let SeekKey::IndexKey(index_key) = key else {
-                        unreachable!("index seek key should be a record");
-                    };
+                    let ordering = self.compare_index_key(payload, key)?;
                     let found = match op {
-                        SeekOp::GT => &record > *index_key,
-                        SeekOp::GE => &record >= *index_key,
-                        SeekOp::EQ => &record == *index_key,
+                        SeekOp::GT => ordering.is_gt(),
+                        SeekOp::GE => ordering.is_ge(),
+                        SeekOp::EQ => ordering.is_eq(),
+                        SeekOp::LE | SeekOp::LT => {
+                            unreachable!("backward seeks don't scan forward")
+                        }
                     };
                     if found {
+                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                         let rowid = match record.values.last() {
                             Some(OwnedValue::Integer(rowid)) => *rowid as u64,
                             _ => unreachable!("index cells should have an integer rowid"),
@@ -358,8 +425,8 @@ impl BTreeCursor {
                 }
                 BTreeCell::IndexLeafCell(IndexLeafCell { payload, .. }) => {
                     self.stack.advance();
-                    let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                     if predicate.is_none() {
+                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                         let rowid = match record.values.last() {
                             Some(OwnedValue::Integer(rowid)) => *rowid as u64,
                             _ => unreachable!("index cells should have an integer rowid"),
@@ -367,15 +434,17 @@ impl BTreeCursor {
                         return Ok(CursorResult::Ok((Some(rowid), Some(record))));
                     }
                     let (key, op) = predicate.as_ref().unwrap();
-                    let SeekKey::IndexKey(index_key) = key else {
-                        unreachable!("index seek key should be a record");
-                    };
+                    let ordering = self.compare_index_key(payload, key)?;
                     let found = match op {
-                        SeekOp::GT => &record > *index_key,
-                        SeekOp::GE => &record >= *index_key,
-                        SeekOp::EQ => &record == *index_key,
+                        SeekOp::GT => ordering.is_gt(),
+                        SeekOp::GE => ordering.is_ge(),
+                        SeekOp::EQ => ordering.is_eq(),
+                        SeekOp::LE | SeekOp::LT => {
+                            unreachable!("backward seeks don't scan forward")
+                        }
                     };
                     if found {
+                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                         let rowid = match record.values.last() {
                             Some(OwnedValue::Integer(rowid)) => *rowid as u64,
                             _ => unreachable!("index cells should have an integer rowid"),
@@ -389,12 +458,27 @@ impl BTreeCursor {
         }
     }
 
+    /// Orders an index record against an index seek key: on every column for
+    /// `IndexKey`, and only on the columns the probe has for `IndexPrefix`.
+    fn compare_index_key(&self, payload: &[u8], key: &SeekKey<'_>) -> Result<std::cmp::Ordering> {
+        match key {
+            SeekKey::IndexKey(index_key) => {
+                Ok(read_record(payload)?.compare(index_key, &self.key_info))
+            }
+            SeekKey::IndexPrefix(prefix) => compare_record_prefix(payload, prefix, &self.key_info),
+            SeekKey::TableRowId(_) => unreachable!("index seek key should be a record"),
+        }
+    }
+
     fn seek(
         &mut self,
         key: SeekKey<'_>,
         op: SeekOp,
     ) -> Result<CursorResult<(Option<u64>, Option<OwnedRecord>)>> {
         return_if_io!(self.move_to(key.clone(), op.clone()));
+        if matches!(op, SeekOp::LE | SeekOp::LT) {
+            return self.seek_backward(key, op);
+        }
 
         {
             let page_rc = self.stack.top();
@@ -424,6 +508,7 @@ impl BTreeCursor {
                             SeekOp::GT => *cell_rowid > rowid_key,
                             SeekOp::GE => *cell_rowid >= rowid_key,
                             SeekOp::EQ => *cell_rowid == rowid_key,
+                            SeekOp::LE | SeekOp::LT => unreachable!(),
                         };
                         self.stack.advance();
                         if found {
@@ -432,17 +517,16 @@ impl BTreeCursor {
                         }
                     }
                     BTreeCell::IndexLeafCell(IndexLeafCell { payload, .. }) => {
-                        let SeekKey::IndexKey(index_key) = key else {
-                            unreachable!("index seek key should be a record");
-                        };
-                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
+                        let ordering = self.compare_index_key(payload, &key)?;
                         let found = match op {
-                            SeekOp::GT => record > *index_key,
-                            SeekOp::GE => record >= *index_key,
-                            SeekOp::EQ => record == *index_key,
+                            SeekOp::GT => ordering.is_gt(),
+                            SeekOp::GE => ordering.is_ge(),
+                            SeekOp::EQ => ordering.is_eq(),
+                            SeekOp::LE | SeekOp::LT => unreachable!(),
                         };
                         self.stack.advance();
                         if found {
+                            let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
                             let rowid = match record.values.last() {
                                 Some(OwnedValue::Integer(rowid)) => *rowid as u64,
                                 _ => unreachable!("index cells should have an integer rowid"),
@@ -458,7 +542,7 @@ impl BTreeCursor {
         }
 
         // We have now iterated over all cells in the leaf page and found no match.
-        let is_index = matches!(key, SeekKey::IndexKey(_));
+        let is_index = matches!(key, SeekKey::IndexKey(_) | SeekKey::IndexPrefix(_));
         if is_index {
             // Unlike tables, indexes store payloads in interior cells as well. self.move_to() always moves to a leaf page, so there are cases where we need to
             // move back up to the parent interior cell and get the next record from there to perform a correct seek.
@@ -480,8 +564,162 @@ impl BTreeCursor {
         Ok(CursorResult::Ok((None, None)))
     }
 
+    /// Finishes a `SeekOp::LE` or `SeekOp::LT` seek once `move_to` has reached the
+    /// leaf page that would hold `key`, returning the largest entry that satisfies
+    /// the bound. The stack is left ready for `get_prev_record`.
+    fn seek_backward(
+        &mut self,
+        key: SeekKey<'_>,
+        op: SeekOp,
+    ) -> Result<CursorResult<(Option<u64>, Option<OwnedRecord>)>> {
+        {
+            let page_rc = self.stack.top();
+            let page = page_rc.borrow();
+            return_if_locked!(page);
+
+            let contents = page.contents.as_ref().unwrap();
+
+            for cell_idx in (0..contents.cell_count()).rev() {
+                let cell = contents.cell_get(
+                    cell_idx,
+                    self.pager.clone(),
+                    self.max_local(contents.page_type()),
+                    self.min_local(contents.page_type()),
+                    self.usable_space(),
+                )?;
+                match &cell {
+                    BTreeCell::TableLeafCell(TableLeafCell {
+                        _rowid: cell_rowid,
+                        _payload: payload,
+                        first_overflow_page: _,
+                    }) => {
+                        let SeekKey::TableRowId(rowid_key) = key else {
+                            unreachable!("table seek key should be a rowid");
+                        };
+                        let found = match op {
+                            SeekOp::LE => *cell_rowid <= rowid_key,
+                            SeekOp::LT => *cell_rowid < rowid_key,
+                            _ => unreachable!(),
+                        };
+                        if found {
+                            self.stack.set_cell_index(cell_idx as i32 - 1);
+                            let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
+                            return Ok(CursorResult::Ok((Some(*cell_rowid), Some(record))));
+                        }
+                    }
+                    BTreeCell::IndexLeafCell(IndexLeafCell { payload, .. }) => {
+                        let ordering = self.compare_index_key(payload, &key)?;
+                        let found = match op {
+                            SeekOp::LE => ordering.is_le(),
+                            SeekOp::LT => ordering.is_lt(),
+                            _ => unreachable!(),
+                        };
+                        if found {
+                            let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
+                            self.stack.set_cell_index(cell_idx as i32 - 1);
+                            let rowid = match record.values.last() {
+                                Some(OwnedValue::Integer(rowid)) => *rowid as u64,
+                                _ => unreachable!("index cells should have an integer rowid"),
+                            };
+                            return Ok(CursorResult::Ok((Some(rowid), Some(record))));
+                        }
+                    }
+                    cell_type => {
+                        unreachable!("unexpected cell type: {:?}", cell_type);
+                    }
+                }
+            }
+        }
+
+        // Every entry in the leaf is past the bound, so the answer is the entry right
+        // before the leaf: the last one of the previous leaf or, in an index, a parent cell.
+        self.stack.set_cell_index(-1);
+        self.get_prev_record()
+    }
+
+    /// Moves to the entry after the current one in `direction` when the cursor
+    /// last moved the other way, by seeking past the current entry.
+    fn reverse_direction(
+        &mut self,
+        direction: IterationDirection,
+    ) -> Result<CursorResult<(Option<u64>, Option<OwnedRecord>)>> {
+        let op = match direction {
+            IterationDirection::Forwards => SeekOp::GT,
+            IterationDirection::Backwards => SeekOp::LT,
+        };
+        let is_index = {
+            let page = self.stack.top();
+            let page = RefCell::borrow(&page);
+            page.contents.as_ref().is_some_and(|contents| {
+                matches!(
+                    contents.page_type(),
+                    PageType::IndexInterior | PageType::IndexLeaf
+                )
+            })
+        };
+        let record = self.record.borrow().clone();
+        let rowid = *self.rowid.borrow();
+        let entry = match (record, rowid) {
+            (Some(record), _) if is_index => {
+                return_if_io!(self.seek(SeekKey::IndexKey(&record), op))
+            }
+            (_, Some(rowid)) if !is_index => {
+                return_if_io!(self.seek(SeekKey::TableRowId(rowid), op))
+            }
+            // there is no current entry to move on from
+            _ => (None, None),
+        };
+        self.direction = direction;
+        Ok(CursorResult::Ok(entry))
+    }
+
+    /// Counts the entries of the b-tree by visiting every page, without moving the
+    /// cursor. If a page has to be loaded the count starts over on the next call.
+    fn count_entries(&mut self) -> Result<CursorResult<u64>> {
+        let mut count = 0;
+        let mut pages = vec![self.root_page];
+        while let Some(page_idx) = pages.pop() {
+            let page = self.pager.read_page(page_idx)?;
+            let page = RefCell::borrow(&page);
+            return_if_locked!(page);
+
+            let contents = page.contents.as_ref().unwrap();
+            match contents.page_type() {
+                PageType::TableLeaf | PageType::IndexLeaf => {
+                    count += contents.cell_count() as u64;
+                    continue;
+                }
+                PageType::IndexInterior => {
+                    // index interior cells hold entries too
+                    count += contents.cell_count() as u64;
+                }
+                PageType::TableInterior => {}
+            }
+            for cell_idx in 0..contents.cell_count() {
+                match contents.cell_get(
+                    cell_idx,
+                    self.pager.clone(),
+                    self.max_local(contents.page_type()),
+                    self.min_local(contents.page_type()),
+                    self.usable_space(),
+                )? {
+                    BTreeCell::TableInteriorCell(TableInteriorCell {
+                        _left_child_page, ..
+                    }) => pages.push(_left_child_page as usize),
+                    BTreeCell::IndexInteriorCell(IndexInteriorCell {
+                        left_child_page, ..
+                    }) => pages.push(left_child_page as usize),
+                    cell_type => unreachable!("unexpected cell type: {:?}", cell_type),
+                }
+            }
+            pages.push(contents.rightmost_pointer().unwrap() as usize);
+        }
+        Ok(CursorResult::Ok(count))
+    }
+
     fn move_to_root(&mut self) {
         let mem_page = self.pager.read_page(self.root_page).unwrap();
+        self.going_upwards = false;
         self.stack.clear();
         self.stack.push(mem_page);
     }
@@ -571,11 +809,17 @@ impl BTreeCursor {
                             unreachable!("table seek key should be a rowid");
                         };
                         let target_leaf_page_is_in_left_subtree = match cmp {
-                            SeekOp::GT => rowid_key < *_rowid,
-                            SeekOp::GE => rowid_key <= *_rowid,
+                            SeekOp::GT | SeekOp::LE => rowid_key < *_rowid,
+                            SeekOp::GE | SeekOp::LT => rowid_key <= *_rowid,
                             SeekOp::EQ => rowid_key <= *_rowid,
                         };
-                        self.stack.advance();
+                        // Forward scans continue after this cell once the child is done,
+                        // backward scans continue before it.
+                        if !target_leaf_page_is_in_left_subtree
+                            || !matches!(cmp, SeekOp::LE | SeekOp::LT)
+                        {
+                            self.stack.advance();
+                        }
                         if target_leaf_page_is_in_left_subtree {
                             let mem_page = self.pager.read_page(*_left_child_page as usize)?;
                             self.stack.push(mem_page);
@@ -597,14 +841,13 @@ impl BTreeCursor {
                         payload,
                         ..
                     }) => {
-                        let SeekKey::IndexKey(index_key) = key else {
-                            unreachable!("index seek key should be a record");
-                        };
-                        let record = crate::storage::sqlite3_ondisk::read_record(payload)?;
+                        let ordering = self.compare_index_key(payload, &key)?;
                         let target_leaf_page_is_in_the_left_subtree = match cmp {
-                            SeekOp::GT => index_key < &record,
-                            SeekOp::GE => index_key <= &record,
-                            SeekOp::EQ => index_key <= &record,
+                            SeekOp::GT => ordering.is_gt(),
+                            SeekOp::GE => ordering.is_ge(),
+                            SeekOp::EQ => ordering.is_ge(),
+                            SeekOp::LE => ordering.is_gt(),
+                            SeekOp::LT => ordering.is_ge(),
                         };
                         if target_leaf_page_is_in_the_left_subtree {
                             // we don't advance in case of index tree internal nodes because we will visit this node going up
@@ -741,70 +984,63 @@ impl BTreeCursor {
         }
         page.write_u16(pointer_area_pc_by_idx - page.offset, pc);
 
-        // update first byte of content area
-        page.write_u16(BTREE_HEADER_OFFSET_CELL_CONTENT, pc);
-
         // update cell count
         let new_n_cells = (page.cell_count() + 1) as u16;
         page.write_u16(BTREE_HEADER_OFFSET_CELL_COUNT, new_n_cells);
     }
 
+    /// Frees `len` bytes at `offset`, merging them with the freeblocks and
+    /// fragmented bytes around them, or with the content area they start.
     fn free_cell_range(&self, page: &mut PageContent, offset: u16, len: u16) {
-        if page.first_freeblock() == 0 {
-            // insert into empty list
-            page.write_u16(offset as usize, 0);
-            page.write_u16(offset as usize + 2, len);
-            page.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, offset);
-            return;
+        let maxpc = self.usable_space() as u16;
+        let mut start = offset;
+        let mut end = offset + len;
+
+        // find the freeblocks around the range, the list is kept sorted by offset
+        let mut prev = None;
+        let mut next = page.first_freeblock();
+        while next != 0 && next < maxpc && next < start {
+            prev = Some(next);
+            next = page.read_u16(next as usize);
         }
-        let first_block = page.first_freeblock();
-
-        if offset < first_block {
-            // insert into head of list
-            page.write_u16(offset as usize, first_block);
-            page.write_u16(offset as usize + 2, len);
-            page.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, offset);
-            return;
+        if next >= maxpc {
+            // a pointer past the end of the page ends the list
+            next = 0;
         }
 
-        if offset <= page.cell_content_area() {
-            // extend boundary of content area
-            page.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, page.first_freeblock());
-            page.write_u16(BTREE_HEADER_OFFSET_CELL_CONTENT, offset + len);
-            return;
+        // gaps of less than 4 bytes to a neighbour are fragmented bytes that
+        // become part of the merged block
+        let mut fragmented = 0;
+        if next != 0 && end + 3 >= next {
+            fragmented += next - end;
+            end = next + page.read_u16(next as usize + 2);
+            next = page.read_u16(next as usize);
         }
-
-        let maxpc = {
-            let db_header = self.database_header.borrow();
-            let usable_space = (db_header.page_size - db_header.unused_space as u16) as usize;
-            usable_space as u16
+        let merged_with_prev = match prev {
+            Some(prev) if prev + page.read_u16(prev as usize + 2) + 3 >= start => {
+                fragmented += start - (prev + page.read_u16(prev as usize + 2));
+                start = prev;
+                true
+            }
+            _ => false,
         };
+        let remaining = page.num_frag_free_bytes().saturating_sub(fragmented as u8);
+        page.write_u8(BTREE_HEADER_OFFSET_FRAGMENTED, remaining);
 
-        let mut pc = first_block;
-        let mut prev = first_block;
-
-        while pc <= maxpc && pc < offset {
-            let next = page.read_u16(pc as usize);
-            prev = pc;
-            pc = next;
+        if start <= page.cell_content_area() {
+            // extend boundary of content area
+            page.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, next);
+            page.write_u16(BTREE_HEADER_OFFSET_CELL_CONTENT, end);
+            return;
         }
-
-        if pc >= maxpc {
-            // insert into tail
-            let offset = offset as usize;
-            let prev = prev as usize;
-            page.write_u16(prev, offset as u16);
-            page.write_u16(offset, 0);
-            page.write_u16(offset + 2, len);
-        } else {
-            // insert in between
-            let next = page.read_u16(pc as usize);
-            let offset = offset as usize;
-            let prev = prev as usize;
-            page.write_u16(prev, offset as u16);
-            page.write_u16(offset, next);
-            page.write_u16(offset + 2, len);
+        if !merged_with_prev {
+            match prev {
+                Some(prev) => page.write_u16(prev as usize, start),
+                None => page.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, start),
+            }
         }
+        page.write_u16(start as usize, next);
+        page.write_u16(start as usize + 2, end - start);
     }
 
     fn drop_cell(&self, page: &mut PageContent, cell_idx: usize) {
@@ -815,9 +1051,248 @@ impl BTreeCursor {
             self.usable_space(),
         );
         self.free_cell_range(page, cell_start as u16, cell_len as u16);
+
+        // close the gap in the cell pointer array
+        let (pointer_area_pc_by_idx, pointer_area_len) = page.cell_get_raw_pointer_region();
+        let pointer_pc = pointer_area_pc_by_idx + (2 * cell_idx);
+        page.as_ptr().copy_within(
+            pointer_pc + 2..pointer_area_pc_by_idx + pointer_area_len,
+            pointer_pc,
+        );
         page.write_u16(BTREE_HEADER_OFFSET_CELL_COUNT, page.cell_count() as u16 - 1);
     }
 
+    /// Finds the cell of the current row in the leaf page `contents`: by rowid in
+    /// tables and by the cursor's cell index in indexes. Returns `None` if the
+    /// row is not in the page.
+    fn find_current_cell(&self, contents: &PageContent, rowid: u64) -> Result<Option<usize>> {
+        match contents.page_type() {
+            PageType::TableLeaf => {
+                let cell_idx = self.find_cell(contents, rowid);
+                let found = cell_idx < contents.cell_count()
+                    && matches!(
+                        contents.cell_get(
+                            cell_idx,
+                            self.pager.clone(),
+                            self.max_local(contents.page_type()),
+                            self.min_local(contents.page_type()),
+                            self.usable_space(),
+                        )?,
+                        BTreeCell::TableLeafCell(TableLeafCell { _rowid, .. }) if _rowid == rowid
+                    );
+                Ok(found.then_some(cell_idx))
+            }
+            PageType::IndexLeaf => {
+                // reading a row moves the cell index past it
+                let cell_idx = match self.direction {
+                    IterationDirection::Forwards => self.stack.current_index() - 1,
+                    IterationDirection::Backwards => self.stack.current_index() + 1,
+                };
+                Ok((0..contents.cell_count() as i32)
+                    .contains(&cell_idx)
+                    .then_some(cell_idx as usize))
+            }
+            PageType::TableInterior | PageType::IndexInterior => {
+                unreachable!("rows are only read from leaf pages")
+            }
+        }
+    }
+
+    /// Removes the cell holding the current row from the leaf page on top of the
+    /// stack and leaves the cell index on the cell that follows it in the
+    /// direction the cursor moves in.
+    ///
+    /// Pages aren't rebalanced after the removal and the overflow pages of the
+    /// removed cell, if any, aren't freed.
+    fn delete_current(&mut self) -> Result<CursorResult<()>> {
+        let Some(rowid) = *self.rowid.borrow() else {
+            return Err(LimboError::InternalError(
+                "delete: cursor is not positioned on a row".to_string(),
+            ));
+        };
+        let page_ref = self.stack.top();
+        let mut page = page_ref.borrow_mut();
+        return_if_locked!(page);
+
+        let contents = page.contents.as_ref().unwrap();
+        if !contents.is_leaf() {
+            return Err(LimboError::InternalError(
+                "delete: removing interior cells is not supported".to_string(),
+            ));
+        }
+        let Some(cell_idx) = self.find_current_cell(contents, rowid)? else {
+            return Err(LimboError::Corrupt(format!(
+                "delete: current row not found in page {}",
+                page.id
+            )));
+        };
+
+        page.set_dirty();
+        self.pager.add_dirty(page.id);
+        self.drop_cell(page.contents.as_mut().unwrap(), cell_idx);
+        self.stack.set_cell_index(match self.direction {
+            IterationDirection::Forwards => cell_idx as i32,
+            IterationDirection::Backwards => cell_idx as i32 - 1,
+        });
+        self.rowid.replace(None);
+        self.record.replace(None);
+        Ok(CursorResult::Ok(()))
+    }
+
+    /// Inserts `rows` in order. When their keys are strictly increasing, a row
+    /// whose key belongs in the leaf the previous row went into is inserted there
+    /// directly instead of descending from the root again.
+    fn insert_batch_rows(
+        &mut self,
+        rows: &[(OwnedValue, OwnedRecord)],
+    ) -> Result<CursorResult<()>> {
+        let result = self.insert_remaining_batch_rows(rows);
+        // only a batch waiting for I/O resumes, the next call after a failure
+        // starts a new one
+        if !matches!(result, Ok(CursorResult::IO(_))) {
+            self.write_info.batch_idx = 0;
+        }
+        result
+    }
+
+    /// Inserts the rows of `rows` from `write_info.batch_idx` on.
+    fn insert_remaining_batch_rows(
+        &mut self,
+        rows: &[(OwnedValue, OwnedRecord)],
+    ) -> Result<CursorResult<()>> {
+        let ascending = rows.windows(2).all(|pair| match (&pair[0].0, &pair[1].0) {
+            (OwnedValue::Integer(prev), OwnedValue::Integer(next)) => prev < next,
+            _ => unreachable!("btree tables are indexed by integers!"),
+        });
+        while self.write_info.batch_idx < rows.len() {
+            let (key, record) = &rows[self.write_info.batch_idx];
+            let OwnedValue::Integer(int_key) = key else {
+                unreachable!("btree tables are indexed by integers!");
+            };
+            // an insert that yielded while rebalancing resumes where it stopped
+            let moved_before = !matches!(self.write_info.state, WriteState::Start)
+                || (ascending
+                    && self.write_info.batch_idx > 0
+                    && return_if_io!(self.leaf_holds_key(*int_key as u64)));
+            return_if_io!(self.insert(key, record, moved_before));
+            self.write_info.batch_idx += 1;
+        }
+        Ok(CursorResult::Ok(()))
+    }
+
+    /// Whether rowid `key` belongs in the table leaf on top of the stack: it
+    /// falls between two rows of the leaf, or after all of them and the leaf is
+    /// the rightmost one of the tree.
+    fn leaf_holds_key(&self, key: u64) -> Result<CursorResult<bool>> {
+        let page_ref = self.stack.top();
+        let page = RefCell::borrow(&page_ref);
+        return_if_locked!(page);
+
+        let contents = page.contents.as_ref().unwrap();
+        if !matches!(contents.page_type(), PageType::TableLeaf) {
+            return Ok(CursorResult::Ok(false));
+        }
+        let cell_idx = self.find_cell(contents, key);
+        let holds = if cell_idx == contents.cell_count() {
+            self.stack.is_rightmost_path(self.root_page)
+        } else {
+            cell_idx > 0
+        };
+        Ok(CursorResult::Ok(holds))
+    }
+
+    /// Replaces the record of the current table row, keeping its rowid.
+    ///
+    /// When neither the old nor the new record spill to overflow pages and the
+    /// new cell is no larger than the old one, it is written over the old cell
+    /// and the bytes left over are freed. Otherwise the old cell is dropped and
+    /// the new one inserted, which may rebalance the page.
+    fn update_current(&mut self, record: &OwnedRecord) -> Result<CursorResult<()>> {
+        if !matches!(self.write_info.state, WriteState::Start) {
+            // a fallback insert is waiting for I/O while rebalancing
+            let rowid = self.rowid.borrow().unwrap();
+            return_if_io!(self.insert_into_page(&OwnedValue::Integer(rowid as i64), record));
+            self.record.replace(Some(record.clone()));
+            return Ok(CursorResult::Ok(()));
+        }
+        let Some(rowid) = *self.rowid.borrow() else {
+            return Err(LimboError::InternalError(
+                "update: cursor is not positioned on a row".to_string(),
+            ));
+        };
+        let page_ref = self.stack.top();
+        {
+            let mut page = page_ref.borrow_mut();
+            return_if_locked!(page);
+
+            let contents = page.contents.as_ref().unwrap();
+            if !matches!(contents.page_type(), PageType::TableLeaf) {
+                return Err(LimboError::InternalError(
+                    "update: only table rows can be updated".to_string(),
+                ));
+            }
+            let Some(cell_idx) = self.find_current_cell(contents, rowid)? else {
+                return Err(LimboError::Corrupt(format!(
+                    "update: current row not found in page {}",
+                    page.id
+                )));
+            };
+            let old_overflows = matches!(
+                contents.cell_get(
+                    cell_idx,
+                    self.pager.clone(),
+                    self.max_local(PageType::TableLeaf),
+                    self.min_local(PageType::TableLeaf),
+                    self.usable_space(),
+                )?,
+                BTreeCell::TableLeafCell(TableLeafCell {
+                    first_overflow_page: Some(_),
+                    ..
+                })
+            );
+            let (cell_start, cell_len) = contents.cell_get_raw_region(
+                cell_idx,
+                self.max_local(PageType::TableLeaf),
+                self.min_local(PageType::TableLeaf),
+                self.usable_space(),
+            );
+
+            let record_size = record.serialized_size();
+            let mut cell = Vec::with_capacity(record_size + 18);
+            write_varint_to_vec(record_size as u64, &mut cell);
+            write_varint_to_vec(rowid, &mut cell);
+            let leftover = cell_len.saturating_sub(cell.len() + record_size);
+            let fits = !old_overflows
+                && record_size <= self.max_local(PageType::TableLeaf)
+                && cell.len() + record_size <= cell_len
+                && (leftover >= 4 || contents.num_frag_free_bytes() as usize + leftover <= 60);
+
+            page.set_dirty();
+            self.pager.add_dirty(page.id);
+            let contents = page.contents.as_mut().unwrap();
+            if fits {
+                record.serialize(&mut cell);
+                contents.as_ptr()[cell_start..cell_start + cell.len()].copy_from_slice(&cell);
+                if leftover >= 4 {
+                    self.free_cell_range(
+                        contents,
+                        (cell_start + cell.len()) as u16,
+                        leftover as u16,
+                    );
+                } else if leftover > 0 {
+                    let fragmented = contents.num_frag_free_bytes() + leftover as u8;
+                    contents.write_u8(BTREE_HEADER_OFFSET_FRAGMENTED, fragmented);
+                }
+                self.record.replace(Some(record.clone()));
+                return Ok(CursorResult::Ok(()));
+            }
+            self.drop_cell(contents, cell_idx);
+        }
+        return_if_io!(self.insert_into_page(&OwnedValue::Integer(rowid as i64), record));
+        self.record.replace(Some(record.clone()));
+        Ok(CursorResult::Ok(()))
+    }
+
     /// This is a naive algorithm that doesn't try to distribute cells evenly by content.
     /// It will try to split the page in half by keys not by content.
     /// Sqlite tries to have a page at least 40% full.
@@ -916,7 +1391,7 @@ impl BTreeCursor {
                 if !loaded {
                     debug!("balance_leaf(loading page)");
                     self.pager.load_page(parent_rc.clone())?;
-                    return Ok(CursorResult::IO);
+                    return Ok(CursorResult::IO(PendingIo::page(parent_rc.borrow().id)));
                 }
                 parent_rc.borrow_mut().set_dirty();
                 self.write_info.state = WriteState::BalanceMoveUp;
@@ -987,6 +1462,8 @@ impl BTreeCursor {
                     contents.write_u16(BTREE_HEADER_OFFSET_CELL_CONTENT, cell_content_area_start);
 
                     contents.write_u8(BTREE_HEADER_OFFSET_FRAGMENTED, 0);
+                    // the overflow cells are in scratch_cells now
+                    contents.overflow_cells.clear();
                     if !contents.is_leaf() {
                         contents.write_u32(BTREE_HEADER_OFFSET_RIGHTMOST, 0);
                     }
@@ -1329,8 +1806,19 @@ impl BTreeCursor {
                                 "error while parsing varint from cell, probably treat this as corruption?"
                             ),
                         };
-                        // TODO: add overflow page calculation
-                        payload_size + nr_payload as u64 + nr_key as u64
+                        // only the local part of an overflowing payload is in the page
+                        let (overflows, to_read) = payload_overflows(
+                            payload_size as usize,
+                            self.max_local(PageType::TableLeaf),
+                            self.min_local(PageType::TableLeaf),
+                            self.usable_space(),
+                        );
+                        let local_size = if overflows {
+                            to_read as u64
+                        } else {
+                            payload_size
+                        };
+                        local_size + nr_payload as u64 + nr_key as u64
                     }
                     PageType::IndexInterior => todo!(),
                     PageType::IndexLeaf => todo!(),
@@ -1437,7 +1925,7 @@ impl BTreeCursor {
             PageType::TableLeaf | PageType::IndexLeaf
         ));
         // TODO: make record raw from start, having to serialize is not good
-        let mut record_buf = vec![];
+        let mut record_buf = Vec::new();
         record.serialize(&mut record_buf);
 
         // fill in header
@@ -1458,7 +1946,6 @@ impl BTreeCursor {
         if record_buf.len() <= max_local {
             // enough allowed space to fit inside a btree page
             cell_payload.extend_from_slice(record_buf.as_slice());
-            cell_payload.resize(cell_payload.len() + 4, 0);
             return;
         }
         log::debug!("fill_cell_payload(overflow)");
@@ -1654,6 +2141,19 @@ impl PageStack {
         *self.current_page.borrow() > 0
     }
 
+    /// Whether the stack goes from `root_page` down through rightmost pointers only.
+    fn is_rightmost_path(&self, root_page: usize) -> bool {
+        let stack = self.stack.borrow();
+        let page_id = |level: usize| stack[level].as_ref().unwrap().borrow().id;
+        page_id(0) == root_page
+            && (0..self.current()).all(|level| {
+                let parent = stack[level].as_ref().unwrap().borrow();
+                let rightmost_pointer =
+                    parent.contents.as_ref().and_then(|c| c.rightmost_pointer());
+                rightmost_pointer == Some(page_id(level + 1) as u32)
+            })
+    }
+
     fn clear(&self) {
         *self.current_page.borrow_mut() = -1;
     }
@@ -1668,26 +2168,39 @@ fn find_free_cell(page_ref: &PageContent, db_header: Ref<DatabaseHeader>, amount
 
     let usable_space = (db_header.page_size - db_header.unused_space as u16) as usize;
     let maxpc = usable_space - amount;
-    let mut found = false;
-    while pc <= maxpc {
+    let mut prev = None;
+    while pc != 0 && pc <= maxpc {
         let next = u16::from_be_bytes(buf[pc..pc + 2].try_into().unwrap());
         let size = u16::from_be_bytes(buf[pc + 2..pc + 4].try_into().unwrap());
         if amount <= size as usize {
-            found = true;
-            break;
+            let leftover = size as usize - amount;
+            if leftover >= 4 {
+                // keep the front of the block free and hand out its end
+                page_ref.write_u16(pc + 2, leftover as u16);
+                return pc + leftover;
+            }
+            if page_ref.num_frag_free_bytes() as usize + leftover > 60 {
+                return 0;
+            }
+            // unlink the block, the bytes left over become fragmented
+            match prev {
+                Some(prev) => page_ref.write_u16(prev, next),
+                None => page_ref.write_u16(BTREE_HEADER_OFFSET_FREEBLOCK, next),
+            }
+            let fragmented = page_ref.num_frag_free_bytes() + leftover as u8;
+            page_ref.write_u8(BTREE_HEADER_OFFSET_FRAGMENTED, fragmented);
+            return pc;
         }
+        prev = Some(pc);
         pc = next as usize;
     }
-    if !found {
-        0
-    } else {
-        pc
-    }
+    0
 }
 
 impl Cursor for BTreeCursor {
     fn seek_to_last(&mut self) -> Result<CursorResult<()>> {
         return_if_io!(self.move_to_rightmost());
+        self.direction = IterationDirection::Forwards;
         let (rowid, record) = return_if_io!(self.get_next_record(None));
         if rowid.is_none() {
             let is_empty = return_if_io!(self.is_empty_table());
@@ -1705,6 +2218,7 @@ impl Cursor for BTreeCursor {
 
     fn rewind(&mut self) -> Result<CursorResult<()>> {
         self.move_to_root();
+        self.direction = IterationDirection::Forwards;
 
         let (rowid, record) = return_if_io!(self.get_next_record(None));
         self.rowid.replace(rowid);
@@ -1713,28 +2227,34 @@ impl Cursor for BTreeCursor {
     }
 
     fn last(&mut self) -> Result<CursorResult<()>> {
-        match self.move_to_rightmost()? {
-            CursorResult::Ok(_) => self.prev(),
-            CursorResult::IO => Ok(CursorResult::IO),
-        }
+        return_if_io!(self.move_to_rightmost());
+        self.direction = IterationDirection::Backwards;
+        self.prev()
     }
 
     fn next(&mut self) -> Result<CursorResult<()>> {
-        let (rowid, record) = return_if_io!(self.get_next_record(None));
+        let (rowid, record) = match self.direction {
+            IterationDirection::Forwards => return_if_io!(self.get_next_record(None)),
+            IterationDirection::Backwards => {
+                return_if_io!(self.reverse_direction(IterationDirection::Forwards))
+            }
+        };
         self.rowid.replace(rowid);
         self.record.replace(record);
         Ok(CursorResult::Ok(()))
     }
 
     fn prev(&mut self) -> Result<CursorResult<()>> {
-        match self.get_prev_record()? {
-            CursorResult::Ok((rowid, record)) => {
-                self.rowid.replace(rowid);
-                self.record.replace(record);
-                Ok(CursorResult::Ok(()))
+        let entry = match self.direction {
+            IterationDirection::Backwards => self.get_prev_record()?,
+            IterationDirection::Forwards => {
+                self.reverse_direction(IterationDirection::Backwards)?
             }
-            CursorResult::IO => Ok(CursorResult::IO),
-        }
+        };
+        Ok(entry.map(|(rowid, record)| {
+            self.rowid.replace(rowid);
+            self.record.replace(record);
+        }))
     }
 
     fn wait_for_completion(&mut self) -> Result<()> {
@@ -1747,7 +2267,12 @@ impl Cursor for BTreeCursor {
     }
 
     fn seek(&mut self, key: SeekKey<'_>, op: SeekOp) -> Result<CursorResult<bool>> {
+        let direction = match op {
+            SeekOp::LE | SeekOp::LT => IterationDirection::Backwards,
+            SeekOp::EQ | SeekOp::GE | SeekOp::GT => IterationDirection::Forwards,
+        };
         let (rowid, record) = return_if_io!(self.seek(key, op));
+        self.direction = direction;
         self.rowid.replace(rowid);
         self.record.replace(record);
         Ok(CursorResult::Ok(rowid.is_some()))
@@ -1775,6 +2300,22 @@ impl Cursor for BTreeCursor {
         Ok(CursorResult::Ok(()))
     }
 
+    fn delete(&mut self) -> Result<CursorResult<()>> {
+        self.delete_current()
+    }
+
+    fn insert_batch(&mut self, rows: &[(OwnedValue, OwnedRecord)]) -> Result<CursorResult<()>> {
+        self.insert_batch_rows(rows)
+    }
+
+    fn update(&mut self, record: &OwnedRecord) -> Result<CursorResult<()>> {
+        self.update_current(record)
+    }
+
+    fn count(&mut self) -> Result<CursorResult<u64>> {
+        self.count_entries()
+    }
+
     fn set_null_flag(&mut self, flag: bool) {
         self.null_flag = flag;
     }
@@ -1802,21 +2343,33 @@ impl Cursor for BTreeCursor {
             _ => unreachable!("btree tables are indexed by integers!"),
         };
         let cell_idx = self.find_cell(contents, int_key);
-        if cell_idx >= contents.cell_count() {
-            Ok(CursorResult::Ok(false))
-        } else {
-            let equals = match &contents.cell_get(
+        let mut found = None;
+        if cell_idx < contents.cell_count() {
+            match &contents.cell_get(
                 cell_idx,
                 self.pager.clone(),
                 self.max_local(contents.page_type()),
                 self.min_local(contents.page_type()),
                 self.usable_space(),
             )? {
-                BTreeCell::TableLeafCell(l) => l._rowid == int_key,
+                BTreeCell::TableLeafCell(l) => {
+                    if l._rowid == int_key {
+                        found = Some(crate::storage::sqlite3_ondisk::read_record(&l._payload)?);
+                    }
+                }
                 _ => unreachable!(),
-            };
-            Ok(CursorResult::Ok(equals))
+            }
+        }
+        let exists = found.is_some();
+        self.direction = IterationDirection::Forwards;
+        if exists {
+            self.stack.set_cell_index(cell_idx as i32 + 1);
+            self.rowid.replace(Some(int_key));
+        } else {
+            self.rowid.replace(None);
         }
+        self.record.replace(found);
+        Ok(CursorResult::Ok(exists))
     }
 
     fn btree_create(&mut self, flags: usize) -> u32 {
@@ -1860,3 +2413,471 @@ pub fn btree_init_page(
 fn to_static_buf(buf: &[u8]) -> &'static [u8] {
     unsafe { std::mem::transmute::<&[u8], &'static [u8]>(buf) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Database, PlatformIO, IO};
+    use std::sync::Arc;
+
+    /// Creates a database by running `sql` with SQLite and opens a cursor on the
+    /// b-tree of the table or index `name`.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn open_btree(sql: &str, name: &str) -> (Arc<dyn IO>, Rc<Database>, BTreeCursor) {
+        let path = tempfile::TempDir::new()
+            .unwrap()
+            .into_path()
+            .join("test.db");
+        let root_page: usize = {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(sql).unwrap();
+            conn.query_row(
+                "SELECT rootpage FROM sqlite_schema WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        let io: Arc<dyn IO> = Arc::new(PlatformIO::new().unwrap());
+        let db = Database::open_file(io.clone(), path.to_str().unwrap()).unwrap();
+        let cursor = BTreeCursor::new(db.pager.clone(), root_page, db.header.clone());
+        (io, db, cursor)
+    }
+
+    /// Runs a cursor operation to completion, running I/O whenever it waits for a page.
+    fn run<T>(io: &Arc<dyn IO>, mut op: impl FnMut() -> Result<CursorResult<T>>) -> T {
+        loop {
+            match op().unwrap() {
+                CursorResult::Ok(value) => return value,
+                CursorResult::IO(_) => io.run_once().unwrap(),
+            }
+        }
+    }
+
+    fn root_page_type(io: &Arc<dyn IO>, cursor: &BTreeCursor) -> PageType {
+        let page = cursor.pager.read_page(cursor.root_page).unwrap();
+        while RefCell::borrow(&page).is_locked() {
+            io.run_once().unwrap();
+        }
+        let page = RefCell::borrow(&page);
+        page.contents.as_ref().unwrap().page_type()
+    }
+
+    /// A table with the even rowids 2 to 2000 and 100 byte rows, spread over
+    /// a few dozen leaves.
+    const EVEN_ROWIDS: &str = "
+        CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+        WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000)
+        INSERT INTO t SELECT 2 * x, printf('%0100d', x) FROM c;
+    ";
+
+    /// An index on 100 byte keys `key(2)`, `key(4)`, .. `key(10000)`, deep
+    /// enough to have interior pages below its root.
+    const EVEN_KEYS: &str = "
+        CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+        CREATE INDEX t_v ON t (v);
+        WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 5000)
+        INSERT INTO t SELECT x, printf('%0100d', 2 * x) FROM c;
+    ";
+
+    fn key(n: u64) -> OwnedRecord {
+        OwnedRecord::new(vec![OwnedValue::Text(Rc::from(format!("{:0100}", n)))])
+    }
+
+    #[test]
+    fn test_count() {
+        let (io, _db, mut cursor) = open_btree(EVEN_ROWIDS, "t");
+        assert!(matches!(
+            root_page_type(&io, &cursor),
+            PageType::TableInterior
+        ));
+        assert_eq!(run(&io, || cursor.count()), 1000);
+
+        // entries of index interior pages count too
+        let (io, _db, mut cursor) = open_btree(EVEN_KEYS, "t_v");
+        assert_eq!(run(&io, || cursor.count()), 5000);
+        let (io, _db, mut cursor) = open_btree(SMALL_TABLE, "t_v");
+        assert_eq!(run(&io, || cursor.count()), 10);
+    }
+
+    #[test]
+    fn test_seek_backward_table() {
+        let (io, _db, mut cursor) = open_btree(EVEN_ROWIDS, "t");
+        assert!(matches!(
+            root_page_type(&io, &cursor),
+            PageType::TableInterior
+        ));
+        let mut seek = |rowid, op: SeekOp| {
+            run(&io, || {
+                Cursor::seek(&mut cursor, SeekKey::TableRowId(rowid), op.clone())
+            })
+            .then(|| cursor.rowid().unwrap().unwrap())
+        };
+        assert_eq!(seek(501, SeekOp::LE), Some(500));
+        assert_eq!(seek(500, SeekOp::LE), Some(500));
+        assert_eq!(seek(500, SeekOp::LT), Some(498));
+        assert_eq!(seek(5000, SeekOp::LT), Some(2000));
+        assert_eq!(seek(2, SeekOp::LE), Some(2));
+        assert_eq!(seek(2, SeekOp::LT), None);
+        assert_eq!(seek(1, SeekOp::LE), None);
+
+        // walking back from an LE seek crosses leaves and interior cells
+        assert!(run(&io, || Cursor::seek(
+            &mut cursor,
+            SeekKey::TableRowId(1001),
+            SeekOp::LE
+        )));
+        let mut expected = 1000;
+        while let Some(rowid) = cursor.rowid().unwrap() {
+            assert_eq!(rowid, expected);
+            expected -= 2;
+            run(&io, || cursor.prev());
+        }
+        assert_eq!(expected, 0);
+    }
+
+    #[test]
+    fn test_seek_backward_index() {
+        let (io, _db, mut cursor) = open_btree(EVEN_KEYS, "t_v");
+        assert!(matches!(
+            root_page_type(&io, &cursor),
+            PageType::IndexInterior
+        ));
+        let mut seek = |n, op: SeekOp| {
+            let key = key(n);
+            run(&io, || {
+                Cursor::seek(&mut cursor, SeekKey::IndexPrefix(&key), op.clone())
+            })
+            .then(|| cursor.rowid().unwrap().unwrap())
+        };
+        assert_eq!(seek(1001, SeekOp::LE), Some(500));
+        assert_eq!(seek(1000, SeekOp::LE), Some(500));
+        assert_eq!(seek(1000, SeekOp::LT), Some(499));
+        assert_eq!(seek(20000, SeekOp::LE), Some(5000));
+        assert_eq!(seek(2, SeekOp::LE), Some(1));
+        assert_eq!(seek(2, SeekOp::LT), None);
+
+        let key = key(7001);
+        assert!(run(&io, || Cursor::seek(
+            &mut cursor,
+            SeekKey::IndexPrefix(&key),
+            SeekOp::LT
+        )));
+        let mut expected = 3500;
+        while let Some(rowid) = cursor.rowid().unwrap() {
+            assert_eq!(rowid, expected);
+            expected -= 1;
+            run(&io, || cursor.prev());
+        }
+        assert_eq!(expected, 0);
+    }
+
+    /// Steps the cursor `steps` times, forwards for `true`, and returns the
+    /// rowids it lands on.
+    fn walk(io: &Arc<dyn IO>, cursor: &mut BTreeCursor, steps: &[bool]) -> Vec<Option<u64>> {
+        steps
+            .iter()
+            .map(|forwards| {
+                if *forwards {
+                    run(io, || cursor.next());
+                } else {
+                    run(io, || cursor.prev());
+                }
+                cursor.rowid().unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_change_direction_table() {
+        let (io, _db, mut cursor) = open_btree(EVEN_ROWIDS, "t");
+        assert!(run(&io, || Cursor::seek(
+            &mut cursor,
+            SeekKey::TableRowId(1000),
+            SeekOp::LE
+        )));
+        assert_eq!(
+            walk(&io, &mut cursor, &[true, true, false, false, false, true]),
+            [
+                Some(1002),
+                Some(1004),
+                Some(1002),
+                Some(1000),
+                Some(998),
+                Some(1000)
+            ]
+        );
+
+        run(&io, || cursor.rewind());
+        assert_eq!(
+            walk(&io, &mut cursor, &[true, false, false]),
+            [Some(4), Some(2), None]
+        );
+
+        run(&io, || cursor.last());
+        assert_eq!(cursor.rowid().unwrap(), Some(2000));
+        assert_eq!(
+            walk(&io, &mut cursor, &[false, true, true]),
+            [Some(1998), Some(2000), None]
+        );
+    }
+
+    #[test]
+    fn test_change_direction_index() {
+        let (io, _db, mut cursor) = open_btree(EVEN_KEYS, "t_v");
+        // walk over a few hundred entries both ways, so the walk turns around
+        // both in leaves and on interior cells
+        let key = key(3000);
+        assert!(run(&io, || Cursor::seek(
+            &mut cursor,
+            SeekKey::IndexPrefix(&key),
+            SeekOp::GE
+        )));
+        let mut expected = 1500;
+        for (forwards, len) in [(true, 300), (false, 500), (true, 250)] {
+            for _ in 0..len {
+                if forwards {
+                    run(&io, || cursor.next());
+                    expected += 1;
+                } else {
+                    run(&io, || cursor.prev());
+                    expected -= 1;
+                }
+                assert_eq!(cursor.rowid().unwrap(), Some(expected));
+            }
+        }
+        assert_eq!(expected, 1550);
+    }
+
+    /// A single leaf table with rowids 1 to 10 and an index on its 20 byte
+    /// values, `small_key(1)` to `small_key(10)`.
+    const SMALL_TABLE: &str = "
+        CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT);
+        CREATE INDEX t_v ON t (v);
+        WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 10)
+        INSERT INTO t SELECT x, printf('%020d', x) FROM c;
+    ";
+
+    fn small_key(n: u64) -> OwnedRecord {
+        OwnedRecord::new(vec![OwnedValue::Text(Rc::from(format!("{:020}", n)))])
+    }
+
+    fn rowids(io: &Arc<dyn IO>, cursor: &mut BTreeCursor) -> Vec<u64> {
+        let mut rowids = vec![];
+        run(io, || cursor.rewind());
+        while let Some(rowid) = cursor.rowid().unwrap() {
+            rowids.push(rowid);
+            run(io, || cursor.next());
+        }
+        rowids
+    }
+
+    /// Checks that the cells and freeblocks of the page on top of the stack lie
+    /// in its cell content area without overlapping, that the freeblock list is
+    /// sorted, and that the bytes between them are counted as fragmented.
+    fn assert_freeblocks_consistent(cursor: &BTreeCursor) {
+        let page = cursor.stack.top();
+        let page = RefCell::borrow(&page);
+        let contents = page.contents.as_ref().unwrap();
+        let page_type = contents.page_type();
+        let usable_space = cursor.usable_space();
+        let mut regions: Vec<(usize, usize)> = (0..contents.cell_count())
+            .map(|idx| {
+                contents.cell_get_raw_region(
+                    idx,
+                    cursor.max_local(page_type.clone()),
+                    cursor.min_local(page_type.clone()),
+                    usable_space,
+                )
+            })
+            .collect();
+        let mut pc = contents.first_freeblock() as usize;
+        let mut prev = 0;
+        while pc != 0 {
+            assert!(pc > prev, "freeblock {} is listed after {}", pc, prev);
+            regions.push((pc, contents.read_u16(pc + 2) as usize));
+            prev = pc;
+            pc = contents.read_u16(pc) as usize;
+        }
+        regions.sort();
+        let mut end = contents.cell_content_area() as usize;
+        let mut gaps = 0;
+        for (start, len) in regions {
+            assert!(
+                start >= end,
+                "region at {} overlaps the one ending at {}",
+                start,
+                end
+            );
+            gaps += start - end;
+            end = start + len;
+        }
+        assert!(end <= usable_space);
+        assert_eq!(gaps, contents.num_frag_free_bytes() as usize);
+    }
+
+    #[test]
+    fn test_delete_table_cells() {
+        let (io, _db, mut cursor) = open_btree(SMALL_TABLE, "t");
+        assert!(matches!(root_page_type(&io, &cursor), PageType::TableLeaf));
+        let mut expected: Vec<u64> = (1..=10).collect();
+        // the middle, first and last cell, with what the cursor moves to next
+        for (rowid, next) in [(5, Some(6)), (1, Some(2)), (10, None)] {
+            assert!(run(&io, || Cursor::seek(
+                &mut cursor,
+                SeekKey::TableRowId(rowid),
+                SeekOp::EQ
+            )));
+            run(&io, || cursor.delete());
+            assert_freeblocks_consistent(&cursor);
+            run(&io, || cursor.next());
+            assert_eq!(cursor.rowid().unwrap(), next);
+            expected.retain(|r| *r != rowid);
+            assert_eq!(rowids(&io, &mut cursor), expected);
+        }
+    }
+
+    #[test]
+    fn test_delete_index_cells() {
+        let (io, _db, mut cursor) = open_btree(SMALL_TABLE, "t_v");
+        assert!(matches!(root_page_type(&io, &cursor), PageType::IndexLeaf));
+        let mut expected: Vec<u64> = (1..=10).collect();
+        // the middle, first and last cell, reached moving forwards and moving
+        // backwards, with what the cursor moves to next in that direction
+        for (n, op, next) in [
+            (5, SeekOp::GE, Some(6)),
+            (6, SeekOp::LE, Some(4)),
+            (1, SeekOp::LE, None),
+            (10, SeekOp::GE, None),
+        ] {
+            let key = small_key(n);
+            let backwards = matches!(op, SeekOp::LE);
+            assert!(run(&io, || Cursor::seek(
+                &mut cursor,
+                SeekKey::IndexPrefix(&key),
+                op.clone()
+            )));
+            assert_eq!(cursor.rowid().unwrap(), Some(n));
+            run(&io, || cursor.delete());
+            assert_freeblocks_consistent(&cursor);
+            if backwards {
+                run(&io, || cursor.prev());
+            } else {
+                run(&io, || cursor.next());
+            }
+            assert_eq!(cursor.rowid().unwrap(), next);
+            expected.retain(|r| *r != n);
+            assert_eq!(rowids(&io, &mut cursor), expected);
+        }
+    }
+
+    fn row(v: &str) -> OwnedRecord {
+        OwnedRecord::new(vec![OwnedValue::Null, OwnedValue::Text(Rc::from(v))])
+    }
+
+    /// Seeks table row `rowid` and returns the offset of its cell.
+    fn seek_cell(io: &Arc<dyn IO>, cursor: &mut BTreeCursor, rowid: u64) -> usize {
+        assert!(run(io, || Cursor::seek(
+            cursor,
+            SeekKey::TableRowId(rowid),
+            SeekOp::EQ
+        )));
+        let page = cursor.stack.top();
+        let page = RefCell::borrow(&page);
+        let contents = page.contents.as_ref().unwrap();
+        let cell_idx = cursor.find_cell(contents, rowid);
+        contents
+            .cell_get_raw_region(
+                cell_idx,
+                cursor.max_local(PageType::TableLeaf),
+                cursor.min_local(PageType::TableLeaf),
+                cursor.usable_space(),
+            )
+            .0
+    }
+
+    #[test]
+    fn test_update_in_place() {
+        let (io, _db, mut cursor) = open_btree(SMALL_TABLE, "t");
+        assert!(matches!(root_page_type(&io, &cursor), PageType::TableLeaf));
+        // leaving a freeblock, then too few bytes for one
+        for (rowid, value) in [(5, "5"), (6, "000000000000000006")] {
+            let cell_start = seek_cell(&io, &mut cursor, rowid);
+            run(&io, || cursor.update(&row(value)));
+            assert_freeblocks_consistent(&cursor);
+            assert_eq!(cursor.record().unwrap().as_ref(), Some(&row(value)));
+            assert_eq!(seek_cell(&io, &mut cursor, rowid), cell_start);
+            assert_eq!(cursor.record().unwrap().as_ref(), Some(&row(value)));
+        }
+        assert_eq!(rowids(&io, &mut cursor), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_update_reinsert() {
+        let (io, _db, mut cursor) = open_btree(SMALL_TABLE, "t");
+        let value = "x".repeat(200);
+        let cell_start = seek_cell(&io, &mut cursor, 5);
+        run(&io, || cursor.update(&row(&value)));
+        assert_freeblocks_consistent(&cursor);
+        assert_ne!(seek_cell(&io, &mut cursor, 5), cell_start);
+        assert_eq!(cursor.record().unwrap().as_ref(), Some(&row(&value)));
+        assert_eq!(rowids(&io, &mut cursor), (1..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_update_reinsert_balances() {
+        let (io, _db, mut cursor) = open_btree(EVEN_ROWIDS, "t");
+        // rows too large for the first leaves to keep them all, small enough
+        // for half of a leaf to always fit in a page when it is split
+        let value = "x".repeat(300);
+        for rowid in (2..=200).step_by(2) {
+            seek_cell(&io, &mut cursor, rowid);
+            run(&io, || cursor.update(&row(&value)));
+            assert_freeblocks_consistent(&cursor);
+        }
+        for rowid in (2..=200).step_by(2) {
+            seek_cell(&io, &mut cursor, rowid);
+            assert_eq!(cursor.record().unwrap().as_ref(), Some(&row(&value)));
+        }
+        assert_eq!(
+            rowids(&io, &mut cursor),
+            (2..=2000).step_by(2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_insert_batch_error_resets_batch() {
+        // leaves with room for the first row
+        let sql = format!("{} DELETE FROM t WHERE id % 4 = 0;", EVEN_ROWIDS);
+        let (io, _db, mut cursor) = open_btree(&sql, "t");
+        assert!(matches!(
+            root_page_type(&io, &cursor),
+            PageType::TableInterior
+        ));
+        {
+            // point the last cell of the root at the last four bytes of the
+            // page, so its rowid is cut off and only seeking past it fails
+            let page = cursor.pager.read_page(cursor.root_page).unwrap();
+            let page = RefCell::borrow(&page);
+            let contents = page.contents.as_ref().unwrap();
+            let (pointers, len) = contents.cell_get_raw_pointer_region();
+            let last = cursor.usable_space() - 4;
+            contents.write_u16(pointers + len - 2 - contents.offset, last as u16);
+        }
+        let record = OwnedRecord::new(vec![OwnedValue::Null, OwnedValue::Integer(0)]);
+        let rows = [
+            (OwnedValue::Integer(1), record.clone()),
+            (OwnedValue::Integer(3001), record.clone()),
+        ];
+        let result = loop {
+            match cursor.insert_batch(&rows) {
+                Ok(CursorResult::IO(_)) => io.run_once().unwrap(),
+                result => break result,
+            }
+        };
+        assert!(matches!(result, Err(LimboError::Corrupt(_))));
+        assert_eq!(cursor.write_info.batch_idx, 0);
+        // the first row went in before the second one failed
+        assert!(run(&io, || cursor.exists(&OwnedValue::Integer(1))));
+    }
+}
@@ -46,7 +46,7 @@ use crate::io::{Buffer, Completion, ReadCompletion, SyncCompletion, WriteComplet
 use crate::storage::buffer_pool::BufferPool;
 use crate::storage::database::DatabaseStorage;
 use crate::storage::pager::{Page, Pager};
-use crate::types::{OwnedRecord, OwnedValue};
+use crate::types::{OwnedRecord, OwnedValue, SortColumn, Value};
 use crate::{File, Result};
 use log::trace;
 use std::cell::RefCell;
@@ -478,10 +478,11 @@ impl PageContent {
                 let (len_payload, n_payload) = read_varint(&buf[cell_pointer + 4..]).unwrap();
                 let (overflows, to_read) =
                     payload_overflows(len_payload as usize, max_local, min_local, usable_size);
+                // `to_read` already counts the overflow page pointer
                 if overflows {
-                    4 + to_read + n_payload + 4
+                    4 + to_read + n_payload
                 } else {
-                    4 + len_payload as usize + n_payload + 4
+                    4 + len_payload as usize + n_payload
                 }
             }
             PageType::TableInterior => {
@@ -493,9 +494,9 @@ impl PageContent {
                 let (overflows, to_read) =
                     payload_overflows(len_payload as usize, max_local, min_local, usable_size);
                 if overflows {
-                    to_read + n_payload + 4
+                    to_read + n_payload
                 } else {
-                    len_payload as usize + n_payload + 4
+                    len_payload as usize + n_payload
                 }
             }
             PageType::TableLeaf => {
@@ -824,21 +825,21 @@ impl TryFrom<u64> for SerialType {
     }
 }
 
+/// Decodes a record payload. A header that doesn't fit in the payload, an
+/// invalid serial type or a value running past the end of the payload is a
+/// `LimboError::Corrupt` error.
 pub fn read_record(payload: &[u8]) -> Result<OwnedRecord> {
+    let (header_size, nr) = read_record_header_size(payload)?;
+    let header = &payload[nr..header_size];
     let mut pos = 0;
-    let (header_size, nr) = read_varint(payload)?;
-    assert!((header_size as usize) >= nr);
-    let mut header_size = (header_size as usize) - nr;
-    pos += nr;
-    let mut serial_types = Vec::with_capacity(header_size);
-    while header_size > 0 {
-        let (serial_type, nr) = read_varint(&payload[pos..])?;
+    let mut serial_types = Vec::with_capacity(header.len());
+    while pos < header.len() {
+        let (serial_type, nr) = read_varint(&header[pos..])?;
         let serial_type = SerialType::try_from(serial_type)?;
         serial_types.push(serial_type);
         pos += nr;
-        assert!(header_size >= nr);
-        header_size -= nr;
     }
+    let mut pos = header_size;
     let mut values = Vec::with_capacity(serial_types.len());
     for serial_type in &serial_types {
         let (value, n) = read_value(&payload[pos..], serial_type)?;
@@ -848,6 +849,77 @@ pub fn read_record(payload: &[u8]) -> Result<OwnedRecord> {
     Ok(OwnedRecord::new(values))
 }
 
+/// Reads the size of a record's header, which counts its own varint, and
+/// returns it with the length of that varint.
+fn read_record_header_size(payload: &[u8]) -> Result<(usize, usize)> {
+    let (header_size, nr) = read_varint(payload)?;
+    if header_size < nr as u64 || header_size > payload.len() as u64 {
+        crate::bail_corrupt_error!(
+            "Invalid record header size {} for a payload of {} bytes",
+            header_size,
+            payload.len()
+        );
+    }
+    Ok((header_size as usize, nr))
+}
+
+/// Compares the record in `payload` with `probe` like
+/// `OwnedRecord::compare_prefix` does after `read_record`, but decodes one
+/// column at a time, borrowing text and blobs from the payload, and stops at
+/// the first column that differs. Seeks use this to probe cells without
+/// allocating.
+pub fn compare_record_prefix(
+    payload: &[u8],
+    probe: &OwnedRecord,
+    key_info: &[SortColumn],
+) -> Result<std::cmp::Ordering> {
+    let (header_size, mut header_pos) = read_record_header_size(payload)?;
+    let mut pos = header_size;
+    for (i, right) in probe.values.iter().enumerate() {
+        if header_pos == header_size {
+            break;
+        }
+        let (serial_type, nr) = read_varint(&payload[header_pos..header_size])?;
+        header_pos += nr;
+        let (left, n) = read_value_ref(&payload[pos..], &SerialType::try_from(serial_type)?)?;
+        pos += n;
+        let ordering = match key_info.get(i) {
+            Some(column) => column.compare_value(&left, right),
+            None => right.partial_cmp(&left).unwrap().reverse(),
+        };
+        if ordering.is_ne() {
+            return Ok(ordering);
+        }
+    }
+    Ok(std::cmp::Ordering::Equal)
+}
+
+/// Like `read_value`, but text and blobs borrow from `buf`. Text that is not
+/// valid UTF-8 is read as a blob, like `OwnedValue::text_from_bytes` does.
+pub fn read_value_ref<'a>(buf: &'a [u8], serial_type: &SerialType) -> Result<(Value<'a>, usize)> {
+    match *serial_type {
+        SerialType::Blob(n) if buf.len() >= n => Ok((Value::Blob(&buf[..n]), n)),
+        SerialType::String(n) if buf.len() >= n => {
+            let bytes = &buf[..n];
+            let value = match std::str::from_utf8(bytes) {
+                Ok(text) => Value::Text(text),
+                Err(_) => Value::Blob(bytes),
+            };
+            Ok((value, n))
+        }
+        _ => {
+            let (value, n) = read_value(buf, serial_type)?;
+            let value = match value {
+                OwnedValue::Null => Value::Null,
+                OwnedValue::Integer(i) => Value::Integer(i),
+                OwnedValue::Float(f) => Value::Float(f),
+                _ => unreachable!("only text and blobs are read from the payload"),
+            };
+            Ok((value, n))
+        }
+    }
+}
+
 pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, usize)> {
     match *serial_type {
         SerialType::Null => Ok((OwnedValue::Null, 0)),
@@ -855,7 +927,7 @@ pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, u
             if buf.is_empty() {
                 crate::bail_corrupt_error!("Invalid UInt8 value");
             }
-            Ok((OwnedValue::Integer(buf[0] as i64), 1))
+            Ok((OwnedValue::Integer(buf[0] as i8 as i64), 1))
         }
         SerialType::BEInt16 => {
             if buf.len() < 2 {
@@ -871,7 +943,8 @@ pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, u
                 crate::bail_corrupt_error!("Invalid BEInt24 value");
             }
             Ok((
-                OwnedValue::Integer(i32::from_be_bytes([0, buf[0], buf[1], buf[2]]) as i64),
+                // sign extend the 24-bit value
+                OwnedValue::Integer((i32::from_be_bytes([buf[0], buf[1], buf[2], 0]) >> 8) as i64),
                 3,
             ))
         }
@@ -889,9 +962,11 @@ pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, u
                 crate::bail_corrupt_error!("Invalid BEInt48 value");
             }
             Ok((
-                OwnedValue::Integer(i64::from_be_bytes([
-                    0, 0, buf[0], buf[1], buf[2], buf[3], buf[4], buf[5],
-                ])),
+                // sign extend the 48-bit value
+                OwnedValue::Integer(
+                    i64::from_be_bytes([buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], 0, 0])
+                        >> 16,
+                ),
                 6,
             ))
         }
@@ -923,7 +998,7 @@ pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, u
             if buf.len() < n {
                 crate::bail_corrupt_error!("Invalid Blob value");
             }
-            Ok((OwnedValue::Blob(buf[0..n].to_vec().into()), n))
+            Ok((OwnedValue::Blob(buf[0..n].into()), n))
         }
         SerialType::String(n) => {
             if buf.len() < n {
@@ -933,9 +1008,7 @@ pub fn read_value(buf: &[u8], serial_type: &SerialType) -> Result<(OwnedValue, u
                     n
                 );
             }
-            let bytes = buf[0..n].to_vec();
-            let value = unsafe { String::from_utf8_unchecked(bytes) };
-            Ok((OwnedValue::Text(value.into()), n))
+            Ok((OwnedValue::text_from_bytes(&buf[0..n]), n))
         }
     }
 }
@@ -955,8 +1028,14 @@ pub fn read_varint(buf: &[u8]) -> Result<(u64, usize)> {
             }
         }
     }
-    v = (v << 8) + buf[8] as u64;
-    Ok((v, 9))
+    match buf.get(8) {
+        // the ninth byte contributes all of its 8 bits
+        Some(&c) => {
+            v = (v << 8) + c as u64;
+            Ok((v, 9))
+        }
+        None => crate::bail_corrupt_error!("Invalid varint"),
+    }
 }
 
 pub fn write_varint(buf: &mut [u8], value: u64) -> usize {
@@ -998,6 +1077,15 @@ pub fn write_varint(buf: &mut [u8], value: u64) -> usize {
     n
 }
 
+/// Returns the number of bytes `write_varint` uses to encode `value`.
+pub fn varint_len(value: u64) -> usize {
+    let mut n = 1;
+    while n < 9 && value >> (7 * n) != 0 {
+        n += 1;
+    }
+    n
+}
+
 pub fn write_varint_to_vec(value: u64, payload: &mut Vec<u8>) {
     let mut varint: Vec<u8> = vec![0; 9];
     let n = write_varint(&mut varint.as_mut_slice()[0..9], value);
@@ -1257,17 +1345,24 @@ mod tests {
 
     #[rstest]
     #[case(&[], SerialType::Null, OwnedValue::Null)]
-    #[case(&[255], SerialType::UInt8, OwnedValue::Integer(255))]
+    #[case(&[127], SerialType::UInt8, OwnedValue::Integer(127))]
+    #[case(&[255], SerialType::UInt8, OwnedValue::Integer(-1))]
     #[case(&[0x12, 0x34], SerialType::BEInt16, OwnedValue::Integer(0x1234))]
     #[case(&[0x12, 0x34, 0x56], SerialType::BEInt24, OwnedValue::Integer(0x123456))]
+    #[case(&[0xFF, 0xFF, 0xFE], SerialType::BEInt24, OwnedValue::Integer(-2))]
     #[case(&[0x12, 0x34, 0x56, 0x78], SerialType::BEInt32, OwnedValue::Integer(0x12345678))]
     #[case(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC], SerialType::BEInt48, OwnedValue::Integer(0x123456789ABC))]
+    #[case(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE], SerialType::BEInt48, OwnedValue::Integer(-2))]
+    #[case(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00], SerialType::BEInt48, OwnedValue::Integer(1 << 40))]
+    #[case(&[0xFF, 0x00, 0x00, 0x00, 0x00, 0x00], SerialType::BEInt48, OwnedValue::Integer(-(1 << 40)))]
+    #[case(&[0x80, 0x00, 0x00, 0x00, 0x00, 0x00], SerialType::BEInt48, OwnedValue::Integer(-(1 << 47)))]
     #[case(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xFF], SerialType::BEInt64, OwnedValue::Integer(0x123456789ABCDEFF))]
     #[case(&[64, 9, 33, 251, 84, 68, 45, 24], SerialType::BEFloat64, OwnedValue::Float(std::f64::consts::PI))]
     #[case(&[], SerialType::ConstInt0, OwnedValue::Integer(0))]
     #[case(&[], SerialType::ConstInt1, OwnedValue::Integer(1))]
     #[case(&[1, 2, 3], SerialType::Blob(3), OwnedValue::Blob(vec![1, 2, 3].into()))]
     #[case(&[65, 66, 67], SerialType::String(3), OwnedValue::Text("ABC".to_string().into()))]
+    #[case(&[192], SerialType::String(1), OwnedValue::Blob(vec![192].into()))] // invalid UTF-8 sequence
     fn test_read_value(
         #[case] buf: &[u8],
         #[case] serial_type: SerialType,
@@ -1287,12 +1382,137 @@ mod tests {
     #[case(&[64, 9, 33, 251, 84, 68, 45], SerialType::BEFloat64)]
     #[case(&[1, 2], SerialType::Blob(3))]
     #[case(&[65, 66], SerialType::String(3))]
-    // TODO: UTF-8 validation is disabled #[case(&[192], SerialType::String(1))] // invalid UTF-8 sequence
     fn test_read_invalid_value(#[case] buf: &[u8], #[case] serial_type: SerialType) {
         let result = read_value(buf, &serial_type);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_compare_record_prefix() {
+        use crate::schema::Order;
+        use crate::types::Collation;
+
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let records = [
+            OwnedRecord::new(vec![
+                OwnedValue::Integer(1),
+                text("abc"),
+                OwnedValue::Integer(7),
+            ]),
+            OwnedRecord::new(vec![
+                OwnedValue::Integer(1),
+                text("ABC"),
+                OwnedValue::Integer(8),
+            ]),
+            OwnedRecord::new(vec![OwnedValue::Float(1.5), text("abc  ")]),
+            OwnedRecord::new(vec![
+                OwnedValue::Null,
+                OwnedValue::Blob(Rc::from(vec![1, 2])),
+            ]),
+            OwnedRecord::new(vec![text("x"), OwnedValue::Integer(-3)]),
+            OwnedRecord::new(vec![OwnedValue::Integer(1)]),
+        ];
+        let key_infos = [
+            vec![],
+            vec![
+                SortColumn {
+                    order: Order::Ascending,
+                    collation: Collation::Binary,
+                },
+                SortColumn {
+                    order: Order::Descending,
+                    collation: Collation::NoCase,
+                },
+            ],
+            vec![
+                SortColumn {
+                    order: Order::Descending,
+                    collation: Collation::Binary,
+                },
+                SortColumn {
+                    order: Order::Ascending,
+                    collation: Collation::RTrim,
+                },
+            ],
+        ];
+        let probes = [
+            OwnedRecord::new(vec![]),
+            OwnedRecord::new(vec![OwnedValue::Integer(1)]),
+            OwnedRecord::new(vec![OwnedValue::Float(1.0), text("abc")]),
+            OwnedRecord::new(vec![
+                OwnedValue::Integer(1),
+                text("abc"),
+                OwnedValue::Integer(8),
+            ]),
+            OwnedRecord::new(vec![OwnedValue::Null, text("y")]),
+        ];
+        for record in &records {
+            let mut payload = Vec::new();
+            record.serialize(&mut payload);
+            for key_info in &key_infos {
+                for probe in &probes {
+                    assert_eq!(
+                        compare_record_prefix(&payload, probe, key_info).unwrap(),
+                        record.compare_prefix(probe, key_info),
+                        "{record:?} vs {probe:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare_record_prefix_corrupt() {
+        let probe = OwnedRecord::new(vec![OwnedValue::Integer(1)]);
+        for payload in [&[][..], &[0x05, 0x01], &[0x02, 0x0a], &[0x02, 0x06, 0x01]] {
+            assert!(matches!(
+                compare_record_prefix(payload, &probe, &[]),
+                Err(LimboError::Corrupt(_))
+            ));
+        }
+    }
+
+    #[rstest]
+    #[case(&[])] // no header size
+    #[case(&[0x00])] // header size smaller than its own varint
+    #[case(&[0x05, 0x01])] // header longer than the payload
+    #[case(&[0xff; 9])] // huge header size
+    #[case(&[0x02, 0x0a])] // reserved serial type
+    #[case(&[0x02, 0x81])] // serial type varint running past the header
+    #[case(&[0x02, 0x06, 0x01, 0x02])] // integer running past the payload
+    #[case(&[0x02, 0x17, b'a'])] // text running past the payload
+    fn test_read_corrupt_record(#[case] payload: &[u8]) {
+        assert!(matches!(read_record(payload), Err(LimboError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_read_truncated_record() {
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Integer(1 << 40),
+            OwnedValue::Text("hello".into()),
+            OwnedValue::Blob(vec![1, 2, 3].into()),
+        ]);
+        let mut payload = Vec::new();
+        record.serialize(&mut payload);
+        assert_eq!(read_record(&payload).unwrap(), record);
+        for len in 0..payload.len() {
+            assert!(read_record(&payload[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_read_garbage_record() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10_000 {
+            let len = rng.gen_range(0..64);
+            let payload: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            // only the absence of a panic matters
+            let _ = read_record(&payload);
+        }
+    }
+
     #[rstest]
     #[case(&[0x01], (1, 1))]
     #[case(&[0x81, 0x01], (129, 2))]
@@ -1303,6 +1523,9 @@ mod tests {
     #[case(&[0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x01], (4432676798593, 7))]
     #[case(&[0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x01], (567382630219905, 8))]
     #[case(&[0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x01], (145249953336295681, 9))]
+    #[case(&[0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xff], (145249953336295935, 9))]
+    #[case(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], (u64::MAX, 9))]
+    #[case(&[0x01, 0xff], (1, 1))]
     fn read_varint_test(#[case] input: &[u8], #[case] expected: (u64, usize)) {
         let result = read_varint(input).unwrap();
         assert_eq!(result, expected);
@@ -1315,6 +1538,28 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_read_truncated_nine_byte_varint() {
+        let buf = [0xff; 8];
+        let result = read_varint(&buf);
+        assert!(result.is_err());
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(0x7f)]
+    #[case(0x80)]
+    #[case(0x3fff)]
+    #[case(0x4000)]
+    #[case(0x00ff_ffff_ffff_ffff)]
+    #[case(0x0100_0000_0000_0000)]
+    #[case(u64::MAX)]
+    fn test_varint_roundtrip(#[case] value: u64) {
+        let mut buf = [0u8; 9];
+        let n = write_varint(&mut buf, value);
+        assert_eq!(read_varint(&buf[..n]).unwrap(), (value, n));
+    }
+
     // **    0x00                      becomes  0x00000000
     // **    0x7f                      becomes  0x0000007f
     // **    0x81 0x00                 becomes  0x00000080
@@ -1337,6 +1582,7 @@ mod tests {
         let mut buf: [u8; 10] = [0; 10];
         let n = write_varint(&mut buf, value.0);
         assert_eq!(n, value.1);
+        assert_eq!(varint_len(value.0), value.1);
         for i in 0..output.len() {
             assert_eq!(buf[i], output[i]);
         }
@@ -694,6 +694,13 @@ pub fn translate_expr(
                         dest: target_register,
                     });
                 }
+                ast::Operator::Concat => {
+                    program.emit_insn(Insn::Concat {
+                        lhs: e1_reg,
+                        rhs: e2_reg,
+                        dest: target_register,
+                    });
+                }
                 other_unimplemented => todo!("{:?}", other_unimplemented),
             }
             Ok(target_register)
@@ -1013,6 +1020,7 @@ pub fn translate_expr(
                         | ScalarFunc::Quote
                         | ScalarFunc::RandomBlob
                         | ScalarFunc::Sign
+                        | ScalarFunc::Trunc
                         | ScalarFunc::ZeroBlob => {
                             let args = if let Some(args) = args {
                                 if args.len() != 1 {
@@ -1231,8 +1239,10 @@ pub fn translate_expr(
                                 );
                             };
 
+                            let mut start_reg = None;
                             for arg in args.iter() {
                                 let reg = program.alloc_register();
+                                start_reg = Some(start_reg.unwrap_or(reg));
                                 translate_expr(
                                     program,
                                     referenced_tables,
@@ -1246,7 +1256,7 @@ pub fn translate_expr(
                             }
                             program.emit_insn(Insn::Function {
                                 constant_mask: 0,
-                                start_reg: target_register + 1,
+                                start_reg: start_reg.unwrap(),
                                 dest: target_register,
                                 func: func_ctx,
                             });
@@ -1359,6 +1369,40 @@ pub fn translate_expr(
 
                             Ok(target_register)
                         }
+                        ScalarFunc::Replace => {
+                            let args = if let Some(args) = args {
+                                if args.len() != 3 {
+                                    crate::bail_parse_error!(
+                                        "{} function must have three arguments",
+                                        srf.to_string()
+                                    );
+                                }
+                                args
+                            } else {
+                                crate::bail_parse_error!(
+                                    "{} function with no arguments",
+                                    srf.to_string()
+                                );
+                            };
+
+                            let start_reg = program.alloc_registers(args.len());
+                            for (i, arg) in args.iter().enumerate() {
+                                translate_expr(
+                                    program,
+                                    referenced_tables,
+                                    arg,
+                                    start_reg + i,
+                                    precomputed_exprs_to_registers,
+                                )?;
+                            }
+                            program.emit_insn(Insn::Function {
+                                constant_mask: 0,
+                                start_reg,
+                                dest: target_register,
+                                func: func_ctx,
+                            });
+                            Ok(target_register)
+                        }
                         ScalarFunc::SqliteVersion => {
                             if args.is_some() {
                                 crate::bail_parse_error!("sqlite_version function with arguments");
@@ -1432,8 +1476,9 @@ pub fn translate_expr(
                 Ok(target_register)
             }
             ast::Literal::String(s) => {
+                // strip the quotes and unescape doubled quotes
                 program.emit_insn(Insn::String8 {
-                    value: s[1..s.len() - 1].to_string(),
+                    value: s[1..s.len() - 1].replace("''", "'"),
                     dest: target_register,
                 });
                 Ok(target_register)
@@ -1541,12 +1586,27 @@ pub fn maybe_apply_affinity(col_type: Type, target_register: usize, program: &mu
     }
 }
 
+/// Only COUNT and GROUP_CONCAT with a single argument support DISTINCT.
+fn check_distinct_aggregate(agg: &Aggregate) -> Result<()> {
+    if !agg.distinct {
+        return Ok(());
+    }
+    if !matches!(agg.func, AggFunc::Count | AggFunc::GroupConcat) {
+        crate::bail_parse_error!("DISTINCT is not supported for {}()", agg.func.to_string());
+    }
+    if agg.args.len() != 1 {
+        crate::bail_parse_error!("DISTINCT aggregates must have exactly one argument");
+    }
+    Ok(())
+}
+
 pub fn translate_aggregation(
     program: &mut ProgramBuilder,
     referenced_tables: &[BTreeTableReference],
     agg: &Aggregate,
     target_register: usize,
 ) -> Result<usize> {
+    check_distinct_aggregate(agg)?;
     let dest = match agg.func {
         AggFunc::Avg => {
             if agg.args.len() != 1 {
@@ -1560,6 +1620,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Avg,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1577,6 +1638,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Count,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1619,6 +1681,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: delimiter_reg,
                 func: AggFunc::GroupConcat,
+                distinct: agg.distinct,
             });
 
             target_register
@@ -1635,6 +1698,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Max,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1650,6 +1714,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Min,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1688,6 +1753,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: delimiter_reg,
                 func: AggFunc::StringAgg,
+                distinct: agg.distinct,
             });
 
             target_register
@@ -1704,6 +1770,7 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Sum,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1719,6 +1786,23 @@ pub fn translate_aggregation(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Total,
+                distinct: agg.distinct,
+            });
+            target_register
+        }
+        AggFunc::Variance(_) => {
+            if agg.args.len() != 1 {
+                crate::bail_parse_error!("{} bad number of arguments", agg.func.to_string());
+            }
+            let expr = &agg.args[0];
+            let expr_reg = program.alloc_register();
+            let _ = translate_expr(program, Some(referenced_tables), expr, expr_reg, None)?;
+            program.emit_insn(Insn::AggStep {
+                acc_reg: target_register,
+                col: expr_reg,
+                delimiter: 0,
+                func: agg.func.clone(),
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1741,6 +1825,7 @@ pub fn translate_aggregation_groupby(
             dest: expr_reg,
         });
     };
+    check_distinct_aggregate(agg)?;
     let dest = match agg.func {
         AggFunc::Avg => {
             if agg.args.len() != 1 {
@@ -1753,6 +1838,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Avg,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1764,6 +1850,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Count,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1805,6 +1892,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: delimiter_reg,
                 func: AggFunc::GroupConcat,
+                distinct: agg.distinct,
             });
 
             target_register
@@ -1820,6 +1908,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Max,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1834,6 +1923,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Min,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1871,6 +1961,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: delimiter_reg,
                 func: AggFunc::StringAgg,
+                distinct: agg.distinct,
             });
 
             target_register
@@ -1886,6 +1977,7 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Sum,
+                distinct: agg.distinct,
             });
             target_register
         }
@@ -1900,6 +1992,22 @@ pub fn translate_aggregation_groupby(
                 col: expr_reg,
                 delimiter: 0,
                 func: AggFunc::Total,
+                distinct: agg.distinct,
+            });
+            target_register
+        }
+        AggFunc::Variance(_) => {
+            if agg.args.len() != 1 {
+                crate::bail_parse_error!("{} bad number of arguments", agg.func.to_string());
+            }
+            let expr_reg = program.alloc_register();
+            emit_column(program, expr_reg);
+            program.emit_insn(Insn::AggStep {
+                acc_reg: target_register,
+                col: expr_reg,
+                delimiter: 0,
+                func: agg.func.clone(),
+                distinct: agg.distinct,
             });
             target_register
         }
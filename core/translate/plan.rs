@@ -161,6 +161,7 @@ impl Display for Direction {
 pub struct Aggregate {
     pub func: AggFunc,
     pub args: Vec<ast::Expr>,
+    pub distinct: bool,
     pub original_expr: ast::Expr,
 }
 
@@ -172,7 +173,11 @@ impl Display for Aggregate {
             .map(|arg| arg.to_string())
             .collect::<Vec<String>>()
             .join(", ");
-        write!(f, "{:?}({})", self.func, args_str)
+        if self.distinct {
+            write!(f, "{:?}(DISTINCT {})", self.func, args_str)
+        } else {
+            write!(f, "{:?}({})", self.func, args_str)
+        }
     }
 }
 
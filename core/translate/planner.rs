@@ -24,7 +24,12 @@ fn resolve_aggregates(expr: &ast::Expr, aggs: &mut Vec<Aggregate>) -> bool {
         return true;
     }
     match expr {
-        ast::Expr::FunctionCall { name, args, .. } => {
+        ast::Expr::FunctionCall {
+            name,
+            distinctness,
+            args,
+            ..
+        } => {
             let args_count = if let Some(args) = &args {
                 args.len()
             } else {
@@ -35,6 +40,7 @@ fn resolve_aggregates(expr: &ast::Expr, aggs: &mut Vec<Aggregate>) -> bool {
                     aggs.push(Aggregate {
                         func: f,
                         args: args.clone().unwrap_or_default(),
+                        distinct: matches!(distinctness, Some(ast::Distinctness::Distinct)),
                         original_expr: expr.clone(),
                     });
                     true
@@ -57,6 +63,7 @@ fn resolve_aggregates(expr: &ast::Expr, aggs: &mut Vec<Aggregate>) -> bool {
                 aggs.push(Aggregate {
                     func: f,
                     args: vec![],
+                    distinct: false,
                     original_expr: expr.clone(),
                 });
                 true
@@ -323,7 +330,7 @@ pub fn prepare_select_plan<'a>(schema: &Schema, select: ast::Select) -> Result<P
                         match &expr {
                             ast::Expr::FunctionCall {
                                 name,
-                                distinctness: _,
+                                distinctness,
                                 args,
                                 filter_over: _,
                                 order_by: _,
@@ -341,6 +348,10 @@ pub fn prepare_select_plan<'a>(schema: &Schema, select: ast::Select) -> Result<P
                                         let agg = Aggregate {
                                             func: f,
                                             args: args.as_ref().unwrap().clone(),
+                                            distinct: matches!(
+                                                distinctness,
+                                                Some(ast::Distinctness::Distinct)
+                                            ),
                                             original_expr: expr.clone(),
                                         };
                                         aggregate_expressions.push(agg.clone());
@@ -373,6 +384,7 @@ pub fn prepare_select_plan<'a>(schema: &Schema, select: ast::Select) -> Result<P
                                         args: vec![ast::Expr::Literal(ast::Literal::Numeric(
                                             "1".to_string(),
                                         ))],
+                                        distinct: false,
                                         original_expr: expr.clone(),
                                     };
                                     aggregate_expressions.push(agg.clone());
@@ -1,14 +1,27 @@
-use std::{cell::Ref, fmt::Display, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::Ref,
+    collections::HashSet,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
-use crate::{error::LimboError, storage::sqlite3_ondisk::write_varint, Result};
+use crate::{
+    error::LimboError,
+    schema::Order,
+    storage::sqlite3_ondisk::{read_record, read_value, varint_len, write_varint, SerialType},
+    Result,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value<'a> {
     Null,
     Integer(i64),
     Float(f64),
-    Text(&'a String),
-    Blob(&'a Vec<u8>),
+    Text(&'a str),
+    Blob(&'a [u8]),
+    Record(&'a OwnedRecord),
 }
 
 impl<'a> Display for Value<'a> {
@@ -16,20 +29,51 @@ impl<'a> Display for Value<'a> {
         match self {
             Self::Null => write!(f, "NULL"),
             Self::Integer(i) => write!(f, "{}", i),
-            Self::Float(fl) => write!(f, "{}", fl),
+            Self::Float(fl) => write!(f, "{}", format_float(*fl)),
             Self::Text(s) => write!(f, "{}", s),
-            Self::Blob(b) => write!(f, "{:?}", b),
+            Self::Blob(b) => write!(f, "X'{}'", hex::encode_upper(b)),
+            Self::Record(r) => write!(f, "{:?}", r),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Value<'_> {
+    /// The datatype name SQLite's `typeof()` returns for this value. Records
+    /// are reported as blobs, which is how they are stored.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "real",
+            Self::Text(_) => "text",
+            Self::Blob(_) | Self::Record(_) => "blob",
+        }
+    }
+
+    /// The number of bytes the value's content takes: 0 for NULL, 8 for numbers,
+    /// the length of text and blobs and the serialized size of records.
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Self::Null => 0,
+            Self::Integer(_) | Self::Float(_) => 8,
+            Self::Text(t) => t.len(),
+            Self::Blob(b) => b.len(),
+            Self::Record(r) => r.serialized_size(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub enum OwnedValue {
     Null,
     Integer(i64),
     Float(f64),
-    Text(Rc<String>),
-    Blob(Rc<Vec<u8>>),
+    Text(Rc<str>),
+    Blob(Rc<[u8]>),
+    /// A blob of this many zero bytes, as returned by `zeroblob(N)`. The bytes
+    /// are only allocated when the value is read as a `Value`; records write
+    /// them without materializing the blob.
+    Zeroblob(usize),
     Agg(Box<AggContext>), // TODO(pere): make this without Box. Currently this might cause cache miss but let's leave it for future analysis
     Record(OwnedRecord),
 }
@@ -39,398 +83,5825 @@ impl Display for OwnedValue {
         match self {
             Self::Null => write!(f, "NULL"),
             Self::Integer(i) => write!(f, "{}", i),
-            Self::Float(fl) => write!(f, "{:?}", fl),
+            Self::Float(fl) => write!(f, "{}", format_float(*fl)),
             Self::Text(s) => write!(f, "{}", s),
-            Self::Blob(b) => write!(f, "{}", String::from_utf8_lossy(b)),
+            Self::Blob(b) => write!(f, "X'{}'", hex::encode_upper(b)),
+            Self::Zeroblob(len) => write!(f, "X'{}'", "00".repeat(*len)),
             Self::Agg(a) => match a.as_ref() {
-                AggContext::Avg(acc, _count) => write!(f, "{}", acc),
+                AggContext::Avg(..) => write!(f, "{}", a.final_value()),
                 AggContext::Sum(acc) => write!(f, "{}", acc),
-                AggContext::Count(count) => write!(f, "{}", count),
+                AggContext::Total(acc) => write!(f, "{}", acc),
+                AggContext::Count(count) | AggContext::CountDistinct(count, _) => {
+                    write!(f, "{}", count)
+                }
                 AggContext::Max(max) => write!(f, "{}", max.as_ref().unwrap_or(&Self::Null)),
                 AggContext::Min(min) => write!(f, "{}", min.as_ref().unwrap_or(&Self::Null)),
-                AggContext::GroupConcat(s) => write!(f, "{}", s),
+                AggContext::GroupConcat { .. }
+                | AggContext::GroupConcatDistinct { .. }
+                | AggContext::Variance { .. } => write!(f, "{}", a.final_value()),
             },
             Self::Record(r) => write!(f, "{:?}", r),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum AggContext {
-    Avg(OwnedValue, OwnedValue), // acc and count
-    Sum(OwnedValue),
-    Count(OwnedValue),
-    Max(Option<OwnedValue>),
-    Min(Option<OwnedValue>),
-    GroupConcat(OwnedValue),
+/// How many characters of text or bytes of a blob `Debug` shows before
+/// eliding the rest.
+const DEBUG_PREVIEW_LEN: usize = 16;
+
+/// Like a derived `Debug`, except that long text and blobs are cut down to
+/// their length and a short preview, e.g. `Blob(4096 bytes: [de, ad, ...])`,
+/// so dumping records doesn't flood the logs. `{:#?}` prints them in full.
+impl std::fmt::Debug for OwnedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "Null"),
+            Self::Integer(i) => f.debug_tuple("Integer").field(i).finish(),
+            Self::Float(fl) => f.debug_tuple("Float").field(fl).finish(),
+            Self::Text(s) if !f.alternate() && s.chars().count() > DEBUG_PREVIEW_LEN => {
+                let preview: String = s.chars().take(DEBUG_PREVIEW_LEN).collect();
+                write!(f, "Text({} bytes: {:?}...)", s.len(), preview)
+            }
+            Self::Text(s) => f.debug_tuple("Text").field(s).finish(),
+            Self::Blob(b) if !f.alternate() && b.len() > DEBUG_PREVIEW_LEN => {
+                write!(f, "Blob({} bytes: [", b.len())?;
+                for byte in &b[..DEBUG_PREVIEW_LEN] {
+                    write!(f, "{:02x}, ", byte)?;
+                }
+                write!(f, "...])")
+            }
+            Self::Blob(b) => f.debug_tuple("Blob").field(b).finish(),
+            Self::Zeroblob(len) => f.debug_tuple("Zeroblob").field(len).finish(),
+            Self::Agg(a) => f.debug_tuple("Agg").field(a).finish(),
+            Self::Record(r) => f.debug_tuple("Record").field(r).finish(),
+        }
+    }
 }
 
-const NULL: OwnedValue = OwnedValue::Null;
+/// Column type affinities, see https://www.sqlite.org/datatype3.html#type_affinity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
 
-impl AggContext {
-    pub fn final_value(&self) -> &OwnedValue {
-        match self {
-            Self::Avg(acc, _count) => acc,
-            Self::Sum(acc) => acc,
-            Self::Count(count) => count,
-            Self::Max(max) => max.as_ref().unwrap_or(&NULL),
-            Self::Min(min) => min.as_ref().unwrap_or(&NULL),
-            Self::GroupConcat(s) => s,
+impl Affinity {
+    /// The affinity applied to the operands of a comparison between operands
+    /// with affinities `lhs` and `rhs`, `None` meaning the operand has none,
+    /// see https://www.sqlite.org/datatype3.html#type_conversions_prior_to_comparison.
+    /// `Blob` means neither operand is converted.
+    pub fn for_comparison(lhs: Option<Affinity>, rhs: Option<Affinity>) -> Affinity {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) if lhs.is_numeric() || rhs.is_numeric() => Affinity::Numeric,
+            (Some(_), Some(_)) | (None, None) => Affinity::Blob,
+            (Some(affinity), None) | (None, Some(affinity)) => affinity,
         }
     }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Integer | Self::Real | Self::Numeric)
+    }
 }
 
-#[allow(clippy::non_canonical_partial_ord_impl)]
-impl PartialOrd<OwnedValue> for OwnedValue {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (Self::Integer(int_left), Self::Integer(int_right)) => int_left.partial_cmp(int_right),
-            (Self::Integer(int_left), Self::Float(float_right)) => {
-                (*int_left as f64).partial_cmp(float_right)
-            }
-            (Self::Float(float_left), Self::Integer(int_right)) => {
-                float_left.partial_cmp(&(*int_right as f64))
-            }
-            (Self::Float(float_left), Self::Float(float_right)) => {
-                float_left.partial_cmp(float_right)
-            }
-            // Numeric vs Text/Blob
-            (Self::Integer(_) | Self::Float(_), Self::Text(_) | Self::Blob(_)) => {
-                Some(std::cmp::Ordering::Less)
-            }
-            (Self::Text(_) | Self::Blob(_), Self::Integer(_) | Self::Float(_)) => {
-                Some(std::cmp::Ordering::Greater)
-            }
+/// Target type of a `CAST(expr AS type)` expression, determined by the affinity
+/// of the type name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastType {
+    Integer,
+    Real,
+    Text,
+    Blob,
+    Numeric,
+}
 
-            (Self::Text(text_left), Self::Text(text_right)) => text_left.partial_cmp(text_right),
-            // Text vs Blob
-            (Self::Text(_), Self::Blob(_)) => Some(std::cmp::Ordering::Less),
-            (Self::Blob(_), Self::Text(_)) => Some(std::cmp::Ordering::Greater),
+/// Which ends of a string `trim()`, `ltrim()` and `rtrim()` remove characters from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimMode {
+    Both,
+    Leading,
+    Trailing,
+}
 
-            (Self::Blob(blob_left), Self::Blob(blob_right)) => blob_left.partial_cmp(blob_right),
-            (Self::Null, Self::Null) => Some(std::cmp::Ordering::Equal),
-            (Self::Null, _) => Some(std::cmp::Ordering::Less),
-            (_, Self::Null) => Some(std::cmp::Ordering::Greater),
-            (Self::Agg(a), Self::Agg(b)) => a.partial_cmp(b),
-            (Self::Agg(a), other) => a.final_value().partial_cmp(other),
-            (other, Self::Agg(b)) => other.partial_cmp(b.final_value()),
-            other => todo!("{:?}", other),
+impl From<Affinity> for CastType {
+    fn from(affinity: Affinity) -> Self {
+        match affinity {
+            Affinity::Integer => Self::Integer,
+            Affinity::Real => Self::Real,
+            Affinity::Text => Self::Text,
+            Affinity::Blob => Self::Blob,
+            Affinity::Numeric => Self::Numeric,
         }
     }
 }
 
-impl std::cmp::PartialOrd<AggContext> for AggContext {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (Self::Avg(a, _), Self::Avg(b, _))
-            | (Self::Sum(a), Self::Sum(b))
-            | (Self::Count(a), Self::Count(b))
-            | (Self::GroupConcat(a), Self::GroupConcat(b)) => a.partial_cmp(b),
-            (Self::Max(a), Self::Max(b)) | (Self::Min(a), Self::Min(b)) => a.partial_cmp(b),
+/// Text collating sequences supported by SQLite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Collation {
+    /// Compares bytes with `memcmp()`.
+    #[default]
+    Binary,
+    /// Like `Binary`, but ASCII upper case letters are folded to lower case.
+    NoCase,
+    /// Like `Binary`, but trailing spaces are ignored.
+    RTrim,
+}
+
+impl Collation {
+    /// Looks up one of the built-in collations by its case-insensitive name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "BINARY" => Some(Self::Binary),
+            "NOCASE" => Some(Self::NoCase),
+            "RTRIM" => Some(Self::RTrim),
             _ => None,
         }
     }
+
+    pub fn compare(&self, left: &str, right: &str) -> std::cmp::Ordering {
+        match self {
+            Self::Binary => left.cmp(right),
+            Self::NoCase => left
+                .bytes()
+                .map(|b| b.to_ascii_lowercase())
+                .cmp(right.bytes().map(|b| b.to_ascii_lowercase())),
+            Self::RTrim => left.trim_end_matches(' ').cmp(right.trim_end_matches(' ')),
+        }
+    }
 }
 
-impl std::cmp::Eq for OwnedValue {}
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggContext {
+    Avg(OwnedValue, OwnedValue), // acc and count
+    Sum(OwnedValue),
+    /// Like `Sum`, but the result is always a float and is 0.0 for an empty set.
+    Total(OwnedValue),
+    Count(OwnedValue),
+    Max(Option<OwnedValue>),
+    Min(Option<OwnedValue>),
+    /// The accumulated text, `None` until a non-NULL value is stepped, and
+    /// the separator placed before the next value.
+    GroupConcat {
+        acc: Option<String>,
+        sep: String,
+    },
+    /// COUNT(DISTINCT ..): the count and the non-NULL values seen so far.
+    CountDistinct(OwnedValue, HashSet<OwnedValue>),
+    /// GROUP_CONCAT(DISTINCT ..): like `GroupConcat`, plus the values seen so far.
+    GroupConcatDistinct {
+        acc: Option<String>,
+        sep: String,
+        seen: HashSet<OwnedValue>,
+    },
+    /// Running state of Welford's algorithm: the number of values, their mean
+    /// and the sum of squared differences from the mean.
+    Variance {
+        count: i64,
+        mean: f64,
+        m2: f64,
+        kind: VarianceKind,
+    },
+}
 
-impl std::cmp::Ord for OwnedValue {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarianceKind {
+    VarPop,
+    VarSamp,
+    StdDevPop,
+    StdDevSamp,
 }
 
-impl std::ops::Add for OwnedValue {
-    type Output = Self;
+/// Separator used by GROUP_CONCAT when none is given.
+pub const DEFAULT_GROUP_CONCAT_SEPARATOR: &str = ",";
 
-    fn add(self, rhs: Self) -> Self {
-        match (self, rhs) {
-            (Self::Integer(int_left), Self::Integer(int_right)) => {
-                Self::Integer(int_left + int_right)
+const NULL: OwnedValue = OwnedValue::Null;
+
+impl AggContext {
+    /// The current result of the aggregate. Most aggregates keep it in their
+    /// state, the others compute it from the running state.
+    pub fn final_value(&self) -> Cow<'_, OwnedValue> {
+        match self {
+            Self::Avg(acc, count) => Cow::Owned(match (acc, count) {
+                (_, OwnedValue::Integer(0)) => OwnedValue::Null,
+                (OwnedValue::Float(sum), OwnedValue::Integer(count)) => {
+                    OwnedValue::Float(sum / *count as f64)
+                }
+                (OwnedValue::Integer(sum), OwnedValue::Integer(count)) => {
+                    OwnedValue::Float(*sum as f64 / *count as f64)
+                }
+                _ => unreachable!("avg() sums numbers and counts rows"),
+            }),
+            Self::Sum(acc) => Cow::Borrowed(acc),
+            Self::Total(acc) => Cow::Borrowed(acc),
+            Self::Count(count) => Cow::Borrowed(count),
+            Self::Max(max) => Cow::Borrowed(max.as_ref().unwrap_or(&NULL)),
+            Self::Min(min) => Cow::Borrowed(min.as_ref().unwrap_or(&NULL)),
+            Self::GroupConcat { acc, .. } | Self::GroupConcatDistinct { acc, .. } => {
+                Cow::Owned(match acc {
+                    Some(text) => OwnedValue::Text(Rc::from(text.as_str())),
+                    None => OwnedValue::Null,
+                })
             }
-            (Self::Integer(int_left), Self::Float(float_right)) => {
-                Self::Float(int_left as f64 + float_right)
+            Self::CountDistinct(count, _) => Cow::Borrowed(count),
+            Self::Variance {
+                count, m2, kind, ..
+            } => {
+                let divisor = match kind {
+                    VarianceKind::VarPop | VarianceKind::StdDevPop => *count,
+                    VarianceKind::VarSamp | VarianceKind::StdDevSamp => *count - 1,
+                };
+                if divisor < 1 {
+                    return Cow::Owned(OwnedValue::Null);
+                }
+                let variance = m2 / divisor as f64;
+                Cow::Owned(OwnedValue::Float(match kind {
+                    VarianceKind::VarPop | VarianceKind::VarSamp => variance,
+                    VarianceKind::StdDevPop | VarianceKind::StdDevSamp => variance.sqrt(),
+                }))
             }
-            (Self::Float(float_left), Self::Integer(int_right)) => {
-                Self::Float(float_left + int_right as f64)
+        }
+    }
+
+    /// Adds a value to a `Sum` aggregate. NULL values are ignored. The sum is an
+    /// integer while all values are and it fits in an i64. Once it overflows
+    /// it continues as a real, as do sums that include a real.
+    pub fn step_sum(&mut self, value: &OwnedValue) {
+        let Self::Sum(acc) = self else {
+            unreachable!("step_sum on {:?}", self);
+        };
+        // Text and blobs that aren't integers make the sum real, as in SQLite
+        let value = match value {
+            OwnedValue::Text(_) | OwnedValue::Blob(_) => {
+                match value.apply_affinity(Affinity::Numeric) {
+                    int @ OwnedValue::Integer(_) => int,
+                    other => OwnedValue::Float(0.0) + other,
+                }
             }
-            (Self::Float(float_left), Self::Float(float_right)) => {
-                Self::Float(float_left + float_right)
+            OwnedValue::Null => return,
+            value => value.clone().into_numeric(),
+        };
+        *acc = match (&*acc, value) {
+            (OwnedValue::Integer(sum), OwnedValue::Integer(i)) => match sum.checked_add(i) {
+                Some(sum) => OwnedValue::Integer(sum),
+                None => OwnedValue::Float(*sum as f64 + i as f64),
+            },
+            (OwnedValue::Null, value) => value,
+            (_, value) => acc.clone() + value,
+        };
+    }
+
+    /// Adds a value to a `Variance` aggregate using Welford's online algorithm.
+    /// NULL values are ignored.
+    pub fn step_variance(&mut self, value: &OwnedValue) {
+        let Self::Variance {
+            count, mean, m2, ..
+        } = self
+        else {
+            unreachable!("step_variance on {:?}", self);
+        };
+        let x = match value.clone().into_numeric() {
+            OwnedValue::Integer(i) => i as f64,
+            OwnedValue::Float(f) => f,
+            _ => return,
+        };
+        *count += 1;
+        let delta = x - *mean;
+        *mean += delta / *count as f64;
+        *m2 += delta * (x - *mean);
+    }
+
+    /// Adds a value to a `GroupConcat` or `GroupConcatDistinct` aggregate,
+    /// preceded by `delimiter` unless it is the first one. NULL values are
+    /// ignored. The text is appended in place, so building up a long result
+    /// takes linear time.
+    pub fn step_group_concat(&mut self, value: &OwnedValue, delimiter: &OwnedValue) {
+        if value.is_null() {
+            return;
+        }
+        let (acc, sep) = match self {
+            Self::GroupConcat { acc, sep } => (acc, sep),
+            Self::GroupConcatDistinct { acc, sep, seen } => {
+                if !seen.insert(value.clone()) {
+                    return;
+                }
+                (acc, sep)
+            }
+            _ => unreachable!("step_group_concat on {:?}", self),
+        };
+        // The delimiter may be a column, so it can change from row to row
+        *sep = match delimiter {
+            OwnedValue::Null => String::new(),
+            delimiter => delimiter.text_lossy().into_owned(),
+        };
+        match acc {
+            Some(text) => {
+                text.push_str(sep);
+                text.push_str(&value.text_lossy());
+            }
+            None => *acc = Some(value.text_lossy().into_owned()),
+        }
+    }
+
+    /// Combines the partial state `other` into `self`, as if all the values
+    /// stepped into `other` had been stepped into `self` afterwards.
+    ///
+    /// Both states must come from the same aggregate. `GROUP_CONCAT(DISTINCT ..)`
+    /// can't be merged because its text doesn't record which values it holds.
+    pub fn merge(&mut self, other: &AggContext) -> Result<()> {
+        match (self, other) {
+            (Self::Avg(acc, count), Self::Avg(other_acc, other_count)) => {
+                *acc += other_acc.clone();
+                *count += other_count.clone();
             }
-            (Self::Text(string_left), Self::Text(string_right)) => {
-                Self::Text(Rc::new(string_left.to_string() + &string_right.to_string()))
+            (Self::Sum(acc), Self::Sum(other_acc))
+            | (Self::Total(acc), Self::Total(other_acc))
+            | (Self::Count(acc), Self::Count(other_acc)) => {
+                *acc += other_acc.clone();
             }
-            (Self::Text(string_left), Self::Integer(int_right)) => {
-                Self::Text(Rc::new(string_left.to_string() + &int_right.to_string()))
+            (Self::Max(acc), Self::Max(other_acc)) => {
+                if let Some(other_max) = other_acc {
+                    let is_larger = match acc {
+                        Some(max) => other_max > max,
+                        None => true,
+                    };
+                    if is_larger {
+                        *acc = Some(other_max.clone());
+                    }
+                }
             }
-            (Self::Integer(int_left), Self::Text(string_right)) => {
-                Self::Text(Rc::new(int_left.to_string() + &string_right.to_string()))
+            (Self::Min(acc), Self::Min(other_acc)) => {
+                if let Some(other_min) = other_acc {
+                    let is_smaller = match acc {
+                        Some(min) => other_min < min,
+                        None => true,
+                    };
+                    if is_smaller {
+                        *acc = Some(other_min.clone());
+                    }
+                }
             }
-            (Self::Text(string_left), Self::Float(float_right)) => {
-                let string_right = Self::Float(float_right).to_string();
-                Self::Text(Rc::new(string_left.to_string() + &string_right))
+            (
+                Self::GroupConcat { acc, sep },
+                Self::GroupConcat {
+                    acc: other_acc,
+                    sep: other_sep,
+                },
+            ) => {
+                match (acc.as_mut(), other_acc) {
+                    (_, None) => {}
+                    (None, Some(_)) => acc.clone_from(other_acc),
+                    (Some(text), Some(other_text)) => {
+                        text.push_str(sep);
+                        text.push_str(other_text);
+                    }
+                }
+                sep.clone_from(other_sep);
             }
-            (Self::Float(float_left), Self::Text(string_right)) => {
-                let string_left = Self::Float(float_left).to_string();
-                Self::Text(Rc::new(string_left + &string_right.to_string()))
+            (Self::CountDistinct(count, seen), Self::CountDistinct(_, other_seen)) => {
+                seen.extend(other_seen.iter().cloned());
+                *count = OwnedValue::Integer(seen.len() as i64);
+            }
+            (
+                Self::Variance {
+                    count,
+                    mean,
+                    m2,
+                    kind,
+                },
+                Self::Variance {
+                    count: other_count,
+                    mean: other_mean,
+                    m2: other_m2,
+                    kind: other_kind,
+                },
+            ) if kind == other_kind => {
+                let total = *count + *other_count;
+                if total == 0 {
+                    return Ok(());
+                }
+                let delta = other_mean - *mean;
+                let (n_a, n_b) = (*count as f64, *other_count as f64);
+                *mean += delta * n_b / total as f64;
+                *m2 += other_m2 + delta * delta * n_a * n_b / total as f64;
+                *count = total;
+            }
+            (this, other) => {
+                return Err(LimboError::InternalError(format!(
+                    "cannot merge aggregate {:?} into {:?}",
+                    other, this
+                )));
             }
-            (lhs, Self::Null) => lhs,
-            (Self::Null, rhs) => rhs,
-            _ => Self::Float(0.0),
         }
+        Ok(())
     }
 }
 
-impl std::ops::Add<f64> for OwnedValue {
-    type Output = Self;
+impl OwnedValue {
+    /// The datatype name SQLite's `typeof()` returns for this value. Aggregates
+    /// report the type of their current result and records are reported as blobs.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "real",
+            Self::Text(_) => "text",
+            Self::Blob(_) | Self::Zeroblob(_) | Self::Record(_) => "blob",
+            Self::Agg(agg) => agg.final_value().type_name(),
+        }
+    }
 
-    fn add(self, rhs: f64) -> Self {
+    /// The number of bytes the value's content takes: 0 for NULL, 8 for numbers,
+    /// the length of text and blobs and the serialized size of records.
+    /// Aggregates report the size of their current result.
+    pub fn byte_len(&self) -> usize {
         match self {
-            Self::Integer(int_left) => Self::Float(int_left as f64 + rhs),
-            Self::Float(float_left) => Self::Float(float_left + rhs),
-            _ => unreachable!(),
+            Self::Null => 0,
+            Self::Integer(_) | Self::Float(_) => 8,
+            Self::Text(t) => t.len(),
+            Self::Blob(b) => b.len(),
+            Self::Zeroblob(len) => *len,
+            Self::Agg(agg) => agg.final_value().byte_len(),
+            Self::Record(r) => r.serialized_size(),
         }
     }
-}
 
-impl std::ops::Add<i64> for OwnedValue {
-    type Output = Self;
+    /// The length `length()` reports: characters for text, bytes for blobs and
+    /// the characters of the text form of numbers. NULL for NULL.
+    pub fn char_length(&self) -> OwnedValue {
+        match self {
+            Self::Null => Self::Null,
+            Self::Text(t) => Self::Integer(t.chars().count() as i64),
+            Self::Blob(_) | Self::Zeroblob(_) => Self::Integer(self.byte_len() as i64),
+            Self::Agg(agg) => agg.final_value().char_length(),
+            _ => Self::Integer(self.text_lossy().chars().count() as i64),
+        }
+    }
 
-    fn add(self, rhs: i64) -> Self {
+    /// The part of the value `substr(value, start, len)` returns, counted in
+    /// characters for text and bytes for blobs. Other values are converted to
+    /// text first. `start` is 1-based and counts from the end when negative. A
+    /// negative `len` takes the characters before `start` instead of after it,
+    /// and without `len` the rest of the value is taken. NULL for NULL.
+    pub fn substr(&self, start: i64, len: Option<i64>) -> OwnedValue {
         match self {
-            Self::Integer(int_left) => Self::Integer(int_left + rhs),
-            Self::Float(float_left) => Self::Float(float_left + rhs as f64),
-            _ => unreachable!(),
+            Self::Null => Self::Null,
+            Self::Blob(_) | Self::Zeroblob(_) => {
+                let blob = self.materialize();
+                let Self::Blob(blob) = blob.as_ref() else {
+                    unreachable!("materialized zeroblobs are blobs");
+                };
+                Self::Blob(Rc::from(&blob[substr_range(blob.len(), start, len)]))
+            }
+            Self::Agg(agg) => agg.final_value().substr(start, len),
+            _ => {
+                let text = self.text_lossy();
+                let range = substr_range(text.chars().count(), start, len);
+                let substring: String = text
+                    .chars()
+                    .skip(range.start)
+                    .take(range.end - range.start)
+                    .collect();
+                Self::Text(Rc::from(substring))
+            }
         }
     }
-}
 
-impl std::ops::AddAssign for OwnedValue {
-    fn add_assign(&mut self, rhs: Self) {
-        *self = self.clone() + rhs;
+    /// The value with every occurrence of `find` replaced by `repl`, as
+    /// `replace()` computes it. Numbers and blobs are converted to text first.
+    /// Like in SQLite, an empty `find` returns the value as text, even if
+    /// `repl` is NULL; otherwise any NULL argument makes the result NULL.
+    pub fn replace(&self, find: &OwnedValue, repl: &OwnedValue) -> OwnedValue {
+        if self.is_null() || find.is_null() {
+            return Self::Null;
+        }
+        let find = find.text_lossy();
+        if find.is_empty() {
+            return Self::Text(Rc::from(self.text_lossy()));
+        }
+        if repl.is_null() {
+            return Self::Null;
+        }
+        Self::Text(Rc::from(
+            self.text_lossy().replace(find.as_ref(), &repl.text_lossy()),
+        ))
     }
-}
 
-impl std::ops::AddAssign<i64> for OwnedValue {
-    fn add_assign(&mut self, rhs: i64) {
-        *self = self.clone() + rhs;
+    /// The value with any of `chars` removed from the ends `mode` selects, as
+    /// `trim()`, `ltrim()` and `rtrim()` compute it. Without `chars` only spaces
+    /// are removed. Numbers and blobs are converted to text first. NULL for NULL.
+    pub fn trim_matches(&self, mode: TrimMode, chars: Option<&str>) -> OwnedValue {
+        if self.is_null() {
+            return Self::Null;
+        }
+        let text = self.text_lossy();
+        let chars: Vec<char> = chars.unwrap_or(" ").chars().collect();
+        let trimmed = match mode {
+            TrimMode::Both => text.trim_matches(&chars[..]),
+            TrimMode::Leading => text.trim_start_matches(&chars[..]),
+            TrimMode::Trailing => text.trim_end_matches(&chars[..]),
+        };
+        Self::Text(Rc::from(trimmed))
     }
-}
 
-impl std::ops::AddAssign<f64> for OwnedValue {
-    fn add_assign(&mut self, rhs: f64) {
-        *self = self.clone() + rhs;
+    /// The value with the ASCII letters a-z upper-cased, as SQLite's built-in
+    /// `upper()` does. Other characters, including non-ASCII letters, are left
+    /// alone. Numbers and blobs are converted to text first. NULL for NULL.
+    pub fn to_upper_ascii(&self) -> OwnedValue {
+        if self.is_null() {
+            return Self::Null;
+        }
+        Self::Text(Rc::from(self.text_lossy().to_ascii_uppercase()))
     }
-}
 
-impl std::ops::Div for OwnedValue {
-    type Output = Self;
+    /// Like `to_upper_ascii`, but lower-casing A-Z as `lower()` does.
+    pub fn to_lower_ascii(&self) -> OwnedValue {
+        if self.is_null() {
+            return Self::Null;
+        }
+        Self::Text(Rc::from(self.text_lossy().to_ascii_lowercase()))
+    }
 
-    fn div(self, rhs: Self) -> Self {
-        match (self, rhs) {
-            (Self::Integer(int_left), Self::Integer(int_right)) => {
-                Self::Integer(int_left / int_right)
+    /// The bytes of the value as upper-case hexadecimal text, as `hex()`
+    /// returns it: the bytes of a blob, or of the text the value converts to
+    /// otherwise. NULL becomes the empty string.
+    pub fn hex(&self) -> OwnedValue {
+        match self {
+            Self::Null => Self::Text(Rc::from("")),
+            Self::Agg(agg) => agg.final_value().hex(),
+            Self::Blob(b) => Self::Text(Rc::from(hex::encode_upper(b.as_ref()))),
+            Self::Zeroblob(len) => Self::Text(Rc::from("00".repeat(*len))),
+            _ => Self::Text(Rc::from(hex::encode_upper(self.text_lossy().as_bytes()))),
+        }
+    }
+
+    /// The blob that the value's text spells in hexadecimal, as `unhex()`
+    /// decodes it. Both cases of the digits are accepted. NULL if the text
+    /// has an odd number of digits or any other character, or for NULL.
+    pub fn unhex(&self) -> OwnedValue {
+        self.unhex_ignoring("")
+    }
+
+    /// Like `unhex`, but skipping any of the characters in `ignored`, as the
+    /// two-argument `unhex(X, Y)` does. Like in SQLite, they are only skipped
+    /// between pairs of digits, so `'4 1'` is NULL even if a space is ignored.
+    pub fn unhex_ignoring(&self, ignored: &str) -> OwnedValue {
+        if self.is_null() {
+            return Self::Null;
+        }
+        let text = self.text_lossy();
+        let mut chars = text.chars();
+        let mut bytes = Vec::with_capacity(text.len() / 2);
+        loop {
+            let high = loop {
+                match chars.next() {
+                    Some(c) if c.is_ascii_hexdigit() => break c,
+                    Some(c) if ignored.contains(c) => continue,
+                    Some(_) => return Self::Null,
+                    None => return Self::Blob(Rc::from(bytes)),
+                }
+            };
+            let low = match chars.next() {
+                Some(c) if c.is_ascii_hexdigit() => c,
+                _ => return Self::Null,
+            };
+            let digit = |c: char| c.to_digit(16).unwrap() as u8;
+            bytes.push((digit(high) << 4) | digit(low));
+        }
+    }
+
+    /// The 1-based position of the first occurrence of `needle` in the value,
+    /// as `instr()` returns it, or 0 if there is none. Positions count bytes
+    /// when both values are blobs and characters otherwise, with other values
+    /// converted to text first. An empty needle is found at position 1. NULL if
+    /// either value is NULL.
+    pub fn instr(&self, needle: &OwnedValue) -> OwnedValue {
+        match (self, needle) {
+            (Self::Agg(agg), _) => agg.final_value().instr(needle),
+            (_, Self::Agg(agg)) => self.instr(&agg.final_value()),
+            (Self::Null, _) | (_, Self::Null) => Self::Null,
+            (Self::Blob(_) | Self::Zeroblob(_), Self::Blob(_) | Self::Zeroblob(_)) => {
+                let (haystack, needle) = (self.materialize(), needle.materialize());
+                let (Self::Blob(haystack), Self::Blob(needle)) =
+                    (haystack.as_ref(), needle.as_ref())
+                else {
+                    unreachable!("materialized zeroblobs are blobs");
+                };
+                let position = if needle.is_empty() {
+                    Some(0)
+                } else {
+                    haystack
+                        .windows(needle.len())
+                        .position(|window| window == &needle[..])
+                };
+                Self::Integer(position.map_or(0, |i| i as i64 + 1))
             }
-            (Self::Integer(int_left), Self::Float(float_right)) => {
-                Self::Float(int_left as f64 / float_right)
+            _ => {
+                let (haystack, needle) = (self.text_lossy(), needle.text_lossy());
+                let position = haystack
+                    .find(needle.as_ref())
+                    .map_or(0, |i| haystack[..i].chars().count() as i64 + 1);
+                Self::Integer(position)
             }
-            (Self::Float(float_left), Self::Integer(int_right)) => {
-                Self::Float(float_left / int_right as f64)
+        }
+    }
+
+    /// The value as an SQL literal, as `quote()` writes it: numbers as they
+    /// are, text in single quotes with quotes doubled, blobs as `X'..'` and
+    /// NULL as `NULL`. Like in SQLite, text stops at the first NUL character,
+    /// and reals that 15 significant digits don't round-trip get 21.
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            Self::Null | Self::Record(_) => "NULL".to_string(),
+            Self::Integer(i) => i.to_string(),
+            Self::Float(f) => {
+                let literal = format_float(*f);
+                if !f.is_finite() || literal.parse::<f64>().ok() == Some(*f) {
+                    return literal;
+                }
+                let scientific = format!("{:.20e}", f);
+                let (mantissa, exponent) = scientific.split_once('e').unwrap();
+                let exponent: i32 = exponent.parse().unwrap();
+                let exponent_sign = if exponent < 0 { '-' } else { '+' };
+                format!("{}e{}{:02}", mantissa, exponent_sign, exponent.abs())
             }
-            (Self::Float(float_left), Self::Float(float_right)) => {
-                Self::Float(float_left / float_right)
+            Self::Text(text) => {
+                let text = text.split('\0').next().unwrap_or_default();
+                format!("'{}'", text.replace('\'', "''"))
             }
-            _ => Self::Float(0.0),
+            Self::Blob(_) | Self::Zeroblob(_) => self.to_string(),
+            Self::Agg(agg) => agg.final_value().to_sql_literal(),
         }
     }
-}
 
-impl std::ops::DivAssign<OwnedValue> for OwnedValue {
-    fn div_assign(&mut self, rhs: Self) {
-        *self = self.clone() / rhs;
+    /// The bytes of two blobs one after the other. NULL unless both values
+    /// are blobs: unlike `concat`, nothing is converted.
+    pub fn blob_concat(&self, other: &Self) -> OwnedValue {
+        match (self, other) {
+            (Self::Agg(agg), _) => agg.final_value().blob_concat(other),
+            (_, Self::Agg(agg)) => self.blob_concat(&agg.final_value()),
+            // zeroblobs stay unallocated
+            (Self::Zeroblob(left), Self::Zeroblob(right)) => Self::Zeroblob(left + right),
+            (Self::Blob(_) | Self::Zeroblob(_), Self::Blob(_) | Self::Zeroblob(_)) => {
+                let (left, right) = (self.materialize(), other.materialize());
+                let (Self::Blob(left), Self::Blob(right)) = (left.as_ref(), right.as_ref()) else {
+                    unreachable!("materialized zeroblobs are blobs");
+                };
+                Self::Blob(Rc::from([&left[..], &right[..]].concat()))
+            }
+            _ => Self::Null,
+        }
     }
-}
 
-pub fn to_value(value: &OwnedValue) -> Value<'_> {
-    match value {
-        OwnedValue::Null => Value::Null,
-        OwnedValue::Integer(i) => Value::Integer(*i),
-        OwnedValue::Float(f) => Value::Float(*f),
-        OwnedValue::Text(s) => Value::Text(s),
-        OwnedValue::Blob(b) => Value::Blob(b),
-        OwnedValue::Agg(a) => match a.as_ref() {
-            AggContext::Avg(acc, _count) => match acc {
-                OwnedValue::Integer(i) => Value::Integer(*i),
-                OwnedValue::Float(f) => Value::Float(*f),
-                _ => Value::Float(0.0),
+    /// The serial type that stores this value in a record, see
+    /// <https://www.sqlite.org/fileformat.html#record_format>. Integers use the
+    /// smallest type that holds them.
+    ///
+    /// Panics for aggregates and records, which can't be stored in a record.
+    pub fn serial_type(&self) -> u64 {
+        match self {
+            Self::Null => 0,
+            Self::Integer(i) => integer_serial_type(*i),
+            Self::Float(_) => 7,
+            Self::Text(t) => (t.len() * 2 + 13) as u64,
+            Self::Blob(b) => (b.len() * 2 + 12) as u64,
+            Self::Zeroblob(len) => (len * 2 + 12) as u64,
+            // not serializable values
+            Self::Agg(_) => unreachable!(),
+            Self::Record(_) => unreachable!(),
+        }
+    }
+
+    /// Builds a text value from raw bytes, such as text read from a database
+    /// file written by another SQLite. Text has to be valid UTF-8, so bytes that
+    /// are not are kept unchanged as a blob instead of being repaired or
+    /// rejected.
+    pub fn text_from_bytes(bytes: &[u8]) -> OwnedValue {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(Rc::from(text)),
+            Err(_) => Self::Blob(Rc::from(bytes)),
+        }
+    }
+
+    /// Decodes one value of a record body from the start of `bytes`, given its
+    /// serial type, and returns it with the number of bytes it took. NULL and
+    /// the constants 0 and 1 take none. An invalid serial type or a payload
+    /// running past the end of `bytes` is a `LimboError::Corrupt` error.
+    pub fn from_serial(serial_type: u64, bytes: &[u8]) -> Result<(OwnedValue, usize)> {
+        read_value(bytes, &SerialType::try_from(serial_type)?)
+    }
+
+    /// Converts a value to a number the way SQLite does for arithmetic operands:
+    /// text and blobs are parsed as numbers (0 when they don't look like one),
+    /// aggregates use their current value and NULL stays NULL.
+    pub(crate) fn into_numeric(self) -> Self {
+        match self {
+            Self::Null | Self::Integer(_) | Self::Float(_) => self,
+            Self::Text(text) => Self::text_to_numeric(&text).unwrap_or(Self::Integer(0)),
+            Self::Blob(blob) => {
+                Self::text_to_numeric(&String::from_utf8_lossy(&blob)).unwrap_or(Self::Integer(0))
+            }
+            Self::Zeroblob(_) => Self::Integer(0),
+            Self::Agg(agg) => agg.final_value().into_owned().into_numeric(),
+            Self::Record(_) => Self::Integer(0),
+        }
+    }
+
+    /// Parses the longest numeric prefix of `s` the way SQLite's `sqlite3AtoF`
+    /// does: leading whitespace, an optional sign, digits with an optional decimal
+    /// point and an optional exponent, stopping at the first invalid character.
+    ///
+    /// Returns `Integer` if the prefix has no decimal point or exponent and fits
+    /// in an i64, `Float` otherwise, and `None` if `s` doesn't start with a number.
+    pub fn text_to_numeric(s: &str) -> Option<OwnedValue> {
+        parse_numeric_prefix(s).map(|(value, _)| value)
+    }
+
+    /// Converts a value the way `CAST(value AS ty)` does, see
+    /// https://www.sqlite.org/lang_expr.html#castexpr. NULL always casts to NULL.
+    pub fn cast_to(&self, ty: CastType) -> OwnedValue {
+        let value = match self {
+            Self::Agg(agg) => return agg.final_value().cast_to(ty),
+            value => value,
+        };
+        if matches!(value, Self::Null) {
+            return Self::Null;
+        }
+        match ty {
+            // Casting to BLOB first casts the value to TEXT, then reinterprets its bytes.
+            // TODO: handle encoding
+            CastType::Blob => match value {
+                Self::Blob(_) | Self::Zeroblob(_) => value.clone(),
+                Self::Text(t) => Self::Blob(Rc::from(t.as_bytes())),
+                _ => Self::Blob(Rc::from(value.to_string().into_bytes())),
             },
-            AggContext::Sum(acc) => match acc {
-                OwnedValue::Integer(i) => Value::Integer(*i),
-                OwnedValue::Float(f) => Value::Float(*f),
-                _ => Value::Float(0.0),
+            // A BLOB is interpreted as text in the database encoding, numbers are
+            // rendered with the same formatting as `Display`.
+            CastType::Text => match value {
+                Self::Text(_) => value.clone(),
+                Self::Blob(_) | Self::Zeroblob(_) => Self::Text(Rc::from(value.text_lossy())),
+                _ => Self::Text(Rc::from(value.to_string())),
             },
-            AggContext::Count(count) => to_value(count),
-            AggContext::Max(max) => match max {
-                Some(max) => to_value(max),
-                None => Value::Null,
+            // The longest prefix that looks like a real number is used, 0.0 if there is none.
+            CastType::Real => match value {
+                Self::Integer(i) => Self::Float(*i as f64),
+                Self::Float(f) => Self::Float(*f),
+                _ => match Self::text_to_numeric(&value.text_lossy()) {
+                    Some(Self::Integer(i)) => Self::Float(i as f64),
+                    Some(Self::Float(f)) => Self::Float(f),
+                    _ => Self::Float(0.0),
+                },
             },
-            AggContext::Min(min) => match min {
-                Some(min) => to_value(min),
-                None => Value::Null,
+            // Reals are truncated toward zero and saturate at the i64 bounds, text uses the
+            // longest prefix that looks like an integer, 0 if there is none.
+            CastType::Integer => match value {
+                Self::Integer(i) => Self::Integer(*i),
+                Self::Float(f) => Self::Integer(*f as i64),
+                _ => Self::Integer(parse_integer_prefix(&value.text_lossy())),
             },
-            AggContext::GroupConcat(s) => to_value(s),
-        },
-        OwnedValue::Record(_) => todo!(),
+            // Text that looks like a real which is losslessly convertible to an integer
+            // becomes an INTEGER. Casting an INTEGER or REAL to NUMERIC is a no-op.
+            CastType::Numeric => match value {
+                Self::Integer(_) | Self::Float(_) => value.clone(),
+                _ => match Self::text_to_numeric(&value.text_lossy()) {
+                    Some(value) => value.normalize_numeric(),
+                    None => Self::Integer(0),
+                },
+            },
+        }
     }
-}
 
-pub trait FromValue<'a> {
-    fn from_value(value: &Value<'a>) -> Result<Self>
-    where
-        Self: Sized + 'a;
-}
+    /// Turns a real with no fractional part into the equal integer, the way
+    /// NUMERIC affinity does. Like SQLite, only reals within 2^51 of zero are
+    /// converted, so `1e20` stays a real. Other values are returned unchanged.
+    pub fn normalize_numeric(&self) -> OwnedValue {
+        match self {
+            Self::Float(f) => match real_to_integer(*f) {
+                Some(i) => Self::Integer(i),
+                None => Self::Float(*f),
+            },
+            Self::Agg(agg) => agg.final_value().normalize_numeric(),
+            value => value.clone(),
+        }
+    }
 
-impl<'a> FromValue<'a> for i64 {
-    fn from_value(value: &Value<'a>) -> Result<Self> {
-        match value {
-            Value::Integer(i) => Ok(*i),
-            _ => Err(LimboError::ConversionError("Expected integer value".into())),
+    /// Absolute value, as computed by the ABS() function. Text and blobs are
+    /// converted to reals, and the absolute value of `i64::MIN` is a float.
+    pub fn abs(&self) -> OwnedValue {
+        match self {
+            Self::Null => Self::Null,
+            Self::Integer(i) => match i.checked_abs() {
+                Some(i) => Self::Integer(i),
+                None => Self::Float(-(*i as f64)),
+            },
+            Self::Float(f) => Self::Float(f.abs()),
+            Self::Agg(agg) => agg.final_value().abs(),
+            _ => match self.cast_to(CastType::Real) {
+                Self::Float(f) => Self::Float(f.abs()),
+                _ => Self::Float(0.0),
+            },
         }
     }
-}
 
-impl<'a> FromValue<'a> for String {
-    fn from_value(value: &Value<'a>) -> Result<Self> {
-        match value {
-            Value::Text(s) => Ok(s.to_string()),
-            _ => Err(LimboError::ConversionError("Expected text value".into())),
+    /// The value rounded to `digits` decimal places as `round()` computes it:
+    /// always a real, with halves rounded away from zero. Text and blobs are
+    /// converted to reals first. Like in SQLite, `digits` is clamped to 0..=30,
+    /// so negative digits round to a whole number. NULL for NULL.
+    pub fn round(&self, digits: i64) -> OwnedValue {
+        match self.cast_to(CastType::Real) {
+            Self::Float(f) => Self::Float(round_real(f, digits.clamp(0, 30) as usize)),
+            _ => Self::Null,
         }
     }
-}
 
-impl<'a> FromValue<'a> for &'a str {
-    fn from_value(value: &Value<'a>) -> Result<&'a str> {
-        match value {
-            Value::Text(s) => Ok(s),
-            _ => Err(LimboError::ConversionError("Expected text value".into())),
+    /// The sign of the value as `sign()` computes it: -1, 0 or 1 as an integer.
+    /// NULL if the value is NULL or not a number, including blobs and text
+    /// that is not entirely a numeric literal.
+    pub fn sign(&self) -> OwnedValue {
+        match self.to_numeric_operand() {
+            Some(Self::Integer(i)) => Self::Integer(i.signum()),
+            Some(Self::Float(f)) if f > 0.0 => Self::Integer(1),
+            Some(Self::Float(f)) if f < 0.0 => Self::Integer(-1),
+            Some(_) => Self::Integer(0),
+            None => Self::Null,
         }
     }
-}
 
-#[derive(Debug)]
-pub struct Record<'a> {
-    pub values: Vec<Value<'a>>,
-}
+    /// The value truncated toward zero as `trunc()` computes it. Integers are
+    /// returned as is and reals stay reals. NULL for the same operands
+    /// [`OwnedValue::sign`] rejects.
+    pub fn trunc(&self) -> OwnedValue {
+        match self.to_numeric_operand() {
+            Some(Self::Float(f)) => Self::Float(f.trunc()),
+            Some(value) => value,
+            None => Self::Null,
+        }
+    }
 
-impl<'a> Record<'a> {
-    pub fn new(values: Vec<Value<'a>>) -> Self {
-        Self { values }
+    /// The value raised to the power `exp` as `pow()` computes it. NULL if either
+    /// operand is not a number or the result is not a real number.
+    pub fn pow(&self, exp: &OwnedValue) -> OwnedValue {
+        match (self.to_real_operand(), exp.to_real_operand()) {
+            (Some(base), Some(exp)) => real_or_null(base.powf(exp)),
+            _ => Self::Null,
+        }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct OwnedRecord {
-    pub values: Vec<OwnedValue>,
-}
+    /// The square root as `sqrt()` computes it. NULL for negative values.
+    pub fn sqrt(&self) -> OwnedValue {
+        match self.to_real_operand() {
+            Some(x) => real_or_null(x.sqrt()),
+            None => Self::Null,
+        }
+    }
 
-impl OwnedRecord {
-    pub fn new(values: Vec<OwnedValue>) -> Self {
-        Self { values }
+    /// The natural logarithm as `ln()` computes it. NULL unless the value is
+    /// positive.
+    pub fn ln(&self) -> OwnedValue {
+        match self.to_real_operand() {
+            Some(x) if x > 0.0 => Self::Float(x.ln()),
+            _ => Self::Null,
+        }
     }
 
-    pub fn serialize(&self, buf: &mut Vec<u8>) {
-        let initial_i = buf.len();
+    /// The logarithm to `base` as `log(B, X)` computes it. NULL unless both the
+    /// value and the base are positive, and for a base of 1.
+    pub fn log(&self, base: &OwnedValue) -> OwnedValue {
+        match (self.to_real_operand(), base.to_real_operand()) {
+            (Some(x), Some(base)) if x > 0.0 && base > 0.0 && base != 1.0 => {
+                Self::Float(x.ln() / base.ln())
+            }
+            _ => Self::Null,
+        }
+    }
 
-        for value in &self.values {
-            let serial_type = match value {
-                OwnedValue::Null => 0,
-                OwnedValue::Integer(_) => 6, // for now let's only do i64
-                OwnedValue::Float(_) => 7,
-                OwnedValue::Text(t) => (t.len() * 2 + 13) as u64,
-                OwnedValue::Blob(b) => (b.len() * 2 + 12) as u64,
-                // not serializable values
-                OwnedValue::Agg(_) => unreachable!(),
-                OwnedValue::Record(_) => unreachable!(),
-            };
+    /// Like [`OwnedValue::to_numeric_operand`], with the number as a real.
+    fn to_real_operand(&self) -> Option<f64> {
+        match self.to_numeric_operand()? {
+            Self::Integer(i) => Some(i as f64),
+            Self::Float(f) => Some(f),
+            _ => None,
+        }
+    }
 
-            buf.resize(buf.len() + 9, 0); // Ensure space for varint
-            let len = buf.len();
-            let n = write_varint(&mut buf[len - 9..], serial_type);
-            buf.truncate(buf.len() - 9 + n); // Remove unused bytes
+    /// Converts an operand of a numeric function that only accepts numbers:
+    /// text counts if it is entirely a numeric literal, and everything else
+    /// that is not an integer or a real is `None`.
+    fn to_numeric_operand(&self) -> Option<OwnedValue> {
+        match self {
+            Self::Integer(_) | Self::Float(_) => Some(self.clone()),
+            Self::Text(text) => parse_numeric_literal(text),
+            Self::Agg(agg) => agg.final_value().to_numeric_operand(),
+            _ => None,
         }
+    }
 
-        let mut header_size = buf.len() - initial_i;
-        // write content
-        for value in &self.values {
-            // TODO: make integers and floats with smaller serial types
-            match value {
-                OwnedValue::Null => {}
-                OwnedValue::Integer(i) => buf.extend_from_slice(&i.to_be_bytes()),
-                OwnedValue::Float(f) => buf.extend_from_slice(&f.to_be_bytes()),
-                OwnedValue::Text(t) => buf.extend_from_slice(t.as_bytes()),
-                OwnedValue::Blob(b) => buf.extend_from_slice(b),
-                // non serializable
-                OwnedValue::Agg(_) => unreachable!(),
-                OwnedValue::Record(_) => unreachable!(),
-            };
+    /// Converts an operand of a bitwise operator to an integer: reals are truncated
+    /// and text uses its integer prefix. Returns `None` for NULL.
+    fn to_integer_operand(&self) -> Option<i64> {
+        match self {
+            Self::Null => None,
+            Self::Integer(i) => Some(*i),
+            Self::Float(f) => Some(*f as i64),
+            Self::Agg(agg) => agg.final_value().to_integer_operand(),
+            Self::Record(_) | Self::Zeroblob(_) => Some(0),
+            Self::Text(_) | Self::Blob(_) => Some(parse_integer_prefix(&self.text_lossy())),
+        }
+    }
+
+    /// The value as a string the way SQLite reads it as text. Unlike `Display`,
+    /// blobs are reinterpreted as (lossy) UTF-8 instead of printed as a hex literal.
+    pub fn text_lossy(&self) -> Cow<'_, str> {
+        match self {
+            Self::Text(t) => Cow::Borrowed(t),
+            Self::Blob(b) => String::from_utf8_lossy(b),
+            Self::Zeroblob(len) => Cow::Owned("\0".repeat(*len)),
+            _ => Cow::Owned(self.to_string()),
+        }
+    }
+
+    /// Concatenates the text of both values the way `||` does: numbers and blobs
+    /// are converted to text, and a NULL operand makes the result NULL.
+    pub fn concat(&self, other: &Self) -> Self {
+        if self.is_null() || other.is_null() {
+            return Self::Null;
+        }
+        let mut text = self.text_lossy().into_owned();
+        text.push_str(&other.text_lossy());
+        Self::Text(Rc::from(text))
+    }
+
+    /// Whether the text matches the `LIKE` pattern: `%` matches any run of
+    /// characters, `_` any single character and ASCII letters match regardless
+    /// of case. The character after `escape` matches itself. NULL and values
+    /// that aren't text never match.
+    pub fn like(&self, pattern: &str, escape: Option<char>) -> bool {
+        let Self::Text(text) = self else {
+            return false;
+        };
+        parse_like_pattern(pattern, escape).is_some_and(|tokens| match_pattern(&tokens, text, true))
+    }
+
+    /// Whether the text matches the `GLOB` pattern: `*` matches any run of
+    /// characters, `?` any single character and `[...]` one character of a set
+    /// such as `[a-z]` or `[^0-9]`. Matching is case sensitive. NULL and values
+    /// that aren't text never match.
+    pub fn glob(&self, pattern: &str) -> bool {
+        let Self::Text(text) = self else {
+            return false;
+        };
+        parse_glob_pattern(pattern).is_some_and(|tokens| match_pattern(&tokens, text, false))
+    }
+
+    /// Whether the value is text or a number holding a valid JSON document.
+    #[cfg(feature = "json")]
+    pub fn json_valid(&self) -> bool {
+        crate::json::json_valid(self)
+    }
+
+    /// Extracts the element at a JSON path such as `$.a.b[0]` from the value's
+    /// JSON text, converted to a value the way `json_extract()` does.
+    #[cfg(feature = "json")]
+    pub fn json_extract(&self, path: &str) -> Result<OwnedValue> {
+        crate::json::json_extract(self, path)
+    }
+
+    /// The Julian day number of a time value: ISO-8601 text (or text holding
+    /// a Julian day number), an integer of unix epoch seconds or a float that
+    /// already is a Julian day number. `None` if the value isn't a time value
+    /// or lies outside the Julian days SQLite accepts, 0 up to 9999-12-31.
+    pub fn to_julian_day(&self) -> Option<f64> {
+        match self {
+            Self::Agg(agg) => agg.final_value().to_julian_day(),
+            _ => crate::vdbe::datetime::julian_day_from_time_value(self),
+        }
+    }
+
+    /// The text form `YYYY-MM-DD HH:MM:SS` of a Julian day number, as
+    /// `datetime()` prints it. NULL if the day is outside SQLite's range.
+    pub fn from_julian_day(jd: f64) -> OwnedValue {
+        match crate::vdbe::datetime::naive_datetime_from_julian_day(jd) {
+            Some(dt) => Self::Text(Rc::from(dt.format("%Y-%m-%d %H:%M:%S").to_string())),
+            None => Self::Null,
+        }
+    }
+
+    /// Applies an arithmetic operator to the operands converted to numbers:
+    /// `int_op` if both are integers and `float_op` otherwise. NULL if either
+    /// operand is. When `int_op` overflows the integer operands are returned
+    /// as the error, so the caller decides whether to fail or use reals.
+    fn arithmetic(
+        self,
+        rhs: Self,
+        int_op: fn(i64, i64) -> Option<i64>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> std::result::Result<Self, (i64, i64)> {
+        match (self.into_numeric(), rhs.into_numeric()) {
+            (Self::Null, _) | (_, Self::Null) => Ok(Self::Null),
+            (Self::Integer(int_left), Self::Integer(int_right)) => {
+                match int_op(int_left, int_right) {
+                    Some(result) => Ok(Self::Integer(result)),
+                    None => Err((int_left, int_right)),
+                }
+            }
+            (Self::Integer(int_left), Self::Float(float_right)) => {
+                Ok(Self::Float(float_op(int_left as f64, float_right)))
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) => {
+                Ok(Self::Float(float_op(float_left, int_right as f64)))
+            }
+            (Self::Float(float_left), Self::Float(float_right)) => {
+                Ok(Self::Float(float_op(float_left, float_right)))
+            }
+            _ => Ok(Self::Float(0.0)),
+        }
+    }
+
+    /// Like `arithmetic` for division, where a zero divisor yields NULL.
+    fn divide(self, rhs: Self) -> std::result::Result<Self, (i64, i64)> {
+        let rhs = rhs.into_numeric();
+        if matches!(rhs, Self::Integer(0) | Self::Float(0.0)) {
+            return Ok(Self::Null);
+        }
+        self.arithmetic(rhs, i64::checked_div, |l, r| l / r)
+    }
+
+    /// Adds two values like `+` in SQL, but an integer sum that doesn't fit
+    /// in an i64 is an `IntegerOverflow` error instead of becoming a real.
+    pub fn checked_add(&self, other: &Self) -> Result<OwnedValue> {
+        self.clone()
+            .arithmetic(other.clone(), i64::checked_add, |l, r| l + r)
+            .map_err(|_| LimboError::IntegerOverflow)
+    }
+
+    /// Like `checked_add` for subtraction.
+    pub fn checked_sub(&self, other: &Self) -> Result<OwnedValue> {
+        self.clone()
+            .arithmetic(other.clone(), i64::checked_sub, |l, r| l - r)
+            .map_err(|_| LimboError::IntegerOverflow)
+    }
+
+    /// Like `checked_add` for multiplication.
+    pub fn checked_mul(&self, other: &Self) -> Result<OwnedValue> {
+        self.clone()
+            .arithmetic(other.clone(), i64::checked_mul, |l, r| l * r)
+            .map_err(|_| LimboError::IntegerOverflow)
+    }
+
+    /// Like `checked_add` for division, which only overflows for
+    /// `i64::MIN / -1`. Division by zero yields NULL.
+    pub fn checked_div(&self, other: &Self) -> Result<OwnedValue> {
+        self.clone()
+            .divide(other.clone())
+            .map_err(|_| LimboError::IntegerOverflow)
+    }
+
+    /// Whether the value is SQL NULL. Aggregates are NULL when their current
+    /// result is, records never are.
+    pub fn is_null(&self) -> bool {
+        match self {
+            Self::Null => true,
+            Self::Agg(agg) => agg.final_value().is_null(),
+            _ => false,
+        }
+    }
+
+    /// Moves the value out, leaving NULL in its place, like `Option::take`.
+    /// Lets a register be emptied without cloning its contents.
+    pub fn take(&mut self) -> OwnedValue {
+        std::mem::replace(self, OwnedValue::Null)
+    }
+
+    /// Sets the value to NULL, dropping whatever it held.
+    pub fn reset(&mut self) {
+        *self = OwnedValue::Null;
+    }
+
+    /// Allocates the bytes of a zeroblob, turning it into an ordinary blob. Other
+    /// values are returned as they are.
+    pub fn materialize(&self) -> Cow<'_, OwnedValue> {
+        match self {
+            Self::Zeroblob(len) => Cow::Owned(Self::Blob(Rc::from(vec![0; *len]))),
+            value => Cow::Borrowed(value),
+        }
+    }
+
+    /// Converts a value toward `affinity` the way SQLite does when storing it in
+    /// a column. Values that can't be converted losslessly are returned unchanged.
+    pub fn apply_affinity(&self, affinity: Affinity) -> OwnedValue {
+        match (affinity, self) {
+            (_, Self::Agg(agg)) => agg.final_value().apply_affinity(affinity),
+            (Affinity::Blob, _) => self.clone(),
+            (Affinity::Text, Self::Integer(_) | Self::Float(_)) => {
+                Self::Text(Rc::from(self.to_string()))
+            }
+            (Affinity::Text, _) => self.clone(),
+            (Affinity::Integer | Affinity::Numeric | Affinity::Real, _) => {
+                let value = match self {
+                    Self::Text(text) => match parse_numeric_literal(text) {
+                        Some(value) => value,
+                        None => return self.clone(),
+                    },
+                    _ => self.clone(),
+                };
+                match value {
+                    Self::Integer(i) if affinity == Affinity::Real => Self::Float(i as f64),
+                    value if affinity != Affinity::Real => value.normalize_numeric(),
+                    value => value,
+                }
+            }
+        }
+    }
+
+    /// Compares two values like `partial_cmp`, but text values are compared
+    /// using `collation`.
+    pub fn compare_with_collation(
+        &self,
+        other: &Self,
+        collation: Collation,
+    ) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Text(text_left), Self::Text(text_right)) => {
+                Some(collation.compare(text_left, text_right))
+            }
+            (Self::Agg(a), _) => a.final_value().compare_with_collation(other, collation),
+            (_, Self::Agg(b)) => self.compare_with_collation(&b.final_value(), collation),
+            _ => self.partial_cmp(other),
+        }
+    }
+
+    /// Compares two values like `sql_cmp` after converting them for a
+    /// comparison with `affinity`, see `Affinity::for_comparison`. With a
+    /// numeric affinity, text that looks like a number is compared as that
+    /// number. With TEXT affinity, numbers are compared as their text.
+    pub fn compare_with_affinity(
+        &self,
+        other: &Self,
+        affinity: Affinity,
+    ) -> Option<std::cmp::Ordering> {
+        self.to_comparison_operand(affinity)
+            .sql_cmp(&other.to_comparison_operand(affinity))
+    }
+
+    /// Converts an operand of a comparison with `affinity`.
+    fn to_comparison_operand(&self, affinity: Affinity) -> Cow<'_, OwnedValue> {
+        match (affinity, self) {
+            (_, Self::Agg(agg)) => Cow::Owned(
+                agg.final_value()
+                    .to_comparison_operand(affinity)
+                    .into_owned(),
+            ),
+            // integers stay integers under every numeric affinity
+            (Affinity::Integer | Affinity::Real | Affinity::Numeric, Self::Text(_)) => {
+                Cow::Owned(self.apply_affinity(Affinity::Numeric))
+            }
+            (Affinity::Text, Self::Integer(_) | Self::Float(_)) => {
+                Cow::Owned(self.apply_affinity(Affinity::Text))
+            }
+            _ => Cow::Borrowed(self),
+        }
+    }
+
+    /// Whether two values are the same for DISTINCT, GROUP BY and compound
+    /// selects: NULL equals NULL, integers and reals are equal when they have
+    /// exactly the same value and text is compared with the binary collation.
+    pub fn values_equal(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Agg(a), _) => a.final_value().values_equal(other),
+            (_, Self::Agg(b)) => self.values_equal(&b.final_value()),
+            (Self::Integer(i), Self::Float(f)) | (Self::Float(f), Self::Integer(i)) => {
+                // i64::MAX as f64 rounds up to 2^63, so check the range before converting
+                (-9223372036854775808.0..9223372036854775808.0).contains(f)
+                    && *f as i64 == *i
+                    && f.fract() == 0.0
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares two values the way a SQL predicate does: if either operand is
+    /// NULL the result is unknown and `None` is returned.
+    ///
+    /// Expression evaluation (`=`, `<`, ... in WHERE, ON and CASE) should use
+    /// this, while sorting, sorters and b-tree keys use `Ord`, which places
+    /// NULL before every other value.
+    pub fn sql_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Agg(a), _) => a.final_value().sql_cmp(other),
+            (_, Self::Agg(b)) => self.sql_cmp(&b.final_value()),
+            (Self::Null, _) | (_, Self::Null) => None,
+            _ => self.partial_cmp(other),
+        }
+    }
+
+    /// `=` with three-valued logic: NULL if either operand is NULL, otherwise
+    /// 1 or 0. The operands are converted for a comparison with `affinity` as in
+    /// `compare_with_affinity` and text is compared using `collation`.
+    pub fn sql_eq(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_eq)
+    }
+
+    /// `<>` with three-valued logic, see `sql_eq`.
+    pub fn sql_ne(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_ne)
+    }
+
+    /// `<` with three-valued logic, see `sql_eq`.
+    pub fn sql_lt(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_lt)
+    }
+
+    /// `<=` with three-valued logic, see `sql_eq`.
+    pub fn sql_le(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_le)
+    }
+
+    /// `>` with three-valued logic, see `sql_eq`.
+    pub fn sql_gt(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_gt)
+    }
+
+    /// `>=` with three-valued logic, see `sql_eq`.
+    pub fn sql_ge(&self, other: &Self, affinity: Affinity, collation: Collation) -> Self {
+        self.sql_predicate(other, affinity, collation, std::cmp::Ordering::is_ge)
+    }
+
+    fn sql_predicate(
+        &self,
+        other: &Self,
+        affinity: Affinity,
+        collation: Collation,
+        holds: fn(std::cmp::Ordering) -> bool,
+    ) -> Self {
+        let lhs = self.to_comparison_operand(affinity);
+        let rhs = other.to_comparison_operand(affinity);
+        if lhs.is_null() || rhs.is_null() {
+            return Self::Null;
+        }
+        match lhs.compare_with_collation(&rhs, collation) {
+            Some(ordering) => Self::Integer(holds(ordering) as i64),
+            None => Self::Null,
         }
+    }
+
+    /// `AND` with three-valued logic: 0 if either operand is false, even if the
+    /// other one is NULL, otherwise NULL if either operand is NULL, otherwise 1.
+    /// See `truth_value` for which values are true.
+    pub fn sql_and(&self, other: &Self) -> Self {
+        match (self.truth_value(), other.truth_value()) {
+            (Some(false), _) | (_, Some(false)) => Self::Integer(0),
+            (Some(true), Some(true)) => Self::Integer(1),
+            _ => Self::Null,
+        }
+    }
+
+    /// `OR` with three-valued logic: 1 if either operand is true, even if the
+    /// other one is NULL, otherwise NULL if either operand is NULL, otherwise 0.
+    pub fn sql_or(&self, other: &Self) -> Self {
+        match (self.truth_value(), other.truth_value()) {
+            (Some(true), _) | (_, Some(true)) => Self::Integer(1),
+            (Some(false), Some(false)) => Self::Integer(0),
+            _ => Self::Null,
+        }
+    }
+
+    /// `NOT` with three-valued logic: NULL stays NULL.
+    pub fn sql_not(&self) -> Self {
+        match self.truth_value() {
+            Some(value) => Self::Integer(!value as i64),
+            None => Self::Null,
+        }
+    }
+
+    /// Whether the value counts as true in a boolean context, `None` for NULL.
+    /// Numbers are true unless they are zero, and text and blobs are converted
+    /// to a real first, so `'abc'` is false and `'0.5'` is true.
+    fn truth_value(&self) -> Option<bool> {
+        match self {
+            Self::Null => None,
+            Self::Integer(i) => Some(*i != 0),
+            Self::Float(f) => Some(*f != 0.0),
+            Self::Agg(agg) => agg.final_value().truth_value(),
+            _ => Some(matches!(self.cast_to(CastType::Real), Self::Float(f) if f != 0.0)),
+        }
+    }
+}
+
+/// The range of the `value_len` characters (or bytes, for blobs) that
+/// `substr(value, start, len)` takes, with the same arithmetic as
+/// substrFunc() in SQLite's func.c.
+fn substr_range(value_len: usize, start: i64, len: Option<i64>) -> std::ops::Range<usize> {
+    let value_len = value_len as i64;
+    let (mut start, mut len, negative_len) = match len {
+        Some(len) => (start, len.saturating_abs(), len < 0),
+        None => (start, i64::MAX, false),
+    };
+    if start < 0 {
+        start += value_len;
+        if start < 0 {
+            len = len.saturating_add(start).max(0);
+            start = 0;
+        }
+    } else if start > 0 {
+        start -= 1;
+    } else if len > 0 {
+        len -= 1;
+    }
+    if negative_len {
+        start -= len;
+        if start < 0 {
+            len += start;
+            start = 0;
+        }
+    }
+    let start = start.min(value_len);
+    let end = start.saturating_add(len).min(value_len);
+    start as usize..end as usize
+}
+
+/// A real result of a math function, or NULL for NaN like in SQLite.
+fn real_or_null(f: f64) -> OwnedValue {
+    if f.is_nan() {
+        OwnedValue::Null
+    } else {
+        OwnedValue::Float(f)
+    }
+}
+
+/// Parses `text` if, apart from surrounding whitespace, it is entirely an
+/// integer or real literal.
+fn parse_numeric_literal(text: &str) -> Option<OwnedValue> {
+    let (value, len) = parse_numeric_prefix(text)?;
+    if text[len..].trim().is_empty() {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Parses the longest numeric prefix of `text` after any leading whitespace,
+/// returning the value and the number of bytes consumed.
+fn parse_numeric_prefix(text: &str) -> Option<(OwnedValue, usize)> {
+    let bytes = text.as_bytes();
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let start = text.len() - text.trim_start().len();
+    let mut i = start;
+    if i < bytes.len() && matches!(bytes[i], b'+' | b'-') {
+        i += 1;
+    }
+    let int_end = digits(i);
+    let mut end = int_end;
+    let mut has_digits = int_end > i;
+    let mut is_integral = true;
+    if end < bytes.len() && bytes[end] == b'.' {
+        let frac_end = digits(end + 1);
+        has_digits |= frac_end > end + 1;
+        end = frac_end;
+        is_integral = false;
+    }
+    if !has_digits {
+        return None;
+    }
+    // The exponent only counts if at least one digit follows it
+    if end < bytes.len() && matches!(bytes[end], b'e' | b'E') {
+        let mut exp = end + 1;
+        if exp < bytes.len() && matches!(bytes[exp], b'+' | b'-') {
+            exp += 1;
+        }
+        let exp_end = digits(exp);
+        if exp_end > exp {
+            end = exp_end;
+            is_integral = false;
+        }
+    }
+    let literal = &text[start..end];
+    if is_integral {
+        if let Ok(i) = literal.parse::<i64>() {
+            return Some((OwnedValue::Integer(i), end));
+        }
+    }
+    let f = literal.parse::<f64>().ok()?;
+    Some((OwnedValue::Float(f), end))
+}
+
+/// Parses the longest integer prefix of `text` after any leading whitespace,
+/// saturating at the i64 bounds. Returns 0 if there is no such prefix.
+fn parse_integer_prefix(text: &str) -> i64 {
+    let text = text.trim_start();
+    let (negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let mut value: i64 = 0;
+    for b in digits.bytes().take_while(|b| b.is_ascii_digit()) {
+        let digit = (b - b'0') as i64;
+        value = match value.checked_mul(10).and_then(|v| {
+            if negative {
+                v.checked_sub(digit)
+            } else {
+                v.checked_add(digit)
+            }
+        }) {
+            Some(v) => v,
+            None if negative => return i64::MIN,
+            None => return i64::MAX,
+        };
+    }
+    value
+}
+
+/// Returns the integer equal to `f` if the conversion is lossless, using the same
+/// 51-bit bound as SQLite's `sqlite3RealSameAsInt`.
+fn real_to_integer(f: f64) -> Option<i64> {
+    let i = f as i64;
+    if f == i as f64 && (-(1i64 << 51)..(1i64 << 51)).contains(&i) {
+        Some(i)
+    } else {
+        None
+    }
+}
 
-        let mut header_bytes_buf: Vec<u8> = vec![];
-        if header_size <= 126 {
-            // common case
-            header_size += 1;
+/// Formats `f` the way SQLite's `%!.15g` does when it renders a REAL as text:
+/// at most 15 significant digits with trailing zeros dropped, but always at
+/// least one digit after the decimal point (`1.0`, `1.0e+20`).
+pub(crate) fn format_float(f: f64) -> String {
+    if f.is_nan() {
+        return "NaN".to_string();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "Inf" } else { "-Inf" }.to_string();
+    }
+    // Let the standard library do the rounding, then lay the digits out.
+    let scientific = format!("{:.14e}", f.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+    // -0.0 prints as 0.0, like in SQLite
+    let sign = if f < 0.0 { "-" } else { "" };
+    if (-4..15).contains(&exponent) {
+        let (int_part, frac_part) = if exponent >= 0 {
+            let (int_part, frac_part) = digits.split_at(exponent as usize + 1);
+            (int_part.to_string(), frac_part.to_string())
         } else {
-            todo!("calculate big header size extra bytes");
-            // get header varint len
-            // header_size += n;
-            // if( nVarint<sqlite3VarintLen(nHdr) ) nHdr++;
+            let zeros = "0".repeat((-exponent - 1) as usize);
+            ("0".to_string(), zeros + &digits)
+        };
+        let frac_part = frac_part.trim_end_matches('0');
+        let frac_part = if frac_part.is_empty() { "0" } else { frac_part };
+        format!("{}{}.{}", sign, int_part, frac_part)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let rest = rest.trim_end_matches('0');
+        let rest = if rest.is_empty() { "0" } else { rest };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        format!(
+            "{}{}.{}e{}{:02}",
+            sign,
+            first,
+            rest,
+            exponent_sign,
+            exponent.abs()
+        )
+    }
+}
+
+/// Rounds `f` to `digits` decimal places, halves away from zero, the way
+/// SQLite's `roundFunc` does.
+fn round_real(f: f64, digits: usize) -> f64 {
+    // reals this large have no fractional part
+    if !(-4503599627370496.0..=4503599627370496.0).contains(&f) {
+        return f;
+    }
+    if digits == 0 {
+        return (f + if f < 0.0 { -0.5 } else { 0.5 }) as i64 as f64;
+    }
+    // SQLite prints the value with `%!.*f` and parses it back. That conversion
+    // takes the first 18 significant digits of the exact value and rounds
+    // them half up at the requested decimal place.
+    let printed = format!("{:.17e}", f.abs());
+    let (mantissa, exponent) = printed.split_once('e').unwrap();
+    let exponent: i64 = exponent.parse().unwrap();
+    let significand: Vec<u8> = mantissa.bytes().filter(u8::is_ascii_digit).collect();
+    // the number of significant digits left of the rounding place
+    let kept = exponent + 1 + digits as i64;
+    if kept >= significand.len() as i64 {
+        return f;
+    }
+    if kept < 0 {
+        return 0.0_f64.copysign(f);
+    }
+    let kept = kept as usize;
+    let mut units = significand[..kept]
+        .iter()
+        .fold(0u64, |acc, d| acc * 10 + (d - b'0') as u64);
+    if significand[kept] >= b'5' {
+        units += 1;
+    }
+    let rounded: f64 = format!("{}e-{}", units, digits).parse().unwrap();
+    rounded.copysign(f)
+}
+
+/// Compares `blob` with a blob of `len` zero bytes.
+fn cmp_zeroblob(blob: &[u8], len: usize) -> std::cmp::Ordering {
+    if blob.iter().take(len).any(|b| *b != 0) {
+        std::cmp::Ordering::Greater
+    } else {
+        blob.len().cmp(&len)
+    }
+}
+
+/// Integers and floats compare equal by value, matching `partial_cmp`.
+impl PartialEq for OwnedValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(int_left), Self::Integer(int_right)) => int_left == int_right,
+            (Self::Integer(int_left), Self::Float(float_right)) => {
+                cmp_f64(*int_left as f64, *float_right).is_eq()
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) => {
+                cmp_f64(*float_left, *int_right as f64).is_eq()
+            }
+            (Self::Float(float_left), Self::Float(float_right)) => {
+                cmp_f64(*float_left, *float_right).is_eq()
+            }
+            (Self::Text(text_left), Self::Text(text_right)) => text_left == text_right,
+            (Self::Blob(blob_left), Self::Blob(blob_right)) => blob_left == blob_right,
+            (Self::Blob(blob), Self::Zeroblob(len)) | (Self::Zeroblob(len), Self::Blob(blob)) => {
+                cmp_zeroblob(blob, *len).is_eq()
+            }
+            (Self::Zeroblob(len_left), Self::Zeroblob(len_right)) => len_left == len_right,
+            (Self::Null, Self::Null) => true,
+            (Self::Record(record_left), Self::Record(record_right)) => record_left == record_right,
+            (Self::Agg(a), Self::Agg(b)) => a == b,
+            (Self::Agg(a), other) => *a.final_value() == *other,
+            (other, Self::Agg(b)) => *other == *b.final_value(),
+            _ => false,
         }
-        assert!(header_size <= 126);
-        header_bytes_buf.extend(std::iter::repeat(0).take(9));
-        let n = write_varint(header_bytes_buf.as_mut_slice(), header_size as u64);
-        header_bytes_buf.truncate(n);
-        buf.splice(initial_i..initial_i, header_bytes_buf.iter().cloned());
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum CursorResult<T> {
-    Ok(T),
-    IO,
+/// Integers and floats hash through their `f64` value so that values that
+/// compare equal across the two types hash identically.
+impl Hash for OwnedValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Null => 0u8.hash(state),
+            Self::Integer(i) => hash_numeric(*i as f64, state),
+            Self::Float(f) => hash_numeric(*f, state),
+            Self::Text(t) => {
+                2u8.hash(state);
+                t.hash(state);
+            }
+            Self::Blob(b) => {
+                3u8.hash(state);
+                b.hash(state);
+            }
+            // Hashes like a blob of zeros, without allocating one.
+            Self::Zeroblob(len) => {
+                3u8.hash(state);
+                len.hash(state);
+                let zeros = [0u8; 256];
+                let mut remaining = *len;
+                while remaining > 0 {
+                    let chunk = remaining.min(zeros.len());
+                    state.write(&zeros[..chunk]);
+                    remaining -= chunk;
+                }
+            }
+            Self::Agg(a) => a.final_value().hash(state),
+            Self::Record(r) => r.values.hash(state),
+        }
+    }
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub enum SeekOp {
-    EQ,
-    GE,
-    GT,
+fn hash_numeric<H: Hasher>(f: f64, state: &mut H) {
+    1u8.hash(state);
+    // -0.0 == 0.0 and all NaNs are equal, so each must hash the same
+    let f = if f == 0.0 {
+        0.0
+    } else if f.is_nan() {
+        f64::NAN
+    } else {
+        f
+    };
+    f.to_bits().hash(state);
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub enum SeekKey<'a> {
-    TableRowId(u64),
-    IndexKey(&'a OwnedRecord),
+/// Total order over floats: NaN sorts after every other value and equals
+/// itself, while -0.0 and 0.0 stay equal (unlike `f64::total_cmp`).
+fn cmp_f64(left: f64, right: f64) -> std::cmp::Ordering {
+    left.partial_cmp(&right)
+        .unwrap_or_else(|| left.is_nan().cmp(&right.is_nan()))
 }
 
-pub trait Cursor {
-    fn is_empty(&self) -> bool;
-    fn rewind(&mut self) -> Result<CursorResult<()>>;
-    fn last(&mut self) -> Result<CursorResult<()>>;
-    fn next(&mut self) -> Result<CursorResult<()>>;
-    fn prev(&mut self) -> Result<CursorResult<()>>;
-    fn wait_for_completion(&mut self) -> Result<()>;
-    fn rowid(&self) -> Result<Option<u64>>;
-    fn seek(&mut self, key: SeekKey, op: SeekOp) -> Result<CursorResult<bool>>;
-    fn seek_to_last(&mut self) -> Result<CursorResult<()>>;
-    fn record(&self) -> Result<Ref<Option<OwnedRecord>>>;
-    fn insert(
-        &mut self,
-        key: &OwnedValue,
-        record: &OwnedRecord,
-        moved_before: bool, /* Tells inserter that it doesn't need to traverse in order to find leaf page */
-    ) -> Result<CursorResult<()>>; //
-    fn exists(&mut self, key: &OwnedValue) -> Result<CursorResult<bool>>;
-    fn set_null_flag(&mut self, flag: bool);
-    fn get_null_flag(&self) -> bool;
-    fn btree_create(&mut self, flags: usize) -> u32;
+/// Sort order: NULL first, then numbers, text, blobs and records. See
+/// `OwnedValue::sql_cmp` for predicate comparisons.
+#[allow(clippy::non_canonical_partial_ord_impl)]
+impl PartialOrd<OwnedValue> for OwnedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Integer(int_left), Self::Integer(int_right)) => int_left.partial_cmp(int_right),
+            (Self::Integer(int_left), Self::Float(float_right)) => {
+                Some(cmp_f64(*int_left as f64, *float_right))
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) => {
+                Some(cmp_f64(*float_left, *int_right as f64))
+            }
+            (Self::Float(float_left), Self::Float(float_right)) => {
+                Some(cmp_f64(*float_left, *float_right))
+            }
+            // Numeric vs Text/Blob
+            (
+                Self::Integer(_) | Self::Float(_),
+                Self::Text(_) | Self::Blob(_) | Self::Zeroblob(_),
+            ) => Some(std::cmp::Ordering::Less),
+            (
+                Self::Text(_) | Self::Blob(_) | Self::Zeroblob(_),
+                Self::Integer(_) | Self::Float(_),
+            ) => Some(std::cmp::Ordering::Greater),
+
+            (Self::Text(text_left), Self::Text(text_right)) => text_left.partial_cmp(text_right),
+            // Text vs Blob
+            (Self::Text(_), Self::Blob(_) | Self::Zeroblob(_)) => Some(std::cmp::Ordering::Less),
+            (Self::Blob(_) | Self::Zeroblob(_), Self::Text(_)) => Some(std::cmp::Ordering::Greater),
+
+            (Self::Blob(blob_left), Self::Blob(blob_right)) => blob_left.partial_cmp(blob_right),
+            (Self::Blob(blob), Self::Zeroblob(len)) => Some(cmp_zeroblob(blob, *len)),
+            (Self::Zeroblob(len), Self::Blob(blob)) => Some(cmp_zeroblob(blob, *len).reverse()),
+            (Self::Zeroblob(len_left), Self::Zeroblob(len_right)) => {
+                len_left.partial_cmp(len_right)
+            }
+            (Self::Null, Self::Null) => Some(std::cmp::Ordering::Equal),
+            (Self::Null, _) => Some(std::cmp::Ordering::Less),
+            (_, Self::Null) => Some(std::cmp::Ordering::Greater),
+            (Self::Agg(a), Self::Agg(b)) => a.partial_cmp(b),
+            (Self::Agg(a), other) => (*a.final_value()).partial_cmp(other),
+            (other, Self::Agg(b)) => other.partial_cmp(&*b.final_value()),
+            (Self::Record(record_left), Self::Record(record_right)) => {
+                record_left.partial_cmp(record_right)
+            }
+            // Records sort after all scalar values
+            (Self::Record(_), _) => Some(std::cmp::Ordering::Greater),
+            (_, Self::Record(_)) => Some(std::cmp::Ordering::Less),
+        }
+    }
+}
+
+impl std::cmp::PartialOrd<AggContext> for AggContext {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Self::Avg(a, _), Self::Avg(b, _))
+            | (Self::Sum(a), Self::Sum(b))
+            | (Self::Total(a), Self::Total(b))
+            | (Self::Count(a), Self::Count(b))
+            | (Self::CountDistinct(a, _), Self::CountDistinct(b, _)) => a.partial_cmp(b),
+            (Self::GroupConcat { acc: a, .. }, Self::GroupConcat { acc: b, .. })
+            | (
+                Self::GroupConcatDistinct { acc: a, .. },
+                Self::GroupConcatDistinct { acc: b, .. },
+            ) => a.partial_cmp(b),
+            (Self::Max(a), Self::Max(b)) | (Self::Min(a), Self::Min(b)) => a.partial_cmp(b),
+            _ => self.final_value().partial_cmp(&other.final_value()),
+        }
+    }
+}
+
+impl std::cmp::Eq for OwnedValue {}
+
+impl std::cmp::Ord for OwnedValue {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
+
+/// Compares with a borrowed value without converting either side, in the same
+/// order as comparing two `OwnedValue`s.
+impl PartialOrd<Value<'_>> for OwnedValue {
+    fn partial_cmp(&self, other: &Value<'_>) -> Option<std::cmp::Ordering> {
+        let ordering = match (self, other) {
+            (Self::Agg(a), _) => return (*a.final_value()).partial_cmp(other),
+            (Self::Integer(int_left), Value::Integer(int_right)) => int_left.cmp(int_right),
+            (Self::Integer(int_left), Value::Float(float_right)) => {
+                cmp_f64(*int_left as f64, *float_right)
+            }
+            (Self::Float(float_left), Value::Integer(int_right)) => {
+                cmp_f64(*float_left, *int_right as f64)
+            }
+            (Self::Float(float_left), Value::Float(float_right)) => {
+                cmp_f64(*float_left, *float_right)
+            }
+            (Self::Text(text_left), Value::Text(text_right)) => (**text_left).cmp(*text_right),
+            (Self::Blob(blob_left), Value::Blob(blob_right)) => (**blob_left).cmp(*blob_right),
+            (Self::Zeroblob(len), Value::Blob(blob)) => cmp_zeroblob(blob, *len).reverse(),
+            (Self::Record(record_left), Value::Record(record_right)) => {
+                return record_left.partial_cmp(record_right)
+            }
+            _ => sort_class(self).cmp(&value_sort_class(other)),
+        };
+        Some(ordering)
+    }
+}
+
+impl PartialEq<Value<'_>> for OwnedValue {
+    fn eq(&self, other: &Value<'_>) -> bool {
+        self.partial_cmp(other) == Some(std::cmp::Ordering::Equal)
+    }
+}
+
+/// The rank of a value's storage class in the sort order: NULL, numbers, text,
+/// blobs, records. Aggregates rank as their current result.
+fn sort_class(value: &OwnedValue) -> u8 {
+    match value {
+        OwnedValue::Null => 0,
+        OwnedValue::Integer(_) | OwnedValue::Float(_) => 1,
+        OwnedValue::Text(_) => 2,
+        OwnedValue::Blob(_) | OwnedValue::Zeroblob(_) => 3,
+        OwnedValue::Record(_) => 4,
+        OwnedValue::Agg(agg) => sort_class(&agg.final_value()),
+    }
+}
+
+/// Like `sort_class` for a borrowed value.
+fn value_sort_class(value: &Value<'_>) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Integer(_) | Value::Float(_) => 1,
+        Value::Text(_) => 2,
+        Value::Blob(_) => 3,
+        Value::Record(_) => 4,
+    }
+}
+
+impl std::ops::Add for OwnedValue {
+    type Output = Self;
+
+    /// Numeric addition. Text and blobs are converted to numbers first, and a
+    /// NULL operand yields the other one, which is what SUM and AVG accumulate
+    /// with. Use `concat` for `||`.
+    fn add(self, rhs: Self) -> Self {
+        match (self.into_numeric(), rhs.into_numeric()) {
+            (lhs, Self::Null) => lhs,
+            (Self::Null, rhs) => rhs,
+            (lhs, rhs) => lhs
+                .arithmetic(rhs, i64::checked_add, |l, r| l + r)
+                .unwrap_or_else(|(l, r)| Self::Float(l as f64 + r as f64)),
+        }
+    }
+}
+
+impl std::ops::Add<f64> for OwnedValue {
+    type Output = Self;
+
+    fn add(self, rhs: f64) -> Self {
+        match self {
+            Self::Integer(int_left) => Self::Float(int_left as f64 + rhs),
+            Self::Float(float_left) => Self::Float(float_left + rhs),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl std::ops::Add<i64> for OwnedValue {
+    type Output = Self;
+
+    fn add(self, rhs: i64) -> Self {
+        match self {
+            Self::Integer(int_left) => match int_left.checked_add(rhs) {
+                Some(result) => Self::Integer(result),
+                None => Self::Float(int_left as f64 + rhs as f64),
+            },
+            Self::Float(float_left) => Self::Float(float_left + rhs as f64),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Numbers are updated in place; only a change of type, an overflow or a
+/// non-numeric operand goes through `Add`.
+impl std::ops::AddAssign for OwnedValue {
+    fn add_assign(&mut self, rhs: Self) {
+        match (&mut *self, &rhs) {
+            (Self::Integer(int_left), Self::Integer(int_right)) => {
+                if let Some(result) = int_left.checked_add(*int_right) {
+                    *int_left = result;
+                    return;
+                }
+            }
+            (Self::Float(float_left), Self::Float(float_right)) => {
+                *float_left += float_right;
+                return;
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) => {
+                *float_left += *int_right as f64;
+                return;
+            }
+            _ => {}
+        }
+        *self = self.take() + rhs;
+    }
+}
+
+impl std::ops::AddAssign<i64> for OwnedValue {
+    fn add_assign(&mut self, rhs: i64) {
+        match self {
+            Self::Integer(int_left) => match int_left.checked_add(rhs) {
+                Some(result) => *int_left = result,
+                None => *self = Self::Float(*int_left as f64 + rhs as f64),
+            },
+            Self::Float(float_left) => *float_left += rhs as f64,
+            _ => *self = self.take() + rhs,
+        }
+    }
+}
+
+impl std::ops::AddAssign<f64> for OwnedValue {
+    fn add_assign(&mut self, rhs: f64) {
+        match self {
+            Self::Float(float_left) => *float_left += rhs,
+            _ => *self = self.take() + rhs,
+        }
+    }
+}
+
+impl std::ops::Sub for OwnedValue {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.arithmetic(rhs, i64::checked_sub, |l, r| l - r)
+            .unwrap_or_else(|(l, r)| Self::Float(l as f64 - r as f64))
+    }
+}
+
+impl std::ops::Sub<f64> for OwnedValue {
+    type Output = Self;
+
+    fn sub(self, rhs: f64) -> Self {
+        self - Self::Float(rhs)
+    }
+}
+
+impl std::ops::Sub<i64> for OwnedValue {
+    type Output = Self;
+
+    fn sub(self, rhs: i64) -> Self {
+        self - Self::Integer(rhs)
+    }
+}
+
+impl std::ops::SubAssign for OwnedValue {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.take() - rhs;
+    }
+}
+
+impl std::ops::SubAssign<i64> for OwnedValue {
+    fn sub_assign(&mut self, rhs: i64) {
+        *self = self.take() - rhs;
+    }
+}
+
+impl std::ops::SubAssign<f64> for OwnedValue {
+    fn sub_assign(&mut self, rhs: f64) {
+        *self = self.take() - rhs;
+    }
+}
+
+impl std::ops::Mul for OwnedValue {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.arithmetic(rhs, i64::checked_mul, |l, r| l * r)
+            .unwrap_or_else(|(l, r)| Self::Float(l as f64 * r as f64))
+    }
+}
+
+impl std::ops::MulAssign for OwnedValue {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = self.take() * rhs;
+    }
+}
+
+impl std::ops::Rem for OwnedValue {
+    type Output = Self;
+
+    /// Like SQLite, both operands are truncated to integers before taking the
+    /// remainder and the result is a float if either operand was a float.
+    /// A zero divisor yields NULL.
+    fn rem(self, rhs: Self) -> Self {
+        match (self.into_numeric(), rhs.into_numeric()) {
+            (Self::Null, _) | (_, Self::Null) => Self::Null,
+            (_, Self::Integer(0)) => Self::Null,
+            (Self::Integer(int_left), Self::Integer(int_right)) => {
+                Self::Integer(int_left.wrapping_rem(int_right))
+            }
+            (Self::Integer(int_left), Self::Float(float_right)) => {
+                float_rem(int_left, float_right as i64)
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) => {
+                float_rem(float_left as i64, int_right)
+            }
+            (Self::Float(float_left), Self::Float(float_right)) => {
+                float_rem(float_left as i64, float_right as i64)
+            }
+            _ => Self::Float(0.0),
+        }
+    }
+}
+
+fn float_rem(int_left: i64, int_right: i64) -> OwnedValue {
+    if int_right == 0 {
+        return OwnedValue::Null;
+    }
+    OwnedValue::Float(int_left.wrapping_rem(int_right) as f64)
+}
+
+impl std::ops::RemAssign for OwnedValue {
+    fn rem_assign(&mut self, rhs: Self) {
+        *self = self.take() % rhs;
+    }
+}
+
+impl std::ops::Div for OwnedValue {
+    type Output = Self;
+
+    /// Division by zero yields NULL, like in SQLite.
+    fn div(self, rhs: Self) -> Self {
+        self.divide(rhs)
+            .unwrap_or_else(|(l, r)| Self::Float(l as f64 / r as f64))
+    }
+}
+
+impl std::ops::DivAssign<OwnedValue> for OwnedValue {
+    fn div_assign(&mut self, rhs: Self) {
+        match (&mut *self, &rhs) {
+            (Self::Integer(int_left), Self::Integer(int_right)) => {
+                if let Some(result) = int_left.checked_div(*int_right) {
+                    *int_left = result;
+                    return;
+                }
+            }
+            (Self::Float(float_left), Self::Float(float_right)) if *float_right != 0.0 => {
+                *float_left /= float_right;
+                return;
+            }
+            (Self::Float(float_left), Self::Integer(int_right)) if *int_right != 0 => {
+                *float_left /= *int_right as f64;
+                return;
+            }
+            _ => {}
+        }
+        *self = self.take() / rhs;
+    }
+}
+
+/// Text is coerced numerically and negating `i64::MIN` promotes to a float.
+impl std::ops::Neg for OwnedValue {
+    type Output = OwnedValue;
+
+    fn neg(self) -> Self::Output {
+        match self.into_numeric() {
+            Self::Integer(i) => match i.checked_neg() {
+                Some(i) => Self::Integer(i),
+                None => Self::Float(-(i as f64)),
+            },
+            Self::Float(f) => Self::Float(-f),
+            _ => Self::Null,
+        }
+    }
+}
+
+impl std::ops::BitAnd for OwnedValue {
+    type Output = OwnedValue;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match (self.to_integer_operand(), rhs.to_integer_operand()) {
+            (Some(lhs), Some(rhs)) => Self::Integer(lhs & rhs),
+            _ => Self::Null,
+        }
+    }
+}
+
+impl std::ops::BitOr for OwnedValue {
+    type Output = OwnedValue;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match (self.to_integer_operand(), rhs.to_integer_operand()) {
+            (Some(lhs), Some(rhs)) => Self::Integer(lhs | rhs),
+            _ => Self::Null,
+        }
+    }
+}
+
+/// Bitwise complement, the `~` operator.
+impl std::ops::Not for OwnedValue {
+    type Output = OwnedValue;
+
+    fn not(self) -> Self::Output {
+        match self.to_integer_operand() {
+            Some(i) => Self::Integer(!i),
+            None => Self::Null,
+        }
+    }
+}
+
+/// A negative shift count shifts right, and shifting by 64 or more bits yields 0.
+impl std::ops::Shl for OwnedValue {
+    type Output = OwnedValue;
+
+    fn shl(self, rhs: Self) -> Self::Output {
+        match (self.to_integer_operand(), rhs.to_integer_operand()) {
+            (Some(lhs), Some(rhs)) => Self::Integer(shift_left(lhs, rhs)),
+            _ => Self::Null,
+        }
+    }
+}
+
+/// Shifts are arithmetic: a negative value shifted by 64 or more bits yields -1.
+/// A negative shift count shifts left.
+impl std::ops::Shr for OwnedValue {
+    type Output = OwnedValue;
+
+    fn shr(self, rhs: Self) -> Self::Output {
+        match (self.to_integer_operand(), rhs.to_integer_operand()) {
+            (Some(lhs), Some(rhs)) => Self::Integer(shift_right(lhs, rhs)),
+            _ => Self::Null,
+        }
+    }
+}
+
+fn shift_left(value: i64, count: i64) -> i64 {
+    match count {
+        count if count < 0 => shift_right(value, count.saturating_neg()),
+        count if count >= 64 => 0,
+        count => ((value as u64) << count) as i64,
+    }
+}
+
+fn shift_right(value: i64, count: i64) -> i64 {
+    match count {
+        count if count < 0 => shift_left(value, count.saturating_neg()),
+        count if count >= 64 => {
+            if value < 0 {
+                -1
+            } else {
+                0
+            }
+        }
+        count => value >> count,
+    }
+}
+
+/// Returns the first value that isn't NULL, as `COALESCE` does, or NULL if
+/// there is none.
+#[allow(dead_code)] // COALESCE and IFNULL are compiled to jumps for now
+pub fn coalesce(values: &[OwnedValue]) -> OwnedValue {
+    values
+        .iter()
+        .find(|value| !value.is_null())
+        .cloned()
+        .unwrap_or(OwnedValue::Null)
+}
+
+/// Returns the smallest argument, as the multi-argument `min()` scalar
+/// function does. Unlike the aggregate, any NULL argument makes the result
+/// NULL.
+pub fn scalar_min(args: &[OwnedValue]) -> OwnedValue {
+    if args.iter().any(|arg| arg.is_null()) {
+        return OwnedValue::Null;
+    }
+    args.iter().min().cloned().unwrap_or(OwnedValue::Null)
+}
+
+/// Returns the largest argument, as the multi-argument `max()` scalar
+/// function does. Unlike the aggregate, any NULL argument makes the result
+/// NULL.
+pub fn scalar_max(args: &[OwnedValue]) -> OwnedValue {
+    if args.iter().any(|arg| arg.is_null()) {
+        return OwnedValue::Null;
+    }
+    args.iter().max().cloned().unwrap_or(OwnedValue::Null)
+}
+
+/// One element of a `LIKE` or `GLOB` pattern.
+#[derive(Debug)]
+enum PatternToken {
+    /// `%` or `*`
+    AnyRun,
+    /// `_` or `?`
+    AnyChar,
+    Char(char),
+    /// A `GLOB` set of inclusive character ranges.
+    Set {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// Returns `None` if the pattern ends in the escape character, which makes it
+/// match nothing.
+fn parse_like_pattern(pattern: &str, escape: Option<char>) -> Option<Vec<PatternToken>> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            c if Some(c) == escape => PatternToken::Char(chars.next()?),
+            '%' => PatternToken::AnyRun,
+            '_' => PatternToken::AnyChar,
+            c => PatternToken::Char(c),
+        });
+    }
+    Some(tokens)
+}
+
+/// Returns `None` if a `[` set isn't closed, which makes the pattern match
+/// nothing.
+fn parse_glob_pattern(pattern: &str) -> Option<Vec<PatternToken>> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '*' => PatternToken::AnyRun,
+            '?' => PatternToken::AnyChar,
+            '[' => {
+                let negated = chars.next_if_eq(&'^').is_some();
+                let mut ranges = vec![];
+                // a `]` right after the opening bracket belongs to the set
+                if let Some(c) = chars.next_if_eq(&']') {
+                    ranges.push((c, c));
+                }
+                loop {
+                    let start = chars.next()?;
+                    if start == ']' {
+                        break;
+                    }
+                    // a `-` before the closing bracket is literal
+                    match chars.next_if(|&c| c == '-') {
+                        Some(_) if chars.peek().is_some_and(|&c| c != ']') => {
+                            ranges.push((start, chars.next().unwrap()));
+                        }
+                        Some(dash) => ranges.extend([(start, start), (dash, dash)]),
+                        None => ranges.push((start, start)),
+                    }
+                }
+                PatternToken::Set { negated, ranges }
+            }
+            c => PatternToken::Char(c),
+        });
+    }
+    Some(tokens)
+}
+
+fn match_pattern(tokens: &[PatternToken], text: &str, ignore_ascii_case: bool) -> bool {
+    let matches_char = |token: &PatternToken, c: char| match token {
+        PatternToken::AnyRun | PatternToken::AnyChar => true,
+        PatternToken::Char(expected) if ignore_ascii_case => expected.eq_ignore_ascii_case(&c),
+        PatternToken::Char(expected) => *expected == c,
+        PatternToken::Set { negated, ranges } => {
+            ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c)) != *negated
+        }
+    };
+    let text: Vec<char> = text.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // pattern position after the last `AnyRun` and the text position it extends to
+    let mut backtrack = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(PatternToken::AnyRun) => {
+                p += 1;
+                backtrack = Some((p, t));
+                continue;
+            }
+            Some(token) if matches_char(token, text[t]) => {
+                p += 1;
+                t += 1;
+                continue;
+            }
+            _ => {}
+        }
+        // let the last `AnyRun` swallow one more character and retry
+        let Some((run_end, run_text)) = backtrack else {
+            return false;
+        };
+        p = run_end;
+        t = run_text + 1;
+        backtrack = Some((run_end, t));
+    }
+    tokens[p..]
+        .iter()
+        .all(|token| matches!(token, PatternToken::AnyRun))
+}
+
+pub fn to_value(value: &OwnedValue) -> Result<Value<'_>> {
+    let value = match value {
+        OwnedValue::Null => Value::Null,
+        OwnedValue::Integer(i) => Value::Integer(*i),
+        OwnedValue::Float(f) => Value::Float(*f),
+        OwnedValue::Text(s) => Value::Text(s),
+        OwnedValue::Blob(b) => Value::Blob(b),
+        OwnedValue::Zeroblob(_) => {
+            return Err(LimboError::InternalError(
+                "zeroblob must be materialized before it is read as a Value".to_string(),
+            ))
+        }
+        OwnedValue::Agg(a) => match a.as_ref() {
+            AggContext::Avg(..) => match *a.final_value() {
+                OwnedValue::Float(f) => Value::Float(f),
+                _ => Value::Null,
+            },
+            AggContext::Sum(acc) => match acc {
+                OwnedValue::Integer(i) => Value::Integer(*i),
+                OwnedValue::Float(f) => Value::Float(*f),
+                _ => Value::Float(0.0),
+            },
+            AggContext::Total(acc) => match acc {
+                OwnedValue::Integer(i) => Value::Float(*i as f64),
+                OwnedValue::Float(f) => Value::Float(*f),
+                _ => Value::Float(0.0),
+            },
+            AggContext::Count(count) => to_value(count)?,
+            AggContext::Max(max) => match max {
+                Some(max) => to_value(max)?,
+                None => Value::Null,
+            },
+            AggContext::Min(min) => match min {
+                Some(min) => to_value(min)?,
+                None => Value::Null,
+            },
+            AggContext::GroupConcat { acc, .. } | AggContext::GroupConcatDistinct { acc, .. } => {
+                match acc {
+                    Some(text) => Value::Text(text),
+                    None => Value::Null,
+                }
+            }
+            AggContext::CountDistinct(count, _) => to_value(count)?,
+            AggContext::Variance { .. } => match *a.final_value() {
+                OwnedValue::Float(f) => Value::Float(f),
+                _ => Value::Null,
+            },
+        },
+        OwnedValue::Record(r) => Value::Record(r),
+    };
+    Ok(value)
+}
+
+pub trait FromValue<'a> {
+    fn from_value(value: &Value<'a>) -> Result<Self>
+    where
+        Self: Sized + 'a;
+}
+
+fn type_mismatch(expected: &'static str, found: &Value) -> LimboError {
+    LimboError::TypeMismatch {
+        expected,
+        found: found.type_name(),
+    }
+}
+
+impl<'a> FromValue<'a> for i64 {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Integer(i) => Ok(*i),
+            value => Err(type_mismatch("integer", value)),
+        }
+    }
+}
+
+/// Integers are accepted and converted with `as f64`, which loses precision
+/// for magnitudes above 2^53.
+impl<'a> FromValue<'a> for f64 {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Float(f) => Ok(*f),
+            Value::Integer(i) => Ok(*i as f64),
+            value => Err(type_mismatch("float", value)),
+        }
+    }
+}
+
+/// Zero is false and any other integer is true, like SQLite's truthiness.
+impl<'a> FromValue<'a> for bool {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Integer(i) => Ok(*i != 0),
+            value => Err(type_mismatch("integer", value)),
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for String {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Text(s) => Ok(s.to_string()),
+            value => Err(type_mismatch("text", value)),
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for &'a str {
+    fn from_value(value: &Value<'a>) -> Result<&'a str> {
+        match value {
+            Value::Text(s) => Ok(s),
+            value => Err(type_mismatch("text", value)),
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for Vec<u8> {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Blob(b) => Ok(b.to_vec()),
+            value => Err(type_mismatch("blob", value)),
+        }
+    }
+}
+
+impl<'a> FromValue<'a> for &'a [u8] {
+    fn from_value(value: &Value<'a>) -> Result<&'a [u8]> {
+        match value {
+            Value::Blob(b) => Ok(b),
+            value => Err(type_mismatch("blob", value)),
+        }
+    }
+}
+
+impl<'a, T: FromValue<'a> + 'a> FromValue<'a> for Option<T> {
+    fn from_value(value: &Value<'a>) -> Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            _ => Ok(Some(T::from_value(value)?)),
+        }
+    }
+}
+
+/// Like `FromValue`, but converting between types the way SQLite's
+/// `sqlite3_column_int64()`, `sqlite3_column_double()` and
+/// `sqlite3_column_text()` do, for callers that don't want exact typing.
+/// Use `Option<T>` for columns that can be NULL.
+pub trait FromValueCoerced<'a> {
+    /// Converts the value and also reports whether the conversion lost
+    /// information, like a real with a fractional part read as an integer.
+    fn from_value_coerced_lossy(value: &Value<'a>) -> Result<(Self, bool)>
+    where
+        Self: Sized + 'a;
+
+    fn from_value_coerced(value: &Value<'a>) -> Result<Self>
+    where
+        Self: Sized + 'a,
+    {
+        Self::from_value_coerced_lossy(value).map(|(value, _)| value)
+    }
+}
+
+/// Truncates toward zero, saturating at the i64 bounds. The conversion is
+/// lossy unless the real is a whole number in range.
+fn real_to_i64_lossy(f: f64) -> (i64, bool) {
+    let in_range = (i64::MIN as f64..-(i64::MIN as f64)).contains(&f);
+    (f as i64, !in_range || f.fract() != 0.0)
+}
+
+/// Reals are truncated toward zero, saturating at the i64 bounds, and text
+/// that is a number is parsed. Other text, blobs and NULL are errors.
+impl<'a> FromValueCoerced<'a> for i64 {
+    fn from_value_coerced_lossy(value: &Value<'a>) -> Result<(Self, bool)> {
+        match value {
+            Value::Integer(i) => Ok((*i, false)),
+            Value::Float(f) => Ok(real_to_i64_lossy(*f)),
+            Value::Text(text) => match parse_numeric_literal(text) {
+                Some(OwnedValue::Integer(i)) => Ok((i, false)),
+                Some(OwnedValue::Float(f)) => Ok(real_to_i64_lossy(f)),
+                _ => Err(type_mismatch("integer", value)),
+            },
+            value => Err(type_mismatch("integer", value)),
+        }
+    }
+}
+
+/// Integers and text that is a number are converted. Other text, blobs and
+/// NULL are errors. Integers too large for a real to hold exactly are lossy.
+impl<'a> FromValueCoerced<'a> for f64 {
+    fn from_value_coerced_lossy(value: &Value<'a>) -> Result<(Self, bool)> {
+        let from_integer = |i: i64| (i as f64, i as f64 as i128 != i as i128);
+        match value {
+            Value::Integer(i) => Ok(from_integer(*i)),
+            Value::Float(f) => Ok((*f, false)),
+            Value::Text(text) => match parse_numeric_literal(text) {
+                Some(OwnedValue::Integer(i)) => Ok(from_integer(i)),
+                Some(OwnedValue::Float(f)) => Ok((f, false)),
+                _ => Err(type_mismatch("float", value)),
+            },
+            value => Err(type_mismatch("float", value)),
+        }
+    }
+}
+
+/// Numbers are formatted like when SQLite casts them to text and blobs are
+/// read as (lossy) UTF-8. NULL is an error. Reals whose text doesn't parse
+/// back to the same value and blobs that aren't valid UTF-8 are lossy.
+impl<'a> FromValueCoerced<'a> for String {
+    fn from_value_coerced_lossy(value: &Value<'a>) -> Result<(Self, bool)> {
+        match value {
+            Value::Integer(_) => Ok((value.to_string(), false)),
+            Value::Float(f) => {
+                let text = value.to_string();
+                let lossy = text.parse::<f64>() != Ok(*f);
+                Ok((text, lossy))
+            }
+            Value::Text(text) => Ok((text.to_string(), false)),
+            Value::Blob(blob) => {
+                let text = String::from_utf8_lossy(blob);
+                let lossy = matches!(text, std::borrow::Cow::Owned(_));
+                Ok((text.into_owned(), lossy))
+            }
+            value => Err(type_mismatch("text", value)),
+        }
+    }
+}
+
+impl<'a, T: FromValueCoerced<'a> + 'a> FromValueCoerced<'a> for Option<T> {
+    fn from_value_coerced_lossy(value: &Value<'a>) -> Result<(Self, bool)> {
+        match value {
+            Value::Null => Ok((None, false)),
+            _ => T::from_value_coerced_lossy(value).map(|(value, lossy)| (Some(value), lossy)),
+        }
+    }
+}
+
+/// Turns the `TypeMismatch` of a failed `FromValue` conversion into the
+/// `ConversionError` the `TryFrom<OwnedValue>` impls report.
+fn owned_conversion_error(err: LimboError) -> LimboError {
+    match err {
+        LimboError::TypeMismatch { expected, found } => {
+            LimboError::ConversionError(format!("Expected {} value, found {}", expected, found))
+        }
+        err => err,
+    }
+}
+
+/// Conversions from owned values that follow the `FromValue` impls, so that
+/// `let n: i64 = value.try_into()?` works without going through `to_value`.
+macro_rules! impl_try_from_owned_value {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<&OwnedValue> for $ty {
+                type Error = LimboError;
+
+                fn try_from(value: &OwnedValue) -> Result<Self> {
+                    <$ty>::from_value(&to_value(&value.materialize())?)
+                        .map_err(owned_conversion_error)
+                }
+            }
+
+            impl TryFrom<OwnedValue> for $ty {
+                type Error = LimboError;
+
+                fn try_from(value: OwnedValue) -> Result<Self> {
+                    <$ty>::try_from(&value)
+                }
+            }
+        )*
+    };
+}
+
+impl_try_from_owned_value!(i64, f64, bool, String, Vec<u8>);
+
+impl From<i64> for OwnedValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f64> for OwnedValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+/// Booleans are stored as the integers 0 and 1, like in SQLite.
+impl From<bool> for OwnedValue {
+    fn from(value: bool) -> Self {
+        Self::Integer(value as i64)
+    }
+}
+
+impl From<&str> for OwnedValue {
+    fn from(value: &str) -> Self {
+        Self::Text(Rc::from(value))
+    }
+}
+
+impl From<String> for OwnedValue {
+    fn from(value: String) -> Self {
+        Self::Text(Rc::from(value))
+    }
+}
+
+impl From<Vec<u8>> for OwnedValue {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Blob(Rc::from(value))
+    }
+}
+
+impl<T: Into<OwnedValue>> From<Option<T>> for OwnedValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// Parameters to bind to a statement, either all by position or all by name.
+/// Values are converted with the `From` impls of `OwnedValue`, so
+/// `params.bind(42)?.bind("text")?` works for any supported type.
+/// Statements don't take parameters yet, since variables aren't translated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params {
+    positional: Vec<OwnedValue>,
+    named: Vec<(String, OwnedValue)>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds the next positional parameter. Fails if named parameters were
+    /// bound already.
+    pub fn bind<T: Into<OwnedValue>>(&mut self, value: T) -> Result<&mut Self> {
+        if !self.named.is_empty() {
+            return Err(LimboError::ParameterError(
+                "cannot bind a positional parameter after named ones".to_string(),
+            ));
+        }
+        self.positional.push(value.into());
+        Ok(self)
+    }
+
+    /// Binds the parameter called `name`, which includes its `:`, `@` or `$`
+    /// prefix like in the SQL text. Binding a name again replaces its value.
+    /// Fails if positional parameters were bound already.
+    pub fn bind_named<T: Into<OwnedValue>>(&mut self, name: &str, value: T) -> Result<&mut Self> {
+        if !self.positional.is_empty() {
+            return Err(LimboError::ParameterError(
+                "cannot bind a named parameter after positional ones".to_string(),
+            ));
+        }
+        if name.len() < 2 || !name.starts_with([':', '@', '$']) {
+            return Err(LimboError::ParameterError(format!(
+                "invalid parameter name {:?}, expected :name, @name or $name",
+                name
+            )));
+        }
+        let value = value.into();
+        match self.named.iter_mut().find(|(bound, _)| bound == name) {
+            Some((_, bound_value)) => *bound_value = value,
+            None => self.named.push((name.to_string(), value)),
+        }
+        Ok(self)
+    }
+
+    /// The positional parameters, in binding order.
+    pub fn positional(&self) -> &[OwnedValue] {
+        &self.positional
+    }
+
+    /// The named parameters with their names, in the order they were first bound.
+    pub fn named(&self) -> &[(String, OwnedValue)] {
+        &self.named
+    }
+
+    /// The value bound to `name`, if any.
+    pub fn get_named(&self, name: &str) -> Option<&OwnedValue> {
+        self.named
+            .iter()
+            .find(|(bound, _)| bound == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positional.len() + self.named.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Record<'a> {
+    pub values: Vec<Value<'a>>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(values: Vec<Value<'a>>) -> Self {
+        Self { values }
+    }
+
+    pub fn get<T: FromValue<'a> + 'a>(&self, idx: usize) -> Result<T> {
+        let value = self
+            .values
+            .get(idx)
+            .ok_or(LimboError::ColumnIndexOutOfRange(idx))?;
+        T::from_value(value)
+    }
+
+    pub fn get_opt<T: FromValue<'a> + 'a>(&self, idx: usize) -> Result<Option<T>> {
+        self.get::<Option<T>>(idx)
+    }
+
+    /// Like `get`, but converting the column with `FromValueCoerced`.
+    pub fn get_coerced<T: FromValueCoerced<'a> + 'a>(&self, idx: usize) -> Result<T> {
+        let value = self
+            .values
+            .get(idx)
+            .ok_or(LimboError::ColumnIndexOutOfRange(idx))?;
+        T::from_value_coerced(value)
+    }
+
+    /// Like `get_coerced`, but also returning whether the conversion lost
+    /// information.
+    pub fn get_coerced_lossy<T: FromValueCoerced<'a> + 'a>(&self, idx: usize) -> Result<(T, bool)> {
+        let value = self
+            .values
+            .get(idx)
+            .ok_or(LimboError::ColumnIndexOutOfRange(idx))?;
+        T::from_value_coerced_lossy(value)
+    }
+
+    /// Iterates over the values of the record in column order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Value<'a>> {
+        self.values.iter()
+    }
+
+    /// The number of columns in the record.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r Record<'a> {
+    type Item = &'r Value<'a>;
+    type IntoIter = std::slice::Iter<'r, Value<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OwnedRecord {
+    pub values: Vec<OwnedValue>,
+}
+
+impl OwnedRecord {
+    pub fn new(values: Vec<OwnedValue>) -> Self {
+        Self { values }
+    }
+
+    /// An empty record with room for `capacity` columns, to be filled with
+    /// `push` and `extend_from_slice`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends a column to the record.
+    pub fn push(&mut self, value: OwnedValue) {
+        self.values.push(value);
+    }
+
+    /// Appends clones of `values` as columns to the record.
+    pub fn extend_from_slice(&mut self, values: &[OwnedValue]) {
+        self.values.extend_from_slice(values);
+    }
+
+    /// Iterates over the values of the record in column order.
+    pub fn iter(&self) -> std::slice::Iter<'_, OwnedValue> {
+        self.values.iter()
+    }
+
+    /// The number of columns in the record.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// A record of the columns at `indices`, in that order. Columns can be
+    /// repeated. Text and blobs share their contents with this record.
+    ///
+    /// Panics if an index is out of range, which is a bug in the caller: the
+    /// columns of a projection are known when the statement is compiled.
+    pub fn project(&self, indices: &[usize]) -> OwnedRecord {
+        let values = indices
+            .iter()
+            .map(|&idx| match self.values.get(idx) {
+                Some(value) => value.clone(),
+                None => panic!(
+                    "column index {} out of range for a record of {} columns",
+                    idx,
+                    self.len()
+                ),
+            })
+            .collect();
+        OwnedRecord::new(values)
+    }
+
+    /// Compares two records column by column, using `key_info[i]` for the sort
+    /// order and collation of column `i`. Columns past the end of `key_info`, like
+    /// the rowid at the end of an index record, sort ascending with binary
+    /// collation. If one record is a prefix of the other, the shorter sorts first,
+    /// the same as the derived `Ord`.
+    pub fn compare(&self, other: &Self, key_info: &[SortColumn]) -> std::cmp::Ordering {
+        self.compare_prefix(other, key_info)
+            .then(self.len().cmp(&other.len()))
+    }
+
+    /// Compares the record with a seek key on the first `probe.len()`
+    /// columns only, so the columns the probe leaves out, including the rowid of
+    /// an index record, don't take part. When the probe is a proper prefix of the
+    /// record and those columns match the result is `Equal`, which lets the seek
+    /// op break the tie: GE and EQ land on the first record with that prefix and
+    /// GT skips past all of them. `key_info` is used as in `compare`.
+    pub fn compare_prefix(
+        &self,
+        probe: &OwnedRecord,
+        key_info: &[SortColumn],
+    ) -> std::cmp::Ordering {
+        for (i, (left, right)) in self.values.iter().zip(probe.values.iter()).enumerate() {
+            let ordering = match key_info.get(i) {
+                Some(column) => column.compare(left, right),
+                None => left.cmp(right),
+            };
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+        std::cmp::Ordering::Equal
+    }
+
+    /// Whether two records have the same columns apart from their last one,
+    /// like two index entries with the same key but different rowids. Both
+    /// records must have the same number of columns. Columns are compared
+    /// like `==`, so NULL equals NULL and text uses binary collation. Unlike
+    /// `compare_prefix`, which only looks at as many columns as the probe has,
+    /// this never treats a shorter record as a match, and a UNIQUE check has
+    /// to skip keys holding NULL itself, since those never conflict.
+    pub fn eq_ignoring_last(&self, other: &Self) -> bool {
+        let key_len = self.len().saturating_sub(1);
+        self.len() == other.len() && self.values[..key_len] == other.values[..key_len]
+    }
+
+    /// Decodes a record in the SQLite record format, as written by `serialize`.
+    /// Malformed input is a `LimboError::Corrupt` error.
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        read_record(buf)
+    }
+
+    /// Appends the record to `buf`. The buffer grows once, to the exact size of
+    /// the record, and the header and the body are written in place.
+    pub fn serialize(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        let (header_len, body_len) = self.header_and_body_len();
+        buf.resize(start + header_len + body_len, 0);
+        let (header, body) = buf[start..].split_at_mut(header_len);
+        self.write_parts(header, body);
+    }
+
+    /// Writes the record at the start of `dst`, header first, and returns the
+    /// number of bytes written. Fails if `dst` is shorter than `serialized_size`.
+    pub fn serialize_into(&self, dst: &mut [u8]) -> Result<usize> {
+        let (header_len, body_len) = self.header_and_body_len();
+        if dst.len() < header_len + body_len {
+            return Err(LimboError::InternalError(format!(
+                "record needs {} bytes but the buffer has {}",
+                header_len + body_len,
+                dst.len()
+            )));
+        }
+        let (header, body) = dst.split_at_mut(header_len);
+        Ok(header_len + self.write_parts(header, body))
+    }
+
+    /// The header and the body of the record as separate buffers, so they can
+    /// be placed independently. `serialize` writes the two back to back.
+    pub fn serialize_parts(&self) -> (Vec<u8>, Vec<u8>) {
+        let (header_len, body_len) = self.header_and_body_len();
+        let mut header = vec![0; header_len];
+        let mut body = vec![0; body_len];
+        self.write_parts(&mut header, &mut body);
+        (header, body)
+    }
+
+    /// Writes the header, which must be exactly `header_len` bytes, and the
+    /// body, which must be large enough. Returns the size of the body.
+    fn write_parts(&self, header: &mut [u8], body: &mut [u8]) -> usize {
+        let mut header_pos = write_varint(header, header.len() as u64);
+        let mut body_pos = 0;
+        for value in &self.values {
+            header_pos += write_varint(&mut header[header_pos..], value.serial_type());
+            if let OwnedValue::Zeroblob(len) = value {
+                body[body_pos..body_pos + len].fill(0);
+                body_pos += len;
+                continue;
+            }
+            let payload: &[u8] = match value {
+                OwnedValue::Null => &[],
+                OwnedValue::Integer(i) => {
+                    let len = integer_serial_type_len(integer_serial_type(*i));
+                    &i.to_be_bytes()[8 - len..]
+                }
+                OwnedValue::Float(f) => &f.to_be_bytes(),
+                OwnedValue::Text(t) => t.as_bytes(),
+                OwnedValue::Blob(b) => b,
+                OwnedValue::Zeroblob(_) => unreachable!(),
+                // non serializable
+                OwnedValue::Agg(_) => unreachable!(),
+                OwnedValue::Record(_) => unreachable!(),
+            };
+            body[body_pos..body_pos + payload.len()].copy_from_slice(payload);
+            body_pos += payload.len();
+        }
+        debug_assert_eq!(header_pos, header.len());
+        body_pos
+    }
+
+    /// The number of bytes `serialize` appends for this record, computed without
+    /// serializing it.
+    pub fn serialized_size(&self) -> usize {
+        let (header_len, body_len) = self.header_and_body_len();
+        header_len + body_len
+    }
+
+    /// The size of the record header, including the varint holding it, and the
+    /// size of the body, from a single pass over the values.
+    fn header_and_body_len(&self) -> (usize, usize) {
+        let mut serial_types_len = 0;
+        let mut body_len = 0;
+        for value in &self.values {
+            let serial_type = value.serial_type();
+            serial_types_len += varint_len(serial_type);
+            body_len += serial_type_payload_len(serial_type);
+        }
+        (header_size(serial_types_len), body_len)
+    }
+}
+
+impl<'r> IntoIterator for &'r OwnedRecord {
+    type Item = &'r OwnedValue;
+    type IntoIter = std::slice::Iter<'r, OwnedValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Number of payload bytes used by a serial type returned by `OwnedValue::serial_type`.
+fn serial_type_payload_len(serial_type: u64) -> usize {
+    match serial_type {
+        0 => 0,
+        7 => 8,
+        n if n >= 12 => ((n - 12) / 2) as usize,
+        n => integer_serial_type_len(n),
+    }
+}
+
+/// The size of a record header whose serial types take `serial_types_len` bytes.
+fn header_size(serial_types_len: usize) -> usize {
+    // The header size includes the varint encoding the header size itself. Growing the
+    // header by that varint can push it past a varint length boundary, in which case
+    // one more byte is needed.
+    let mut header_size = serial_types_len + varint_len(serial_types_len as u64);
+    if varint_len(header_size as u64) > header_size - serial_types_len {
+        header_size += 1;
+    }
+    header_size
+}
+
+/// Returns the smallest integer serial type that can hold `i`, following
+/// `sqlite3VdbeSerialType`. The constants 0 and 1 use serial types 8 and 9,
+/// which have no payload bytes.
+fn integer_serial_type(i: i64) -> u64 {
+    const MAX_6BYTE: u64 = 0x00007fff_ffffffff;
+    if i == 0 || i == 1 {
+        return 8 + i as u64;
+    }
+    let u = if i < 0 { !i as u64 } else { i as u64 };
+    match u {
+        0..=127 => 1,
+        128..=32767 => 2,
+        32768..=8388607 => 3,
+        8388608..=2147483647 => 4,
+        2147483648..=MAX_6BYTE => 5,
+        _ => 6,
+    }
+}
+
+/// Number of payload bytes used by an integer serial type.
+fn integer_serial_type_len(serial_type: u64) -> usize {
+    match serial_type {
+        8 | 9 => 0,
+        1..=4 => serial_type as usize,
+        5 => 6,
+        6 => 8,
+        _ => unreachable!("not an integer serial type: {}", serial_type),
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum CursorResult<T> {
+    Ok(T),
+    /// The operation has to be retried once the I/O it started or is waiting
+    /// for completes.
+    IO(PendingIo),
+}
+
+/// The I/O a `CursorResult::IO` operation is waiting for, so that a caller can
+/// wait for that completion instead of retrying blindly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingIo {
+    /// The page that is being read or is locked by in-flight I/O.
+    pub page_id: usize,
+}
+
+impl PendingIo {
+    pub fn page(page_id: usize) -> Self {
+        Self { page_id }
+    }
+}
+
+impl<T> CursorResult<T> {
+    /// Transforms the value of a completed operation, passing `IO` through.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> CursorResult<U> {
+        match self {
+            Self::Ok(v) => CursorResult::Ok(f(v)),
+            Self::IO(pending) => CursorResult::IO(pending),
+        }
+    }
+
+    /// Chains an operation that may itself need I/O after a completed one.
+    #[allow(dead_code)]
+    pub fn and_then<U>(self, f: impl FnOnce(T) -> CursorResult<U>) -> CursorResult<U> {
+        match self {
+            Self::Ok(v) => f(v),
+            Self::IO(pending) => CursorResult::IO(pending),
+        }
+    }
+
+    /// Whether the operation is waiting for I/O and has to be retried.
+    pub fn is_io(&self) -> bool {
+        matches!(self, Self::IO(_))
+    }
+
+    /// The I/O the operation is waiting for, or `None` if it completed.
+    pub fn pending_io(&self) -> Option<PendingIo> {
+        match self {
+            Self::Ok(_) => None,
+            Self::IO(pending) => Some(*pending),
+        }
+    }
+
+    /// The value of a completed operation, or `None` if it's waiting for I/O.
+    #[allow(dead_code)]
+    pub fn ok(self) -> Option<T> {
+        match self {
+            Self::Ok(v) => Some(v),
+            Self::IO(_) => None,
+        }
+    }
+}
+
+/// Sort order and collation of one column of an index key, as used by
+/// `OwnedRecord::compare`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortColumn {
+    pub order: Order,
+    pub collation: Collation,
+}
+
+impl SortColumn {
+    /// Compares two values of this column. The collation only applies when both
+    /// are text; other pairs use `OwnedValue`'s ordering.
+    pub fn compare(&self, left: &OwnedValue, right: &OwnedValue) -> std::cmp::Ordering {
+        let ordering = left
+            .compare_with_collation(right, self.collation)
+            .unwrap_or_else(|| left.cmp(right));
+        match self.order {
+            Order::Ascending => ordering,
+            Order::Descending => ordering.reverse(),
+        }
+    }
+
+    /// Like `compare`, with the left value borrowed from a record payload.
+    pub fn compare_value(&self, left: &Value<'_>, right: &OwnedValue) -> std::cmp::Ordering {
+        let ordering = match (left, right) {
+            (Value::Text(text_left), OwnedValue::Text(text_right)) => {
+                self.collation.compare(text_left, text_right)
+            }
+            _ => right.partial_cmp(left).unwrap().reverse(),
+        };
+        match self.order {
+            Order::Ascending => ordering,
+            Order::Descending => ordering.reverse(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SeekOp {
+    EQ,
+    GE,
+    GT,
+    /// Positions on the largest key less than or equal to the seek key.
+    #[allow(dead_code)] // not emitted by the VDBE yet
+    LE,
+    /// Positions on the largest key less than the seek key.
+    LT,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SeekKey<'a> {
+    TableRowId(u64),
+    /// A complete index key, compared on every column including the rowid.
+    IndexKey(&'a OwnedRecord),
+    /// The leading columns of an index key. Entries that match on those columns
+    /// compare equal, so `SeekOp::GE` lands on the first of them and
+    /// `SeekOp::GT` skips them all.
+    IndexPrefix(&'a OwnedRecord),
+}
+
+pub trait Cursor {
+    fn is_empty(&self) -> bool;
+    fn rewind(&mut self) -> Result<CursorResult<()>>;
+    fn last(&mut self) -> Result<CursorResult<()>>;
+    fn next(&mut self) -> Result<CursorResult<()>>;
+    fn prev(&mut self) -> Result<CursorResult<()>>;
+    fn wait_for_completion(&mut self) -> Result<()>;
+    fn rowid(&self) -> Result<Option<u64>>;
+    fn seek(&mut self, key: SeekKey, op: SeekOp) -> Result<CursorResult<bool>>;
+    fn seek_to_last(&mut self) -> Result<CursorResult<()>>;
+    fn record(&self) -> Result<Ref<Option<OwnedRecord>>>;
+    fn insert(
+        &mut self,
+        key: &OwnedValue,
+        record: &OwnedRecord,
+        moved_before: bool, /* Tells inserter that it doesn't need to traverse in order to find leaf page */
+    ) -> Result<CursorResult<()>>; //
+    /// Inserts `rows` of `(key, record)` in order. Runs of increasing keys go
+    /// into the same leaf without descending from the root for every row.
+    #[allow(dead_code)] // not used by the VDBE until bulk inserts are compiled to it
+    fn insert_batch(&mut self, rows: &[(OwnedValue, OwnedRecord)]) -> Result<CursorResult<()>>;
+    /// Removes the row the cursor points at. Afterwards the cursor is not on a
+    /// row, and `next()` moves it to the row that followed the deleted one.
+    /// Fails if the cursor isn't positioned on a row.
+    #[allow(dead_code)] // not used by the VDBE until DELETE is supported
+    fn delete(&mut self) -> Result<CursorResult<()>>;
+    /// Replaces the record of the row the cursor points at, keeping its rowid.
+    /// The cursor stays on the row. Fails if the cursor isn't positioned on a
+    /// table row.
+    #[allow(dead_code)] // not used by the VDBE until UPDATE is supported
+    fn update(&mut self, record: &OwnedRecord) -> Result<CursorResult<()>>;
+    /// Counts the rows of the table or the entries of the index the cursor runs
+    /// over. The cursor keeps its position.
+    #[allow(dead_code)] // not emitted by the VDBE yet
+    fn count(&mut self) -> Result<CursorResult<u64>>;
+    /// Looks up the row with rowid `key`. This moves the cursor: if the row exists
+    /// the cursor is positioned on it, so `record()` and `rowid()` return it, and
+    /// otherwise the cursor is left on no row.
+    fn exists(&mut self, key: &OwnedValue) -> Result<CursorResult<bool>>;
+    fn set_null_flag(&mut self, flag: bool);
+    fn get_null_flag(&self) -> bool;
+    fn btree_create(&mut self, flags: usize) -> u32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn hash_of(v: &OwnedValue) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_sub() {
+        assert_eq!(
+            OwnedValue::Integer(5) - OwnedValue::Integer(3),
+            OwnedValue::Integer(2)
+        );
+        assert_eq!(
+            OwnedValue::Integer(5) - OwnedValue::Float(0.5),
+            OwnedValue::Float(4.5)
+        );
+        assert_eq!(OwnedValue::Float(1.5) - 1i64, OwnedValue::Float(0.5));
+        assert_eq!(OwnedValue::Null - OwnedValue::Integer(1), OwnedValue::Null);
+        assert_eq!(OwnedValue::Integer(1) - OwnedValue::Null, OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Text(Rc::from("10")) - OwnedValue::Integer(4),
+            OwnedValue::Integer(6)
+        );
+        assert_eq!(
+            OwnedValue::Text(Rc::from("abc")) - OwnedValue::Float(1.0),
+            OwnedValue::Float(-1.0)
+        );
+
+        let mut value = OwnedValue::Integer(10);
+        value -= 3i64;
+        value -= OwnedValue::Integer(2);
+        assert_eq!(value, OwnedValue::Integer(5));
+    }
+
+    #[test]
+    fn test_mul() {
+        assert_eq!(
+            OwnedValue::Integer(6) * OwnedValue::Integer(7),
+            OwnedValue::Integer(42)
+        );
+        assert_eq!(
+            OwnedValue::Integer(3) * OwnedValue::Float(0.5),
+            OwnedValue::Float(1.5)
+        );
+        assert_eq!(OwnedValue::Null * OwnedValue::Integer(2), OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Text(Rc::from("2.5")) * OwnedValue::Integer(2),
+            OwnedValue::Float(5.0)
+        );
+
+        let mut value = OwnedValue::Integer(2);
+        value *= OwnedValue::Integer(3);
+        assert_eq!(value, OwnedValue::Integer(6));
+    }
+
+    #[test]
+    fn test_rem() {
+        assert_eq!(
+            OwnedValue::Integer(7) % OwnedValue::Integer(3),
+            OwnedValue::Integer(1)
+        );
+        assert_eq!(
+            OwnedValue::Integer(-7) % OwnedValue::Integer(3),
+            OwnedValue::Integer(-1)
+        );
+        assert_eq!(
+            OwnedValue::Float(7.5) % OwnedValue::Integer(2),
+            OwnedValue::Float(1.0)
+        );
+        assert_eq!(
+            OwnedValue::Integer(7) % OwnedValue::Integer(0),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Integer(7) % OwnedValue::Float(0.5),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MIN) % OwnedValue::Integer(-1),
+            OwnedValue::Integer(0)
+        );
+        assert_eq!(OwnedValue::Null % OwnedValue::Integer(2), OwnedValue::Null);
+
+        let mut value = OwnedValue::Integer(10);
+        value %= OwnedValue::Integer(4);
+        assert_eq!(value, OwnedValue::Integer(2));
+    }
+
+    #[test]
+    fn test_integer_overflow_promotes_to_float() {
+        assert_eq!(
+            OwnedValue::Integer(i64::MAX) + OwnedValue::Integer(1),
+            OwnedValue::Float(i64::MAX as f64 + 1.0)
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MAX) + 1i64,
+            OwnedValue::Float(i64::MAX as f64 + 1.0)
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MIN) - OwnedValue::Integer(1),
+            OwnedValue::Float(i64::MIN as f64 - 1.0)
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MAX) * OwnedValue::Integer(2),
+            OwnedValue::Float(i64::MAX as f64 * 2.0)
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MAX - 1) + OwnedValue::Integer(1),
+            OwnedValue::Integer(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_div_by_zero() {
+        assert_eq!(
+            OwnedValue::Integer(5) / OwnedValue::Integer(0),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Float(5.0) / OwnedValue::Float(0.0),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Integer(5) / OwnedValue::Float(0.0),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Float(5.0) / OwnedValue::Float(-0.0),
+            OwnedValue::Null
+        );
+
+        let mut value = OwnedValue::Integer(5);
+        value /= OwnedValue::Integer(0);
+        assert_eq!(value, OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_div() {
+        assert_eq!(
+            OwnedValue::Integer(7) / OwnedValue::Integer(2),
+            OwnedValue::Integer(3)
+        );
+        assert_eq!(
+            OwnedValue::Integer(7) / OwnedValue::Float(2.0),
+            OwnedValue::Float(3.5)
+        );
+        assert_eq!(
+            OwnedValue::Integer(i64::MIN) / OwnedValue::Integer(-1),
+            OwnedValue::Float(-(i64::MIN as f64))
+        );
+        assert_eq!(OwnedValue::Null / OwnedValue::Integer(2), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_from_value_f64_and_bool() {
+        assert_eq!(f64::from_value(&Value::Float(1.5)).unwrap(), 1.5);
+        assert_eq!(f64::from_value(&Value::Integer(3)).unwrap(), 3.0);
+        assert!(f64::from_value(&Value::Null).is_err());
+
+        assert!(!bool::from_value(&Value::Integer(0)).unwrap());
+        assert!(bool::from_value(&Value::Integer(1)).unwrap());
+        assert!(bool::from_value(&Value::Integer(-7)).unwrap());
+        assert!(bool::from_value(&Value::Float(1.0)).is_err());
+    }
+
+    #[test]
+    fn test_from_value_option() {
+        assert_eq!(Option::<i64>::from_value(&Value::Null).unwrap(), None);
+        assert_eq!(
+            Option::<i64>::from_value(&Value::Integer(7)).unwrap(),
+            Some(7)
+        );
+        let text = "limbo".to_string();
+        assert_eq!(
+            Option::<String>::from_value(&Value::Text(&text)).unwrap(),
+            Some("limbo".to_string())
+        );
+        assert!(Option::<i64>::from_value(&Value::Text(&text)).is_err());
+    }
+
+    #[test]
+    fn test_from_value_blob() {
+        let blob = vec![1u8, 2, 3];
+        assert_eq!(Vec::<u8>::from_value(&Value::Blob(&blob)).unwrap(), blob);
+        assert_eq!(
+            <&[u8]>::from_value(&Value::Blob(&blob)).unwrap(),
+            &[1u8, 2, 3][..]
+        );
+        assert!(Vec::<u8>::from_value(&Value::Integer(1)).is_err());
+    }
+
+    #[test]
+    fn test_from_value_coerced() {
+        assert_eq!(i64::from_value_coerced(&Value::Float(2.9)).unwrap(), 2);
+        assert_eq!(i64::from_value_coerced(&Value::Float(-2.9)).unwrap(), -2);
+        assert_eq!(
+            i64::from_value_coerced(&Value::Float(1e30)).unwrap(),
+            i64::MAX
+        );
+        assert_eq!(i64::from_value_coerced(&Value::Text(" 42 ")).unwrap(), 42);
+        assert_eq!(i64::from_value_coerced(&Value::Text("4.5e1")).unwrap(), 45);
+        assert!(i64::from_value_coerced(&Value::Text("42abc")).is_err());
+        assert!(i64::from_value_coerced(&Value::Blob(&[1])).is_err());
+        assert!(i64::from_value_coerced(&Value::Null).is_err());
+
+        assert_eq!(f64::from_value_coerced(&Value::Integer(3)).unwrap(), 3.0);
+        assert_eq!(f64::from_value_coerced(&Value::Text("-1.5")).unwrap(), -1.5);
+        assert!(f64::from_value_coerced(&Value::Text("")).is_err());
+
+        assert_eq!(
+            String::from_value_coerced(&Value::Integer(-7)).unwrap(),
+            "-7"
+        );
+        assert_eq!(
+            String::from_value_coerced(&Value::Float(2.0)).unwrap(),
+            "2.0"
+        );
+        assert_eq!(
+            String::from_value_coerced(&Value::Text("limbo")).unwrap(),
+            "limbo"
+        );
+        assert_eq!(
+            String::from_value_coerced(&Value::Blob(b"abc")).unwrap(),
+            "abc"
+        );
+        assert!(String::from_value_coerced(&Value::Null).is_err());
+
+        assert_eq!(
+            Option::<i64>::from_value_coerced(&Value::Null).unwrap(),
+            None
+        );
+        assert_eq!(
+            Option::<i64>::from_value_coerced(&Value::Text("5")).unwrap(),
+            Some(5)
+        );
+        // the strict conversion still rejects other types
+        assert!(i64::from_value(&Value::Float(2.0)).is_err());
+
+        let record = Record::new(vec![Value::Float(7.5), Value::Null]);
+        assert_eq!(record.get_coerced::<i64>(0).unwrap(), 7);
+        assert_eq!(record.get_coerced::<String>(0).unwrap(), "7.5");
+        assert_eq!(record.get_coerced::<Option<f64>>(1).unwrap(), None);
+        assert!(matches!(
+            record.get_coerced::<i64>(2),
+            Err(LimboError::ColumnIndexOutOfRange(2))
+        ));
+    }
+
+    #[test]
+    fn test_record_get() {
+        let text = "limbo".to_string();
+        let record = Record::new(vec![Value::Integer(1), Value::Text(&text), Value::Null]);
+        assert_eq!(record.get::<i64>(0).unwrap(), 1);
+        assert_eq!(record.get::<&str>(1).unwrap(), "limbo");
+        assert_eq!(record.get_opt::<i64>(2).unwrap(), None);
+        assert_eq!(record.get_opt::<i64>(0).unwrap(), Some(1));
+        assert!(matches!(
+            record.get::<i64>(3),
+            Err(LimboError::ColumnIndexOutOfRange(3))
+        ));
+        assert!(record.get::<i64>(1).is_err());
+    }
+
+    #[test]
+    fn test_record_builder() {
+        let mut record = OwnedRecord::with_capacity(3);
+        assert!(record.is_empty());
+        record.push(OwnedValue::Integer(1));
+        record.extend_from_slice(&[OwnedValue::Text(Rc::from("limbo")), OwnedValue::Null]);
+        record.extend_from_slice(&[]);
+        assert_eq!(
+            record,
+            OwnedRecord::new(vec![
+                OwnedValue::Integer(1),
+                OwnedValue::Text(Rc::from("limbo")),
+                OwnedValue::Null,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_record_eq_ignoring_last() {
+        let record = |values: &[i64]| {
+            OwnedRecord::new(values.iter().map(|v| OwnedValue::Integer(*v)).collect())
+        };
+        assert!(record(&[1, 2, 10]).eq_ignoring_last(&record(&[1, 2, 11])));
+        assert!(!record(&[1, 3, 10]).eq_ignoring_last(&record(&[1, 2, 10])));
+        assert!(!record(&[1, 2]).eq_ignoring_last(&record(&[1, 2, 10])));
+        assert!(record(&[10]).eq_ignoring_last(&record(&[11])));
+        assert!(record(&[]).eq_ignoring_last(&record(&[])));
+        let with_null = |rowid| {
+            OwnedRecord::new(vec![
+                OwnedValue::Null,
+                OwnedValue::Float(1.0),
+                OwnedValue::Integer(rowid),
+            ])
+        };
+        let mut other = with_null(2);
+        other.values[1] = OwnedValue::Integer(1);
+        assert!(with_null(1).eq_ignoring_last(&other));
+    }
+
+    #[test]
+    fn test_record_project() {
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Integer(1),
+            OwnedValue::Text(Rc::from("limbo")),
+            OwnedValue::Null,
+        ]);
+        let projected = record.project(&[2, 1, 1]);
+        assert_eq!(
+            projected.values,
+            vec![
+                OwnedValue::Null,
+                OwnedValue::Text(Rc::from("limbo")),
+                OwnedValue::Text(Rc::from("limbo")),
+            ]
+        );
+        match (&record.values[1], &projected.values[1]) {
+            (OwnedValue::Text(a), OwnedValue::Text(b)) => assert!(Rc::ptr_eq(a, b)),
+            _ => unreachable!(),
+        }
+        assert!(record.project(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "column index 3 out of range")]
+    fn test_record_project_out_of_range() {
+        OwnedRecord::new(vec![OwnedValue::Integer(1)]).project(&[0, 3]);
+    }
+
+    #[test]
+    fn test_record_iter() {
+        let text = "limbo".to_string();
+        let record = Record::new(vec![Value::Integer(1), Value::Text(&text), Value::Null]);
+        let mut seen = vec![];
+        for value in &record {
+            seen.push(value.to_string());
+        }
+        assert_eq!(seen, vec!["1", "limbo", "NULL"]);
+        assert_eq!(
+            record.iter().filter(|v| matches!(v, Value::Null)).count(),
+            1
+        );
+
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Integer(1),
+            OwnedValue::Float(2.5),
+            OwnedValue::Text(Rc::from("x")),
+        ]);
+        let projected: Vec<&OwnedValue> = record.iter().skip(1).collect();
+        assert_eq!(projected, record.values[1..].iter().collect::<Vec<_>>());
+        assert_eq!((&record).into_iter().len(), 3);
+    }
+
+    #[test]
+    fn test_record_len() {
+        let record = Record::new(vec![Value::Integer(1), Value::Null]);
+        assert_eq!(record.len(), 2);
+        assert!(!record.is_empty());
+        assert!(Record::new(vec![]).is_empty());
+
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(1)]);
+        assert_eq!(record.len(), 1);
+        assert!(!record.is_empty());
+        assert_eq!(OwnedRecord::new(vec![]).len(), 0);
+    }
+
+    #[test]
+    fn test_record_serialize_roundtrip() {
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Null,
+            OwnedValue::Integer(0),
+            OwnedValue::Integer(1),
+            OwnedValue::Integer(-42),
+            OwnedValue::Integer(i64::MAX),
+            OwnedValue::Float(3.25),
+            OwnedValue::Text(Rc::from("limbo")),
+            OwnedValue::Blob(Rc::from(vec![0xde, 0xad, 0xbe, 0xef])),
+        ]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        assert_eq!(OwnedRecord::deserialize(&buf).unwrap(), record);
+    }
+
+    #[rstest]
+    #[case(0, &[0x02, 0x08])]
+    #[case(1, &[0x02, 0x09])]
+    #[case(2, &[0x02, 0x01, 0x02])]
+    #[case(-1, &[0x02, 0x01, 0xff])]
+    #[case(127, &[0x02, 0x01, 0x7f])]
+    #[case(-128, &[0x02, 0x01, 0x80])]
+    #[case(128, &[0x02, 0x02, 0x00, 0x80])]
+    #[case(-129, &[0x02, 0x02, 0xff, 0x7f])]
+    #[case(32767, &[0x02, 0x02, 0x7f, 0xff])]
+    #[case(32768, &[0x02, 0x03, 0x00, 0x80, 0x00])]
+    #[case(8388607, &[0x02, 0x03, 0x7f, 0xff, 0xff])]
+    #[case(8388608, &[0x02, 0x04, 0x00, 0x80, 0x00, 0x00])]
+    #[case(2147483647, &[0x02, 0x04, 0x7f, 0xff, 0xff, 0xff])]
+    #[case(2147483648, &[0x02, 0x05, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00])]
+    #[case(-2147483649, &[0x02, 0x05, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff])]
+    #[case((1 << 40) - 1, &[0x02, 0x05, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff])]
+    #[case(1 << 40, &[0x02, 0x05, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00])]
+    #[case(-(1 << 40), &[0x02, 0x05, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00])]
+    #[case(-(1 << 40) - 1, &[0x02, 0x05, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff])]
+    #[case(0x7fff_ffff_ffff, &[0x02, 0x05, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff])]
+    #[case(-0x8000_0000_0000, &[0x02, 0x05, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00])]
+    #[case(-0x8000_0000_0001, &[0x02, 0x06, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff])]
+    #[case(0x8000_0000_0000, &[0x02, 0x06, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00])]
+    #[case(i64::MIN, &[0x02, 0x06, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])]
+    fn test_serialize_integer(#[case] value: i64, #[case] expected: &[u8]) {
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(value)]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        assert_eq!(buf, expected);
+        assert_eq!(OwnedRecord::deserialize(&buf).unwrap(), record);
+    }
+
+    #[test]
+    fn test_serialize_large_header() {
+        let text = OwnedValue::Text(Rc::from("x".repeat(100)));
+        let record = OwnedRecord::new(vec![text; 64]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        // 64 two-byte serial types plus a two-byte header size varint
+        assert_eq!(&buf[..2], &[0x81, 0x02]);
+        assert_eq!(buf.len(), 130 + 64 * 100);
+        assert_eq!(OwnedRecord::deserialize(&buf).unwrap(), record);
+    }
+
+    #[test]
+    fn test_serialize_header_size_varint_boundary() {
+        // 127 serial type bytes plus a one-byte header size would be 128, which no longer
+        // fits in a one-byte varint, so the header grows to 129 bytes.
+        let record = OwnedRecord::new(vec![OwnedValue::Null; 127]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        assert_eq!(&buf[..2], &[0x81, 0x01]);
+        assert_eq!(buf.len(), 129);
+        assert_eq!(OwnedRecord::deserialize(&buf).unwrap(), record);
+
+        let record = OwnedRecord::new(vec![OwnedValue::Null; 126]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        assert_eq!(buf[0], 127);
+        assert_eq!(buf.len(), 127);
+    }
+
+    #[test]
+    fn test_eq_across_numeric_types() {
+        assert_eq!(OwnedValue::Integer(1), OwnedValue::Float(1.0));
+        assert_eq!(OwnedValue::Float(-3.0), OwnedValue::Integer(-3));
+        assert_ne!(OwnedValue::Integer(1), OwnedValue::Float(1.5));
+        assert_ne!(OwnedValue::Integer(1), OwnedValue::Text(Rc::from("1")));
+        assert_ne!(OwnedValue::Null, OwnedValue::Integer(0));
+        assert_eq!(OwnedValue::Null, OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Integer(2).partial_cmp(&OwnedValue::Float(2.0)),
+            Some(std::cmp::Ordering::Equal)
+        );
+        assert_eq!(
+            OwnedRecord::new(vec![OwnedValue::Integer(7)]),
+            OwnedRecord::new(vec![OwnedValue::Float(7.0)])
+        );
+    }
+
+    #[test]
+    fn test_hash_consistent_with_eq() {
+        use std::collections::HashMap;
+
+        assert_eq!(
+            hash_of(&OwnedValue::Integer(1)),
+            hash_of(&OwnedValue::Float(1.0))
+        );
+        assert_eq!(
+            hash_of(&OwnedValue::Float(-0.0)),
+            hash_of(&OwnedValue::Float(0.0))
+        );
+        assert_eq!(
+            hash_of(&OwnedValue::Integer(0)),
+            hash_of(&OwnedValue::Float(-0.0))
+        );
+
+        let mut groups: HashMap<OwnedValue, i64> = HashMap::new();
+        for v in [
+            OwnedValue::Integer(1),
+            OwnedValue::Float(1.0),
+            OwnedValue::Text(Rc::from("1")),
+            OwnedValue::Null,
+            OwnedValue::Null,
+        ] {
+            *groups.entry(v).or_insert(0) += 1;
+        }
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&OwnedValue::Integer(1)], 2);
+        assert_eq!(groups[&OwnedValue::Null], 2);
+    }
+
+    #[test]
+    fn test_sort_with_nan() {
+        let mut values = [
+            OwnedValue::Float(f64::NAN),
+            OwnedValue::Float(1.5),
+            OwnedValue::Integer(3),
+            OwnedValue::Float(f64::NAN),
+            OwnedValue::Float(f64::INFINITY),
+            OwnedValue::Float(-2.0),
+        ];
+        values.sort();
+        assert_eq!(values[0], OwnedValue::Float(-2.0));
+        assert_eq!(values[1], OwnedValue::Float(1.5));
+        assert_eq!(values[2], OwnedValue::Integer(3));
+        assert_eq!(values[3], OwnedValue::Float(f64::INFINITY));
+        assert!(matches!(values[4], OwnedValue::Float(f) if f.is_nan()));
+        assert!(matches!(values[5], OwnedValue::Float(f) if f.is_nan()));
+
+        assert_eq!(OwnedValue::Float(f64::NAN), OwnedValue::Float(f64::NAN));
+        assert!(OwnedValue::Integer(i64::MAX) < OwnedValue::Float(f64::NAN));
+        assert!(OwnedValue::Float(f64::NAN) < OwnedValue::Text(Rc::from("a")));
+    }
+
+    #[test]
+    fn test_cmp_records() {
+        let record = |values| OwnedValue::Record(OwnedRecord::new(values));
+        let small = record(vec![OwnedValue::Integer(1)]);
+        let large = record(vec![OwnedValue::Integer(1), OwnedValue::Null]);
+        assert_eq!(small.cmp(&large), std::cmp::Ordering::Less);
+        assert_eq!(small.cmp(&small.clone()), std::cmp::Ordering::Equal);
+
+        let text = OwnedValue::Text(Rc::from("z"));
+        assert_eq!(small.cmp(&text), std::cmp::Ordering::Greater);
+        assert_eq!(text.cmp(&small), std::cmp::Ordering::Less);
+        assert_eq!(OwnedValue::Null.cmp(&small), std::cmp::Ordering::Less);
+
+        let sum = OwnedValue::Agg(Box::new(AggContext::Sum(OwnedValue::Integer(2))));
+        let count = OwnedValue::Agg(Box::new(AggContext::Count(OwnedValue::Integer(1))));
+        assert_eq!(sum.cmp(&count), std::cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn test_to_value_record() {
+        let value = OwnedValue::Record(OwnedRecord::new(vec![OwnedValue::Integer(1)]));
+        match to_value(&value).unwrap() {
+            Value::Record(r) => assert_eq!(r.values, vec![OwnedValue::Integer(1)]),
+            other => panic!("expected record, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_to_value_zeroblob_is_an_error() {
+        assert!(to_value(&OwnedValue::Zeroblob(4)).is_err());
+        match to_value(&OwnedValue::Zeroblob(4).materialize()).unwrap() {
+            Value::Blob(b) => assert_eq!(b, &[0; 4]),
+            other => panic!("expected blob, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_sql_cmp_null_is_unknown() {
+        use std::cmp::Ordering;
+        assert_eq!(OwnedValue::Null.sql_cmp(&OwnedValue::Null), None);
+        assert_eq!(OwnedValue::Null.sql_cmp(&OwnedValue::Integer(1)), None);
+        assert_eq!(OwnedValue::Integer(1).sql_cmp(&OwnedValue::Null), None);
+        let null_max = OwnedValue::Agg(Box::new(AggContext::Max(None)));
+        assert_eq!(null_max.sql_cmp(&OwnedValue::Integer(1)), None);
+        assert_eq!(
+            OwnedValue::Integer(1).sql_cmp(&OwnedValue::Float(2.0)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(OwnedValue::Null.cmp(&OwnedValue::Null), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_compare_with_collation() {
+        use std::cmp::Ordering;
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(
+            text("abc").compare_with_collation(&text("ABC"), Collation::NoCase),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            text("abc").compare_with_collation(&text("ABC"), Collation::Binary),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            text("abc").compare_with_collation(&text("abd"), Collation::NoCase),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            text("abc  ").compare_with_collation(&text("abc"), Collation::RTrim),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            text(" abc").compare_with_collation(&text("abc"), Collation::RTrim),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            text("abc  ").compare_with_collation(&text("abc"), Collation::Binary),
+            Some(Ordering::Greater)
+        );
+        // Non-ASCII characters are not folded
+        assert_ne!(
+            text("é").compare_with_collation(&text("É"), Collation::NoCase),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            OwnedValue::Integer(1).compare_with_collation(&text("a"), Collation::NoCase),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn test_values_equal() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert!(OwnedValue::Null.values_equal(&OwnedValue::Null));
+        assert!(!OwnedValue::Null.values_equal(&OwnedValue::Integer(0)));
+        assert!(OwnedValue::Integer(1).values_equal(&OwnedValue::Float(1.0)));
+        assert!(OwnedValue::Float(-0.0).values_equal(&OwnedValue::Integer(0)));
+        assert!(!OwnedValue::Integer(1).values_equal(&OwnedValue::Float(1.5)));
+        // both round to the same f64
+        assert!(!OwnedValue::Integer(9007199254740993)
+            .values_equal(&OwnedValue::Float(9007199254740992.0)));
+        assert!(!OwnedValue::Integer(i64::MAX).values_equal(&OwnedValue::Float(i64::MAX as f64)));
+        assert!(OwnedValue::Integer(i64::MIN).values_equal(&OwnedValue::Float(i64::MIN as f64)));
+        assert!(text("a").values_equal(&text("a")));
+        assert!(!text("a").values_equal(&text("A")));
+        assert!(!text("1").values_equal(&OwnedValue::Integer(1)));
+        assert!(!text("a").values_equal(&OwnedValue::Blob(Rc::from(b"a".to_vec()))));
+        assert!(OwnedValue::Zeroblob(2).values_equal(&OwnedValue::Blob(Rc::from(vec![0, 0]))));
+        let max = OwnedValue::Agg(Box::new(AggContext::Max(None)));
+        assert!(max.values_equal(&OwnedValue::Null));
+        assert!(
+            OwnedValue::Integer(3).values_equal(&OwnedValue::Agg(Box::new(AggContext::Max(Some(
+                OwnedValue::Float(3.0)
+            )))))
+        );
+    }
+
+    #[test]
+    fn test_compare_with_affinity() {
+        use std::cmp::Ordering;
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        // converted to a number '10' equals 10, unconverted text sorts after numbers
+        assert_eq!(
+            text("10").compare_with_affinity(&OwnedValue::Integer(10), Affinity::Numeric),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            text(" 10 ").compare_with_affinity(&OwnedValue::Integer(9), Affinity::Integer),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            text("1e1").compare_with_affinity(&OwnedValue::Float(10.0), Affinity::Real),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            text("10").compare_with_affinity(&OwnedValue::Integer(10), Affinity::Blob),
+            Some(Ordering::Greater)
+        );
+        // text that isn't a number stays text, which sorts after numbers
+        assert_eq!(
+            text("10a").compare_with_affinity(&OwnedValue::Integer(10), Affinity::Numeric),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            OwnedValue::Integer(10).compare_with_affinity(&text("9"), Affinity::Text),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            OwnedValue::Float(1.5).compare_with_affinity(&text("1.5"), Affinity::Text),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            OwnedValue::Null.compare_with_affinity(&text("1"), Affinity::Numeric),
+            None
+        );
+        let max = OwnedValue::Agg(Box::new(AggContext::Max(Some(text("7")))));
+        assert_eq!(
+            max.compare_with_affinity(&OwnedValue::Integer(7), Affinity::Numeric),
+            Some(Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn test_affinity_for_comparison() {
+        use Affinity::*;
+        assert_eq!(Affinity::for_comparison(Some(Integer), Some(Text)), Numeric);
+        assert_eq!(Affinity::for_comparison(Some(Blob), Some(Real)), Numeric);
+        assert_eq!(Affinity::for_comparison(Some(Numeric), None), Numeric);
+        assert_eq!(Affinity::for_comparison(None, Some(Text)), Text);
+        assert_eq!(Affinity::for_comparison(Some(Text), Some(Blob)), Blob);
+        assert_eq!(Affinity::for_comparison(Some(Text), Some(Text)), Blob);
+        assert_eq!(Affinity::for_comparison(Some(Blob), None), Blob);
+        assert_eq!(Affinity::for_comparison(None, None), Blob);
+    }
+
+    #[test]
+    fn test_apply_affinity() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(
+            text("123").apply_affinity(Affinity::Numeric),
+            OwnedValue::Integer(123)
+        );
+        assert!(matches!(
+            text("1.5").apply_affinity(Affinity::Numeric),
+            OwnedValue::Float(f) if f == 1.5
+        ));
+        assert!(matches!(
+            text("3.0e+5").apply_affinity(Affinity::Numeric),
+            OwnedValue::Integer(300000)
+        ));
+        assert!(matches!(
+            text(" 42 ").apply_affinity(Affinity::Integer),
+            OwnedValue::Integer(42)
+        ));
+        assert!(matches!(
+            OwnedValue::Float(7.0).apply_affinity(Affinity::Integer),
+            OwnedValue::Integer(7)
+        ));
+        assert!(matches!(
+            OwnedValue::Float(7.5).apply_affinity(Affinity::Integer),
+            OwnedValue::Float(f) if f == 7.5
+        ));
+        assert!(matches!(
+            OwnedValue::Integer(7).apply_affinity(Affinity::Real),
+            OwnedValue::Float(f) if f == 7.0
+        ));
+        assert!(matches!(
+            text("12abc").apply_affinity(Affinity::Numeric),
+            OwnedValue::Text(t) if &*t == "12abc"
+        ));
+        assert!(matches!(
+            text("inf").apply_affinity(Affinity::Real),
+            OwnedValue::Text(t) if &*t == "inf"
+        ));
+        assert!(matches!(
+            OwnedValue::Integer(5).apply_affinity(Affinity::Text),
+            OwnedValue::Text(t) if &*t == "5"
+        ));
+        assert!(matches!(
+            OwnedValue::Integer(5).apply_affinity(Affinity::Blob),
+            OwnedValue::Integer(5)
+        ));
+        assert!(matches!(
+            OwnedValue::Null.apply_affinity(Affinity::Numeric),
+            OwnedValue::Null
+        ));
+    }
+
+    #[test]
+    fn test_text_to_numeric() {
+        let parse = OwnedValue::text_to_numeric;
+        assert_eq!(parse("42"), Some(OwnedValue::Integer(42)));
+        assert_eq!(parse("  -17abc"), Some(OwnedValue::Integer(-17)));
+        assert_eq!(parse("+5"), Some(OwnedValue::Integer(5)));
+        assert!(matches!(parse("1.5xyz"), Some(OwnedValue::Float(f)) if f == 1.5));
+        assert!(matches!(parse(".5"), Some(OwnedValue::Float(f)) if f == 0.5));
+        assert!(matches!(parse("3."), Some(OwnedValue::Float(f)) if f == 3.0));
+        assert!(matches!(parse("1e3"), Some(OwnedValue::Float(f)) if f == 1000.0));
+        assert!(matches!(parse("2.5E-1 "), Some(OwnedValue::Float(f)) if f == 0.25));
+        // An exponent without digits is not part of the number
+        assert_eq!(parse("7e"), Some(OwnedValue::Integer(7)));
+        assert_eq!(parse("7e+x"), Some(OwnedValue::Integer(7)));
+        assert!(matches!(
+            parse("9223372036854775808"),
+            Some(OwnedValue::Float(f)) if f == 9223372036854775808.0
+        ));
+        assert_eq!(
+            parse("-9223372036854775808"),
+            Some(OwnedValue::Integer(i64::MIN))
+        );
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("abc"), None);
+        assert_eq!(parse("-"), None);
+        assert_eq!(parse("."), None);
+        assert_eq!(parse("inf"), None);
+        assert_eq!(parse("NaN"), None);
+    }
+
+    #[test]
+    fn test_arithmetic_on_numeric_text_prefix() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(
+            text("10abc") - OwnedValue::Integer(3),
+            OwnedValue::Integer(7)
+        );
+        assert_eq!(text("abc") * OwnedValue::Integer(3), OwnedValue::Integer(0));
+    }
+
+    #[test]
+    fn test_cast_to() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(
+            OwnedValue::Null.cast_to(CastType::Integer),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            text(" 12.7abc").cast_to(CastType::Integer),
+            OwnedValue::Integer(12)
+        );
+        assert_eq!(
+            text("-1e3").cast_to(CastType::Integer),
+            OwnedValue::Integer(-1)
+        );
+        assert_eq!(
+            text("abc").cast_to(CastType::Integer),
+            OwnedValue::Integer(0)
+        );
+        assert_eq!(
+            text("99999999999999999999").cast_to(CastType::Integer),
+            OwnedValue::Integer(i64::MAX)
+        );
+        assert_eq!(
+            text("-99999999999999999999").cast_to(CastType::Integer),
+            OwnedValue::Integer(i64::MIN)
+        );
+        assert_eq!(
+            OwnedValue::Float(-2.9).cast_to(CastType::Integer),
+            OwnedValue::Integer(-2)
+        );
+        assert_eq!(
+            OwnedValue::Float(1e30).cast_to(CastType::Integer),
+            OwnedValue::Integer(i64::MAX)
+        );
+        assert!(matches!(
+            text("1.5e1x").cast_to(CastType::Real),
+            OwnedValue::Float(f) if f == 15.0
+        ));
+        assert!(matches!(
+            text("").cast_to(CastType::Real),
+            OwnedValue::Float(f) if f == 0.0
+        ));
+        assert_eq!(
+            text("3.0").cast_to(CastType::Numeric),
+            OwnedValue::Integer(3)
+        );
+        assert!(matches!(
+            text("3.5").cast_to(CastType::Numeric),
+            OwnedValue::Float(f) if f == 3.5
+        ));
+        assert!(matches!(
+            OwnedValue::Float(3.0).cast_to(CastType::Numeric),
+            OwnedValue::Float(f) if f == 3.0
+        ));
+        assert_eq!(
+            text("abc").cast_to(CastType::Blob),
+            OwnedValue::Blob(Rc::from(b"abc".as_slice()))
+        );
+        assert_eq!(OwnedValue::Integer(42).cast_to(CastType::Text), text("42"));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"hi".as_slice())).cast_to(CastType::Text),
+            text("hi")
+        );
+    }
+
+    #[rstest]
+    // Expected values from sqlite3
+    #[case(
+        OwnedValue::Integer(12),
+        OwnedValue::Integer(10),
+        OwnedValue::Integer(8),
+        OwnedValue::Integer(14)
+    )]
+    #[case(OwnedValue::Integer(-1), OwnedValue::Integer(255), OwnedValue::Integer(255), OwnedValue::Integer(-1))]
+    #[case(
+        OwnedValue::Float(12.9),
+        OwnedValue::Integer(10),
+        OwnedValue::Integer(8),
+        OwnedValue::Integer(14)
+    )]
+    #[case(
+        OwnedValue::Text(Rc::from("12abc")),
+        OwnedValue::Integer(10),
+        OwnedValue::Integer(8),
+        OwnedValue::Integer(14)
+    )]
+    #[case(
+        OwnedValue::Null,
+        OwnedValue::Integer(10),
+        OwnedValue::Null,
+        OwnedValue::Null
+    )]
+    #[case(
+        OwnedValue::Integer(1),
+        OwnedValue::Null,
+        OwnedValue::Null,
+        OwnedValue::Null
+    )]
+    fn test_bitand_bitor(
+        #[case] lhs: OwnedValue,
+        #[case] rhs: OwnedValue,
+        #[case] and: OwnedValue,
+        #[case] or: OwnedValue,
+    ) {
+        assert_eq!(lhs.clone() & rhs.clone(), and);
+        assert_eq!(lhs | rhs, or);
+    }
+
+    #[rstest]
+    // Expected values from sqlite3
+    #[case(1, 3, 8, 0)]
+    #[case(-16, 2, -64, -4)]
+    #[case(1, 63, i64::MIN, 0)]
+    #[case(1, 64, 0, 0)]
+    #[case(-8, 64, 0, -1)]
+    #[case(-8, 100, 0, -1)]
+    #[case(8, -2, 2, 32)]
+    #[case(-8, -1, -4, -16)]
+    #[case(1, i64::MIN, 0, 0)]
+    fn test_shifts(#[case] value: i64, #[case] count: i64, #[case] shl: i64, #[case] shr: i64) {
+        let value = OwnedValue::Integer(value);
+        let count = OwnedValue::Integer(count);
+        assert_eq!(value.clone() << count.clone(), OwnedValue::Integer(shl));
+        assert_eq!(value >> count, OwnedValue::Integer(shr));
+    }
+
+    #[test]
+    fn test_bitwise_not() {
+        assert_eq!(!OwnedValue::Integer(0), OwnedValue::Integer(-1));
+        assert_eq!(!OwnedValue::Integer(5), OwnedValue::Integer(-6));
+        assert_eq!(!OwnedValue::Float(5.7), OwnedValue::Integer(-6));
+        assert_eq!(!OwnedValue::Text(Rc::from("5")), OwnedValue::Integer(-6));
+        assert_eq!(!OwnedValue::Null, OwnedValue::Null);
+        assert_eq!(OwnedValue::Integer(1) << OwnedValue::Null, OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_neg() {
+        assert_eq!(-OwnedValue::Integer(5), OwnedValue::Integer(-5));
+        assert_eq!(
+            -OwnedValue::Integer(i64::MIN),
+            OwnedValue::Float(9223372036854775808.0)
+        );
+        assert_eq!(-OwnedValue::Float(1.5), OwnedValue::Float(-1.5));
+        assert_eq!(-OwnedValue::Text(Rc::from("5")), OwnedValue::Integer(-5));
+        assert_eq!(-OwnedValue::Text(Rc::from("abc")), OwnedValue::Integer(0));
+        assert_eq!(-OwnedValue::Null, OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_abs() {
+        assert_eq!(OwnedValue::Integer(-5).abs(), OwnedValue::Integer(5));
+        assert_eq!(
+            OwnedValue::Integer(i64::MIN).abs(),
+            OwnedValue::Float(9223372036854775808.0)
+        );
+        assert_eq!(OwnedValue::Float(-1.5).abs(), OwnedValue::Float(1.5));
+        assert_eq!(
+            OwnedValue::Text(Rc::from("-5")).abs(),
+            OwnedValue::Float(5.0)
+        );
+        assert_eq!(OwnedValue::Null.abs(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_round() {
+        let round = |value: OwnedValue, digits: i64| match value.round(digits) {
+            OwnedValue::Float(f) => Some(f),
+            _ => None,
+        };
+        assert_eq!(round(OwnedValue::Float(2.5), 0), Some(3.0));
+        assert_eq!(round(OwnedValue::Float(0.5), 0), Some(1.0));
+        assert_eq!(round(OwnedValue::Float(-2.5), 0), Some(-3.0));
+        assert_eq!(round(OwnedValue::Float(-0.4), 0), Some(0.0));
+        assert_eq!(round(OwnedValue::Float(1.005), 2), Some(1.0));
+        assert_eq!(round(OwnedValue::Float(2.675), 2), Some(2.67));
+        assert_eq!(round(OwnedValue::Float(-1.005), 2), Some(-1.0));
+        assert_eq!(round(OwnedValue::Float(9.995), 2), Some(9.99));
+        assert_eq!(round(OwnedValue::Float(0.125), 2), Some(0.13));
+        assert_eq!(round(OwnedValue::Float(-0.125), 2), Some(-0.13));
+        assert_eq!(round(OwnedValue::Float(0.006), 2), Some(0.01));
+        assert_eq!(round(OwnedValue::Float(0.004), 2), Some(0.0));
+        assert_eq!(round(OwnedValue::Float(99.996), 2), Some(100.0));
+        assert_eq!(round(OwnedValue::Float(0.000123456), 5), Some(0.00012));
+        assert_eq!(round(OwnedValue::Float(123.456), 40), Some(123.456));
+        assert_eq!(round(OwnedValue::Float(1234.5), -2), Some(1235.0));
+        assert_eq!(round(OwnedValue::Float(1e300), 2), Some(1e300));
+        assert_eq!(round(OwnedValue::Integer(5), 0), Some(5.0));
+        assert_eq!(round(OwnedValue::Text(Rc::from("2.71828")), 2), Some(2.72));
+        assert_eq!(round(OwnedValue::Text(Rc::from("abc")), 0), Some(0.0));
+        assert_eq!(
+            round(OwnedValue::Blob(Rc::from(b"1".to_vec())), 0),
+            Some(1.0)
+        );
+        assert_eq!(OwnedValue::Null.round(0), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_agg_total() {
+        let mut total = OwnedValue::Agg(Box::new(AggContext::Total(OwnedValue::Float(0.0))));
+        assert!(matches!(to_value(&total).unwrap(), Value::Float(f) if f == 0.0));
+        for v in [
+            OwnedValue::Integer(2),
+            OwnedValue::Null,
+            OwnedValue::Text(Rc::from("3")),
+        ] {
+            let OwnedValue::Agg(agg) = &mut total else {
+                unreachable!();
+            };
+            let AggContext::Total(acc) = agg.as_mut() else {
+                unreachable!();
+            };
+            *acc += v.into_numeric();
+        }
+        assert!(matches!(to_value(&total).unwrap(), Value::Float(f) if f == 5.0));
+        assert_eq!(total.to_string(), "5.0");
+    }
+
+    #[test]
+    fn test_agg_distinct_final_value() {
+        let seen: HashSet<OwnedValue> = [OwnedValue::Integer(1), OwnedValue::Float(1.0)]
+            .into_iter()
+            .collect();
+        assert_eq!(seen.len(), 1);
+        let count = AggContext::CountDistinct(OwnedValue::Integer(1), seen.clone());
+        assert_eq!(*count.final_value(), OwnedValue::Integer(1));
+        let concat = AggContext::GroupConcatDistinct {
+            acc: Some("1".to_string()),
+            sep: DEFAULT_GROUP_CONCAT_SEPARATOR.to_string(),
+            seen,
+        };
+        assert_eq!(*concat.final_value(), OwnedValue::Text(Rc::from("1")));
+        assert_eq!(OwnedValue::Agg(Box::new(count)).to_string(), "1");
+    }
+
+    #[test]
+    fn test_agg_group_concat_step() {
+        let comma = OwnedValue::Text(Rc::from(","));
+        let mut concat = AggContext::GroupConcat {
+            acc: None,
+            sep: DEFAULT_GROUP_CONCAT_SEPARATOR.to_string(),
+        };
+        concat.step_group_concat(&OwnedValue::Null, &comma);
+        assert_eq!(*concat.final_value(), OwnedValue::Null);
+        concat.step_group_concat(&OwnedValue::Text(Rc::from("")), &comma);
+        concat.step_group_concat(&OwnedValue::Integer(1), &comma);
+        concat.step_group_concat(&OwnedValue::Null, &comma);
+        concat.step_group_concat(&OwnedValue::Float(2.5), &OwnedValue::Null);
+        assert_eq!(*concat.final_value(), OwnedValue::Text(Rc::from(",12.5")));
+    }
+
+    fn sum(values: &[OwnedValue]) -> OwnedValue {
+        let mut ctx = AggContext::Sum(OwnedValue::Null);
+        for v in values {
+            ctx.step_sum(v);
+        }
+        ctx.final_value().into_owned()
+    }
+
+    #[test]
+    fn test_step_sum() {
+        use OwnedValue::{Float, Integer, Null};
+        assert_eq!(sum(&[]), Null);
+        assert_eq!(sum(&[Null, Null]), Null);
+        assert!(matches!(sum(&[Integer(1), Null, Integer(2)]), Integer(3)));
+        assert!(matches!(
+            sum(&[Integer(i64::MAX - 1), Integer(1)]),
+            Integer(i64::MAX)
+        ));
+        assert!(matches!(
+            sum(&[Integer(i64::MAX), Integer(1)]),
+            Float(f) if f == 9223372036854775808.0
+        ));
+        // once real, the sum stays real even if it fits again
+        assert!(matches!(
+            sum(&[Integer(i64::MAX), Integer(1), Integer(-2)]),
+            Float(f) if f == 9223372036854775806.0
+        ));
+        assert!(matches!(
+            sum(&[Integer(i64::MIN), Integer(-1)]),
+            Float(f) if f == -9223372036854775809.0
+        ));
+        assert!(matches!(sum(&[Integer(1), Float(0.5)]), Float(f) if f == 1.5));
+        assert!(matches!(
+            sum(&[OwnedValue::Text(Rc::from("2")), Integer(1)]),
+            Integer(3)
+        ));
+        assert!(matches!(
+            sum(&[OwnedValue::Text(Rc::from("x")), Integer(1)]),
+            Float(f) if f == 1.0
+        ));
+    }
+
+    fn variance(kind: VarianceKind, values: &[OwnedValue]) -> OwnedValue {
+        let mut ctx = AggContext::Variance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            kind,
+        };
+        for v in values {
+            ctx.step_variance(v);
+        }
+        ctx.final_value().into_owned()
+    }
+
+    #[test]
+    fn test_agg_variance() {
+        let data: Vec<OwnedValue> = [2, 4, 4, 4, 5, 5, 7, 9]
+            .iter()
+            .map(|&i| OwnedValue::Integer(i))
+            .chain(std::iter::once(OwnedValue::Null))
+            .collect();
+        assert_eq!(
+            variance(VarianceKind::VarPop, &data),
+            OwnedValue::Float(4.0)
+        );
+        assert_eq!(
+            variance(VarianceKind::StdDevPop, &data),
+            OwnedValue::Float(2.0)
+        );
+        let OwnedValue::Float(var_samp) = variance(VarianceKind::VarSamp, &data) else {
+            panic!("expected float");
+        };
+        assert!((var_samp - 32.0 / 7.0).abs() < 1e-12);
+        let OwnedValue::Float(stddev_samp) = variance(VarianceKind::StdDevSamp, &data) else {
+            panic!("expected float");
+        };
+        assert!((stddev_samp - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_agg_variance_too_few_rows() {
+        let one = [OwnedValue::Float(3.5)];
+        assert_eq!(variance(VarianceKind::VarSamp, &one), OwnedValue::Null);
+        assert_eq!(variance(VarianceKind::VarPop, &one), OwnedValue::Float(0.0));
+        assert_eq!(variance(VarianceKind::VarPop, &[]), OwnedValue::Null);
+        assert_eq!(
+            variance(VarianceKind::StdDevSamp, &[OwnedValue::Null]),
+            OwnedValue::Null
+        );
+    }
+
+    fn stepped(mut ctx: AggContext, values: &[OwnedValue]) -> AggContext {
+        for v in values {
+            ctx.step_variance(v);
+        }
+        ctx
+    }
+
+    #[test]
+    fn test_agg_merge() {
+        let mut sum = AggContext::Sum(OwnedValue::Integer(3));
+        sum.merge(&AggContext::Sum(OwnedValue::Float(1.5))).unwrap();
+        assert_eq!(*sum.final_value(), OwnedValue::Float(4.5));
+
+        let mut sum = AggContext::Sum(OwnedValue::Null);
+        sum.merge(&AggContext::Sum(OwnedValue::Integer(2))).unwrap();
+        assert_eq!(*sum.final_value(), OwnedValue::Integer(2));
+
+        let mut avg = AggContext::Avg(OwnedValue::Float(10.0), OwnedValue::Integer(2));
+        avg.merge(&AggContext::Avg(
+            OwnedValue::Float(5.0),
+            OwnedValue::Integer(3),
+        ))
+        .unwrap();
+        let AggContext::Avg(acc, count) = &avg else {
+            unreachable!();
+        };
+        assert_eq!(*acc, OwnedValue::Float(15.0));
+        assert_eq!(*count, OwnedValue::Integer(5));
+        assert_eq!(*avg.final_value(), OwnedValue::Float(3.0));
+
+        let mut max = AggContext::Max(Some(OwnedValue::Integer(3)));
+        max.merge(&AggContext::Max(Some(OwnedValue::Integer(7))))
+            .unwrap();
+        max.merge(&AggContext::Max(None)).unwrap();
+        assert_eq!(*max.final_value(), OwnedValue::Integer(7));
+
+        let mut min = AggContext::Min(None);
+        min.merge(&AggContext::Min(Some(OwnedValue::Integer(7))))
+            .unwrap();
+        min.merge(&AggContext::Min(Some(OwnedValue::Integer(3))))
+            .unwrap();
+        assert_eq!(*min.final_value(), OwnedValue::Integer(3));
+
+        let mut concat = AggContext::GroupConcat {
+            acc: Some("a,b".to_string()),
+            sep: ",".to_string(),
+        };
+        concat
+            .merge(&AggContext::GroupConcat {
+                acc: Some("c".to_string()),
+                sep: ",".to_string(),
+            })
+            .unwrap();
+        assert_eq!(*concat.final_value(), OwnedValue::Text(Rc::from("a,b,c")));
+
+        let mut concat = AggContext::GroupConcat {
+            acc: Some("a,b".to_string()),
+            sep: ",".to_string(),
+        };
+        concat
+            .merge(&AggContext::GroupConcat {
+                acc: None,
+                sep: ",".to_string(),
+            })
+            .unwrap();
+        assert_eq!(*concat.final_value(), OwnedValue::Text(Rc::from("a,b")));
+
+        let mut concat = AggContext::GroupConcat {
+            acc: None,
+            sep: ",".to_string(),
+        };
+        concat
+            .merge(&AggContext::GroupConcat {
+                acc: Some("c".to_string()),
+                sep: ",".to_string(),
+            })
+            .unwrap();
+        assert_eq!(*concat.final_value(), OwnedValue::Text(Rc::from("c")));
+
+        let mut distinct = AggContext::CountDistinct(
+            OwnedValue::Integer(2),
+            HashSet::from([OwnedValue::Integer(1), OwnedValue::Integer(2)]),
+        );
+        distinct
+            .merge(&AggContext::CountDistinct(
+                OwnedValue::Integer(2),
+                HashSet::from([OwnedValue::Integer(2), OwnedValue::Float(3.0)]),
+            ))
+            .unwrap();
+        assert_eq!(*distinct.final_value(), OwnedValue::Integer(3));
+    }
+
+    #[test]
+    fn test_agg_merge_variance() {
+        let empty = AggContext::Variance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            kind: VarianceKind::VarPop,
+        };
+        let data: Vec<OwnedValue> = [2, 4, 4, 4, 5, 5, 7, 9]
+            .iter()
+            .map(|&i| OwnedValue::Integer(i))
+            .collect();
+        let mut left = stepped(empty.clone(), &data[..3]);
+        left.merge(&stepped(empty.clone(), &data[3..])).unwrap();
+        assert_eq!(*left.final_value(), OwnedValue::Float(4.0));
+
+        let mut none = empty.clone();
+        none.merge(&empty).unwrap();
+        assert_eq!(*none.final_value(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_agg_merge_mismatched() {
+        let mut sum = AggContext::Sum(OwnedValue::Integer(1));
+        assert!(sum
+            .merge(&AggContext::Count(OwnedValue::Integer(1)))
+            .is_err());
+
+        let mut var_pop = AggContext::Variance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            kind: VarianceKind::VarPop,
+        };
+        let var_samp = AggContext::Variance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            kind: VarianceKind::VarSamp,
+        };
+        assert!(var_pop.merge(&var_samp).is_err());
+    }
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(OwnedValue::Null.type_name(), "null");
+        assert_eq!(OwnedValue::Integer(1).type_name(), "integer");
+        assert_eq!(OwnedValue::Float(1.5).type_name(), "real");
+        assert_eq!(OwnedValue::Text(Rc::from("a")).type_name(), "text");
+        assert_eq!(OwnedValue::Blob(Rc::from(&b"a"[..])).type_name(), "blob");
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(1)]);
+        assert_eq!(OwnedValue::Record(record.clone()).type_name(), "blob");
+        assert_eq!(
+            OwnedValue::Agg(Box::new(AggContext::Sum(OwnedValue::Float(2.0)))).type_name(),
+            "real"
+        );
+        assert_eq!(
+            OwnedValue::Agg(Box::new(AggContext::Max(None))).type_name(),
+            "null"
+        );
+
+        assert_eq!(Value::Null.type_name(), "null");
+        assert_eq!(Value::Integer(1).type_name(), "integer");
+        assert_eq!(Value::Float(1.5).type_name(), "real");
+        assert_eq!(Value::Text("a").type_name(), "text");
+        assert_eq!(Value::Blob(b"a").type_name(), "blob");
+        assert_eq!(Value::Record(&record).type_name(), "blob");
+    }
+
+    #[test]
+    fn test_from_value_type_mismatch() {
+        let record = Record::new(vec![Value::Text("abc"), Value::Null]);
+        let err = record.get::<i64>(0).unwrap_err();
+        assert!(matches!(
+            err,
+            LimboError::TypeMismatch {
+                expected: "integer",
+                found: "text"
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Conversion error: Expected integer value, found text"
+        );
+        assert!(matches!(
+            record.get::<Vec<u8>>(1),
+            Err(LimboError::TypeMismatch {
+                expected: "blob",
+                found: "null"
+            })
+        ));
+        assert_eq!(record.get_opt::<i64>(1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_serialized_size() {
+        let long_text = "x".repeat(200);
+        let records = [
+            OwnedRecord::new(vec![]),
+            OwnedRecord::new(vec![OwnedValue::Null]),
+            OwnedRecord::new(vec![
+                OwnedValue::Integer(0),
+                OwnedValue::Integer(1),
+                OwnedValue::Integer(-128),
+                OwnedValue::Integer(1 << 40),
+                OwnedValue::Integer(i64::MIN),
+                OwnedValue::Float(1.5),
+            ]),
+            OwnedRecord::new(vec![
+                OwnedValue::Text(Rc::from(long_text.as_str())),
+                OwnedValue::Blob(Rc::from(&b"\x01\x02"[..])),
+                OwnedValue::Text(Rc::from("")),
+            ]),
+            OwnedRecord::new(vec![OwnedValue::Integer(2); 200]),
+        ];
+        for record in &records {
+            let mut buf = Vec::new();
+            record.serialize(&mut buf);
+            assert_eq!(record.serialized_size(), buf.len(), "{:?}", record);
+        }
+    }
+
+    #[test]
+    fn test_serialize_into() {
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Integer(1000),
+            OwnedValue::Null,
+            OwnedValue::Float(-2.5),
+            OwnedValue::Text(Rc::from("hello")),
+            OwnedValue::Blob(Rc::from(&b"\xff"[..])),
+        ]);
+        let mut expected = Vec::new();
+        record.serialize(&mut expected);
+
+        let mut page = vec![0xAA; expected.len() + 4];
+        let n = record.serialize_into(&mut page[2..]).unwrap();
+        assert_eq!(n, expected.len());
+        assert_eq!(&page[2..2 + n], expected.as_slice());
+        assert_eq!(&page[..2], &[0xAA, 0xAA]);
+        assert_eq!(&page[2 + n..], &[0xAA, 0xAA]);
+        assert_eq!(OwnedRecord::deserialize(&page[2..2 + n]).unwrap(), record);
+
+        let mut short = vec![0; expected.len() - 1];
+        assert!(record.serialize_into(&mut short).is_err());
+    }
+
+    #[test]
+    fn test_serialize_parts() {
+        let record = OwnedRecord::new(vec![
+            OwnedValue::Integer(1000),
+            OwnedValue::Null,
+            OwnedValue::Text(Rc::from("hello")),
+            OwnedValue::Zeroblob(3),
+        ]);
+        let mut expected = Vec::new();
+        record.serialize(&mut expected);
+        let (header, body) = record.serialize_parts();
+        // header size, then serial types 2 (i16), 0, 23 (5 bytes of text), 18 (3 byte blob)
+        assert_eq!(header, vec![5, 2, 0, 23, 18]);
+        assert_eq!(body, [&[0x03, 0xe8][..], b"hello", &[0, 0, 0]].concat());
+        assert_eq!([header, body].concat(), expected);
+
+        let (header, body) = OwnedRecord::new(vec![]).serialize_parts();
+        assert_eq!(header, vec![1]);
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_cursor_result_combinators() {
+        let io = PendingIo::page(3);
+        assert_eq!(CursorResult::Ok(2).map(|v| v * 3), CursorResult::Ok(6));
+        assert_eq!(
+            CursorResult::<i32>::IO(io).map(|v| v * 3),
+            CursorResult::IO(io)
+        );
+
+        assert_eq!(
+            CursorResult::Ok(2).and_then(|v| CursorResult::Ok(v + 1)),
+            CursorResult::Ok(3)
+        );
+        assert_eq!(
+            CursorResult::Ok(2).and_then(|_| CursorResult::<i32>::IO(io)),
+            CursorResult::IO(io)
+        );
+        assert_eq!(
+            CursorResult::<i32>::IO(io).and_then(|v| CursorResult::Ok(v + 1)),
+            CursorResult::IO(io)
+        );
+
+        assert!(CursorResult::<()>::IO(io).is_io());
+        assert!(!CursorResult::Ok(()).is_io());
+        assert_eq!(CursorResult::<()>::IO(io).pending_io(), Some(io));
+        assert_eq!(CursorResult::Ok(()).pending_io(), None);
+        assert_eq!(CursorResult::Ok(1).ok(), Some(1));
+        assert_eq!(CursorResult::<i32>::IO(io).ok(), None);
+    }
+
+    #[test]
+    fn test_byte_len() {
+        assert_eq!(OwnedValue::Null.byte_len(), 0);
+        assert_eq!(OwnedValue::Integer(1).byte_len(), 8);
+        assert_eq!(OwnedValue::Float(1.5).byte_len(), 8);
+        assert_eq!(OwnedValue::Text(Rc::from("héllo")).byte_len(), 6);
+        assert_eq!(OwnedValue::Blob(Rc::from(&b"\x00\x01"[..])).byte_len(), 2);
+        assert_eq!(
+            OwnedValue::Agg(Box::new(AggContext::Count(OwnedValue::Integer(3)))).byte_len(),
+            8
+        );
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(1), OwnedValue::Null]);
+        assert_eq!(
+            OwnedValue::Record(record.clone()).byte_len(),
+            record.serialized_size()
+        );
+        assert_eq!(Value::Text("abc").byte_len(), 3);
+        assert_eq!(Value::Null.byte_len(), 0);
+    }
+
+    #[test]
+    fn test_serial_type() {
+        assert_eq!(OwnedValue::Null.serial_type(), 0);
+        assert_eq!(OwnedValue::Integer(0).serial_type(), 8);
+        assert_eq!(OwnedValue::Integer(1).serial_type(), 9);
+        assert_eq!(OwnedValue::Integer(-1).serial_type(), 1);
+        assert_eq!(OwnedValue::Integer(300).serial_type(), 2);
+        assert_eq!(OwnedValue::Integer(1 << 40).serial_type(), 5);
+        assert_eq!(OwnedValue::Integer(i64::MAX).serial_type(), 6);
+        assert_eq!(OwnedValue::Float(0.5).serial_type(), 7);
+        assert_eq!(OwnedValue::Text(Rc::from("abc")).serial_type(), 19);
+        assert_eq!(OwnedValue::Blob(Rc::from(&b"ab"[..])).serial_type(), 16);
+    }
+
+    #[test]
+    fn test_try_from_owned_value() {
+        let n: i64 = OwnedValue::Integer(42).try_into().unwrap();
+        assert_eq!(n, 42);
+        let f: f64 = (&OwnedValue::Integer(2)).try_into().unwrap();
+        assert_eq!(f, 2.0);
+        let b: bool = OwnedValue::Integer(0).try_into().unwrap();
+        assert!(!b);
+        let text: String = OwnedValue::Text(Rc::from("abc")).try_into().unwrap();
+        assert_eq!(text, "abc");
+        let blob: Vec<u8> = OwnedValue::Blob(Rc::from(&b"ab"[..])).try_into().unwrap();
+        assert_eq!(blob, b"ab");
+        let sum = OwnedValue::Agg(Box::new(AggContext::Sum(OwnedValue::Integer(7))));
+        assert_eq!(i64::try_from(&sum).unwrap(), 7);
+
+        let err = i64::try_from(OwnedValue::Text(Rc::from("1"))).unwrap_err();
+        assert!(matches!(err, LimboError::ConversionError(_)));
+        assert_eq!(
+            err.to_string(),
+            "Conversion error: Expected integer value, found text"
+        );
+        assert!(matches!(
+            String::try_from(OwnedValue::Null),
+            Err(LimboError::ConversionError(_))
+        ));
+    }
+
+    #[test]
+    fn test_owned_value_from() {
+        assert_eq!(OwnedValue::from(7i64), OwnedValue::Integer(7));
+        assert_eq!(OwnedValue::from(1.5), OwnedValue::Float(1.5));
+        assert_eq!(OwnedValue::from(true), OwnedValue::Integer(1));
+        assert_eq!(OwnedValue::from(false), OwnedValue::Integer(0));
+        assert_eq!(OwnedValue::from("abc"), OwnedValue::Text(Rc::from("abc")));
+        assert_eq!(
+            OwnedValue::from("abc".to_string()),
+            OwnedValue::Text(Rc::from("abc"))
+        );
+        assert_eq!(
+            OwnedValue::from(vec![1u8, 2]),
+            OwnedValue::Blob(Rc::from(&[1u8, 2][..]))
+        );
+        assert_eq!(OwnedValue::from(Some(3i64)), OwnedValue::Integer(3));
+        assert_eq!(OwnedValue::from(None::<&str>), OwnedValue::Null);
+
+        let value: OwnedValue = 42i64.into();
+        assert_eq!(i64::try_from(value).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_negative_zero() {
+        use std::collections::HashMap;
+
+        let neg = OwnedValue::Float(-0.0);
+        let pos = OwnedValue::Float(0.0);
+        assert_eq!(neg, pos);
+        assert_eq!(neg.cmp(&pos), std::cmp::Ordering::Equal);
+        assert_eq!(neg, OwnedValue::Integer(0));
+
+        let mut groups: HashMap<OwnedValue, i64> = HashMap::new();
+        for v in [neg.clone(), pos.clone(), OwnedValue::Integer(0), neg] {
+            *groups.entry(v).or_default() += 1;
+        }
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[&pos], 4);
+    }
+
+    #[test]
+    fn test_blob_display() {
+        let bytes = [0x00, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(Value::Blob(&bytes).to_string(), "X'00DEADBEEF'");
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(&bytes[..])).to_string(),
+            "X'00DEADBEEF'"
+        );
+        assert_eq!(OwnedValue::Blob(Rc::from(Vec::new())).to_string(), "X''");
+
+        let blob = OwnedValue::Blob(Rc::from(b"abc".to_vec()));
+        assert_eq!(blob.text_lossy(), "abc");
+    }
+
+    #[test]
+    fn test_format_float() {
+        let cases = [
+            // integral values keep a decimal point
+            (1.0, "1.0"),
+            (100.0, "100.0"),
+            (-0.0, "0.0"),
+            (100000000000000.0, "100000000000000.0"),
+            // at most 15 significant digits, trailing zeros dropped
+            (0.1, "0.1"),
+            (2.5, "2.5"),
+            (-123.456, "-123.456"),
+            (1.0 / 3.0, "0.333333333333333"),
+            (0.30000000000000004, "0.3"),
+            // tiny values
+            (0.0001, "0.0001"),
+            (0.00001, "1.0e-05"),
+            (1.5e-7, "1.5e-07"),
+            (-1.25e-5, "-1.25e-05"),
+            (1e-320, "9.99988867182683e-321"),
+            // large exponents, including rounding up into one
+            (1e15, "1.0e+15"),
+            (999999999999999.9, "1.0e+15"),
+            (123456789012345678.0, "1.23456789012346e+17"),
+            (1e100, "1.0e+100"),
+            (f64::INFINITY, "Inf"),
+            (f64::NEG_INFINITY, "-Inf"),
+        ];
+        for (f, expected) in cases {
+            assert_eq!(format_float(f), expected, "formatting {:?}", f);
+            assert_eq!(Value::Float(f).to_string(), expected);
+            assert_eq!(OwnedValue::Float(f).to_string(), expected);
+        }
+        assert_eq!(
+            OwnedValue::Float(0.1).cast_to(CastType::Text),
+            OwnedValue::Text(Rc::from("0.1"))
+        );
+    }
+
+    #[test]
+    fn test_record_compare() {
+        use std::cmp::Ordering;
+
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let asc = SortColumn {
+            order: Order::Ascending,
+            collation: Collation::Binary,
+        };
+        let desc = SortColumn {
+            order: Order::Descending,
+            collation: Collation::Binary,
+        };
+        let nocase = SortColumn {
+            order: Order::Ascending,
+            collation: Collation::NoCase,
+        };
+
+        let a = OwnedRecord::new(vec![
+            OwnedValue::Integer(1),
+            text("abc"),
+            OwnedValue::Integer(7),
+        ]);
+        let b = OwnedRecord::new(vec![
+            OwnedValue::Integer(1),
+            text("ABD"),
+            OwnedValue::Integer(3),
+        ]);
+
+        // without key info it is the derived ordering
+        assert_eq!(a.compare(&b, &[]), a.cmp(&b));
+        assert_eq!(
+            a.compare(&b, &[asc.clone(), asc.clone()]),
+            Ordering::Greater
+        );
+        assert_eq!(a.compare(&b, &[asc.clone(), desc.clone()]), Ordering::Less);
+        assert_eq!(
+            a.compare(&b, &[asc.clone(), nocase.clone()]),
+            Ordering::Less
+        );
+
+        // equal under NOCASE, so the trailing rowid decides
+        let c = OwnedRecord::new(vec![
+            OwnedValue::Integer(1),
+            text("ABC"),
+            OwnedValue::Integer(3),
+        ]);
+        assert_eq!(
+            a.compare(&c, &[asc.clone(), nocase.clone()]),
+            Ordering::Greater
+        );
+        assert_eq!(a.compare(&c, &[desc.clone(), desc.clone()]), Ordering::Less);
+
+        // a prefix sorts first regardless of direction
+        let prefix = OwnedRecord::new(vec![OwnedValue::Integer(1)]);
+        assert_eq!(prefix.compare(&a, &[desc]), Ordering::Less);
+
+        let rtrim = SortColumn {
+            order: Order::Ascending,
+            collation: Collation::RTrim,
+        };
+        assert_eq!(rtrim.compare(&text("x  "), &text("x")), Ordering::Equal);
+        assert_eq!(Collation::from_name("nocase"), Some(Collation::NoCase));
+        assert_eq!(Collation::from_name("unknown"), None);
+    }
+
+    #[test]
+    fn test_record_compare_prefix() {
+        use std::cmp::Ordering;
+
+        let record = |values: &[i64]| {
+            OwnedRecord::new(values.iter().map(|v| OwnedValue::Integer(*v)).collect())
+        };
+        // index record (a, b, rowid)
+        let row = record(&[1, 5, 42]);
+
+        assert_eq!(row.compare_prefix(&record(&[1]), &[]), Ordering::Equal);
+        assert_eq!(row.compare_prefix(&record(&[1, 5]), &[]), Ordering::Equal);
+        assert_eq!(row.compare_prefix(&record(&[1, 4]), &[]), Ordering::Greater);
+        assert_eq!(row.compare_prefix(&record(&[1, 6]), &[]), Ordering::Less);
+        assert_eq!(row.compare_prefix(&record(&[0, 9]), &[]), Ordering::Greater);
+        // a full comparison treats the longer record as greater
+        assert_eq!(row.compare(&record(&[1, 5]), &[]), Ordering::Greater);
+
+        let desc = SortColumn {
+            order: Order::Descending,
+            collation: Collation::Binary,
+        };
+        assert_eq!(row.compare_prefix(&record(&[0]), &[desc]), Ordering::Less);
+    }
+
+    #[test]
+    fn test_zeroblob() {
+        use std::cmp::Ordering;
+
+        let zeroblob = OwnedValue::Zeroblob(300);
+        let blob = OwnedValue::Blob(Rc::from(vec![0; 300]));
+
+        assert_eq!(zeroblob, blob);
+        assert_eq!(hash_of(&zeroblob), hash_of(&blob));
+        assert_eq!(zeroblob.materialize().as_ref(), &blob);
+        assert!(matches!(
+            zeroblob.materialize().as_ref(),
+            OwnedValue::Blob(_)
+        ));
+        assert_eq!(zeroblob.byte_len(), 300);
+        assert_eq!(zeroblob.serial_type(), blob.serial_type());
+        assert_eq!(zeroblob.type_name(), "blob");
+        assert_eq!(OwnedValue::Zeroblob(2).to_string(), "X'0000'");
+        assert_eq!(OwnedValue::Zeroblob(2).text_lossy(), "\0\0");
+
+        // ordered like the blob of zeros it stands for
+        let ones = OwnedValue::Blob(Rc::from(vec![0, 1]));
+        assert_eq!(OwnedValue::Zeroblob(3).cmp(&ones), Ordering::Less);
+        assert_eq!(ones.cmp(&OwnedValue::Zeroblob(3)), Ordering::Greater);
+        let short = OwnedValue::Blob(Rc::from(vec![0, 0]));
+        assert_eq!(OwnedValue::Zeroblob(3).cmp(&short), Ordering::Greater);
+        assert_eq!(
+            OwnedValue::Zeroblob(3).cmp(&OwnedValue::Zeroblob(4)),
+            Ordering::Less
+        );
+        assert_ne!(OwnedValue::Zeroblob(2), ones);
+
+        // serialized as an ordinary blob, without allocating it
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(1), zeroblob]);
+        let expected = OwnedRecord::new(vec![OwnedValue::Integer(1), blob.clone()]);
+        let mut buf = Vec::new();
+        record.serialize(&mut buf);
+        let mut expected_buf = Vec::new();
+        expected.serialize(&mut expected_buf);
+        assert_eq!(buf, expected_buf);
+        assert_eq!(OwnedRecord::deserialize(&buf).unwrap().values[1], blob);
+    }
+
+    #[test]
+    fn test_is_null_and_coalesce() {
+        let empty_max = OwnedValue::Agg(Box::new(AggContext::Max(None)));
+        let max = OwnedValue::Agg(Box::new(AggContext::Max(Some(OwnedValue::Integer(3)))));
+
+        assert!(OwnedValue::Null.is_null());
+        assert!(empty_max.is_null());
+        assert!(!max.is_null());
+        assert!(!OwnedValue::Integer(0).is_null());
+        assert!(!OwnedValue::Text(Rc::from("")).is_null());
+        assert!(!OwnedValue::Record(OwnedRecord::new(vec![OwnedValue::Null])).is_null());
+
+        assert_eq!(coalesce(&[]), OwnedValue::Null);
+        assert_eq!(
+            coalesce(&[OwnedValue::Null, empty_max.clone()]),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            coalesce(&[OwnedValue::Null, empty_max, max, OwnedValue::Integer(5)]),
+            OwnedValue::Integer(3)
+        );
+    }
+
+    /// Stores `values` as the only row of a table with rusqlite and returns the
+    /// record SQLite wrote for it, read straight from the table's leaf page.
+    fn sqlite_record(values: &[OwnedValue]) -> Vec<u8> {
+        use crate::storage::sqlite3_ondisk::read_varint;
+        use rusqlite::types::Value as SqliteValue;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("record.db");
+        let columns: Vec<String> = (0..values.len()).map(|i| format!("c{}", i)).collect();
+        let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{}", i)).collect();
+        let params: Vec<SqliteValue> = values
+            .iter()
+            .map(|value| match value {
+                OwnedValue::Null => SqliteValue::Null,
+                OwnedValue::Integer(i) => SqliteValue::Integer(*i),
+                OwnedValue::Float(f) => SqliteValue::Real(*f),
+                OwnedValue::Text(t) => SqliteValue::Text(t.to_string()),
+                OwnedValue::Blob(b) => SqliteValue::Blob(b.to_vec()),
+                value => panic!("{:?} can't be stored by SQLite", value),
+            })
+            .collect();
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            // Columns without a declared type have no affinity, so SQLite stores
+            // the values exactly as they are bound.
+            conn.execute(&format!("CREATE TABLE t ({})", columns.join(", ")), [])
+                .unwrap();
+            conn.execute(
+                &format!("INSERT INTO t VALUES ({})", placeholders.join(", ")),
+                rusqlite::params_from_iter(params),
+            )
+            .unwrap();
+        }
+
+        let file = std::fs::read(&path).unwrap();
+        let page_size = u16::from_be_bytes([file[16], file[17]]) as usize;
+        // The table is the only one in the schema, so its root is page 2.
+        let page = &file[page_size..2 * page_size];
+        assert_eq!(page[0], 13, "expected a table leaf page");
+        assert_eq!(u16::from_be_bytes([page[3], page[4]]), 1);
+        let cell = u16::from_be_bytes([page[8], page[9]]) as usize;
+        let (payload_size, n) = read_varint(&page[cell..]).unwrap();
+        let (_rowid, m) = read_varint(&page[cell + n..]).unwrap();
+        let start = cell + n + m;
+        page[start..start + payload_size as usize].to_vec()
+    }
+
+    #[test]
+    fn test_serialize_matches_sqlite() {
+        let mut values = vec![OwnedValue::Null];
+        // the edges of every integer serial type
+        for i in [
+            0,
+            1,
+            2,
+            -1,
+            127,
+            128,
+            -128,
+            -129,
+            32767,
+            32768,
+            -32769,
+            8388607,
+            8388608,
+            -8388609,
+            2147483647,
+            2147483648,
+            -2147483649,
+            (1 << 47) - 1,
+            1 << 47,
+            -(1 << 47) - 1,
+            i64::MAX,
+            i64::MIN,
+        ] {
+            values.push(OwnedValue::Integer(i));
+        }
+        for f in [0.0, 1.0, -1.5, 0.1, 1e300, f64::MIN_POSITIVE] {
+            values.push(OwnedValue::Float(f));
+        }
+        for text in ["", "a", &"x".repeat(100)] {
+            values.push(OwnedValue::Text(Rc::from(text)));
+        }
+        for blob in [vec![], vec![0, 1, 2], vec![0xff; 300]] {
+            values.push(OwnedValue::Blob(Rc::from(blob)));
+        }
+
+        // each value on its own, then all of them in one record with a header
+        // longer than 127 bytes
+        let mut records: Vec<OwnedRecord> = values
+            .iter()
+            .map(|value| OwnedRecord::new(vec![value.clone()]))
+            .collect();
+        records.push(OwnedRecord::new(values));
+        for record in records {
+            let expected = sqlite_record(&record.values);
+            let mut buf = Vec::new();
+            record.serialize(&mut buf);
+            assert_eq!(buf, expected, "serializing {:?}", record.values);
+            assert_eq!(OwnedRecord::deserialize(&expected).unwrap(), record);
+        }
+    }
+
+    #[test]
+    fn test_concat() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(text("ab").concat(&text("cd")), text("abcd"));
+        assert_eq!(OwnedValue::Integer(1).concat(&text("x")), text("1x"));
+        assert_eq!(
+            OwnedValue::Float(2.5).concat(&OwnedValue::Integer(3)),
+            text("2.53")
+        );
+        assert_eq!(OwnedValue::Float(1.0).concat(&text("")), text("1.0"));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"hi".to_vec())).concat(&text("!")),
+            text("hi!")
+        );
+        assert_eq!(text("a").concat(&OwnedValue::Null), OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Null.concat(&OwnedValue::Integer(1)),
+            OwnedValue::Null
+        );
+    }
+
+    #[test]
+    fn test_add_is_numeric() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(text("1") + OwnedValue::Integer(2), OwnedValue::Integer(3));
+        assert_eq!(OwnedValue::Integer(2) + text("1.5"), OwnedValue::Float(3.5));
+        assert_eq!(text("abc") + text("def"), OwnedValue::Integer(0));
+        assert_eq!(
+            OwnedValue::Null + OwnedValue::Integer(2),
+            OwnedValue::Integer(2)
+        );
+        assert_eq!(
+            OwnedValue::Integer(2) + OwnedValue::Null,
+            OwnedValue::Integer(2)
+        );
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        use OwnedValue::{Float, Integer, Null};
+        assert!(matches!(
+            Integer(1).checked_add(&Integer(2)),
+            Ok(Integer(3))
+        ));
+        assert!(matches!(
+            Integer(i64::MAX).checked_add(&Integer(1)),
+            Err(LimboError::IntegerOverflow)
+        ));
+        assert!(matches!(
+            Integer(i64::MIN).checked_sub(&Integer(1)),
+            Err(LimboError::IntegerOverflow)
+        ));
+        assert!(matches!(
+            Integer(i64::MAX).checked_mul(&Integer(2)),
+            Err(LimboError::IntegerOverflow)
+        ));
+        assert!(matches!(
+            Integer(i64::MIN).checked_div(&Integer(-1)),
+            Err(LimboError::IntegerOverflow)
+        ));
+        // reals never overflow and NULL propagates, unlike in `+`
+        assert!(matches!(
+            Float(f64::MAX).checked_mul(&Integer(2)),
+            Ok(Float(f)) if f.is_infinite()
+        ));
+        assert!(matches!(Integer(i64::MAX).checked_add(&Null), Ok(Null)));
+        assert!(matches!(Integer(1).checked_div(&Integer(0)), Ok(Null)));
+        assert!(matches!(
+            OwnedValue::Text(Rc::from("7")).checked_sub(&Float(0.5)),
+            Ok(Float(f)) if f == 6.5
+        ));
+        // the lenient operators use reals instead
+        assert_eq!(Integer(i64::MAX) + Integer(1), Float(9223372036854775808.0));
+        assert_eq!(
+            Integer(i64::MIN) / Integer(-1),
+            Float(9223372036854775808.0)
+        );
+        assert_eq!(Integer(7) / Integer(2), Integer(3));
+        assert_eq!(Integer(7) / Float(0.0), Null);
+    }
+
+    #[test]
+    fn test_like() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert!(text("Hello").like("hE%", None));
+        assert!(text("abc").like("_b_", None));
+        assert!(text("").like("%", None));
+        assert!(text("mississippi").like("%iss%ppi", None));
+        assert!(!text("aaaa").like("%a.a", None));
+        assert!(!text("abc").like("ab", None));
+        // only ASCII letters are case folded
+        assert!(!text("ÄB").like("äb", None));
+        assert!(text("a_c").like("a\\_c", Some('\\')));
+        assert!(!text("abc").like("a\\_c", Some('\\')));
+        assert!(text("10%").like("10!%", Some('!')));
+        assert!(!text("a").like("a!", Some('!')));
+        assert!(!OwnedValue::Null.like("%", None));
+        assert!(!OwnedValue::Integer(1).like("%", None));
+    }
+
+    #[test]
+    fn test_glob() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert!(text("abc").glob("a*"));
+        assert!(!text("Abc").glob("a*"));
+        assert!(text("abc").glob("?b?"));
+        assert!(text("mississippi").glob("*sip*"));
+        assert!(text("b").glob("[a-c]"));
+        assert!(text("d").glob("[^a-c]"));
+        assert!(!text("b").glob("[^a-c]"));
+        assert!(text("]").glob("[]a]"));
+        assert!(text("-").glob("[a-]"));
+        assert!(text("*").glob("[*]"));
+        assert!(!text("a").glob("[a"));
+        assert!(!OwnedValue::Null.glob("*"));
+    }
+
+    #[test]
+    fn test_char_length() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(text("héllo").char_length(), OwnedValue::Integer(5));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from("héllo".as_bytes().to_vec())).char_length(),
+            OwnedValue::Integer(6)
+        );
+        assert_eq!(
+            OwnedValue::Zeroblob(3).char_length(),
+            OwnedValue::Integer(3)
+        );
+        assert_eq!(OwnedValue::Float(1.5).char_length(), OwnedValue::Integer(3));
+        assert_eq!(OwnedValue::Null.char_length(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_substr() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let limbo = text("limbo");
+        assert_eq!(limbo.substr(1, Some(3)), text("lim"));
+        assert_eq!(limbo.substr(0, Some(3)), text("li"));
+        assert_eq!(limbo.substr(0, Some(1)), text(""));
+        assert_eq!(limbo.substr(0, None), text("limbo"));
+        assert_eq!(limbo.substr(-2, None), text("bo"));
+        assert_eq!(limbo.substr(-10, Some(7)), text("li"));
+        assert_eq!(limbo.substr(3, Some(-2)), text("li"));
+        assert_eq!(limbo.substr(5, Some(-10)), text("limb"));
+        assert_eq!(limbo.substr(-5, Some(-1)), text(""));
+        assert_eq!(limbo.substr(10, None), text(""));
+        assert_eq!(limbo.substr(i64::MIN, Some(i64::MIN)), text(""));
+        assert_eq!(text("héllo").substr(2, Some(3)), text("éll"));
+        assert_eq!(OwnedValue::Integer(12345).substr(2, Some(2)), text("23"));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"ABC".to_vec())).substr(2, None),
+            OwnedValue::Blob(Rc::from(b"BC".to_vec()))
+        );
+        assert_eq!(
+            OwnedValue::Zeroblob(4).substr(-1, None),
+            OwnedValue::Blob(Rc::from(vec![0]))
+        );
+        assert_eq!(OwnedValue::Null.substr(1, None), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_blob_concat() {
+        let blob = |b: &[u8]| OwnedValue::Blob(Rc::from(b));
+        assert_eq!(blob(b"ab").blob_concat(&blob(b"cd")), blob(b"abcd"));
+        assert_eq!(blob(b"").blob_concat(&blob(b"cd")), blob(b"cd"));
+        assert_eq!(
+            blob(b"a").blob_concat(&OwnedValue::Zeroblob(2)),
+            blob(b"a\0\0")
+        );
+        assert!(matches!(
+            OwnedValue::Zeroblob(2).blob_concat(&OwnedValue::Zeroblob(3)),
+            OwnedValue::Zeroblob(5)
+        ));
+        // sub-blobs are sliced by byte, even when the bytes are text
+        assert_eq!(
+            blob("é!".as_bytes())
+                .blob_concat(&blob(b"?"))
+                .substr(2, Some(2)),
+            blob(&"é!".as_bytes()[1..])
+        );
+        assert_eq!(
+            blob(b"ab").blob_concat(&OwnedValue::Text(Rc::from("cd"))),
+            OwnedValue::Null
+        );
+        assert_eq!(OwnedValue::Null.blob_concat(&blob(b"ab")), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_to_sql_literal() {
+        assert_eq!(OwnedValue::Null.to_sql_literal(), "NULL");
+        assert_eq!(OwnedValue::Integer(-42).to_sql_literal(), "-42");
+        assert_eq!(OwnedValue::Float(-2.5).to_sql_literal(), "-2.5");
+        assert_eq!(OwnedValue::Float(100.0).to_sql_literal(), "100.0");
+        assert_eq!(OwnedValue::Float(0.1).to_sql_literal(), "0.1");
+        assert_eq!(OwnedValue::Float(1e300).to_sql_literal(), "1.0e+300");
+        assert_eq!(OwnedValue::Float(1e-5).to_sql_literal(), "1.0e-05");
+        assert_eq!(OwnedValue::Float(f64::INFINITY).to_sql_literal(), "Inf");
+        // 15 digits aren't enough to read the value back
+        assert_eq!(
+            OwnedValue::Float(123456789012345678.0).to_sql_literal(),
+            "1.23456789012345680000e+17"
+        );
+        assert_eq!(
+            OwnedValue::Text(Rc::from("it's")).to_sql_literal(),
+            "'it''s'"
+        );
+        assert_eq!(OwnedValue::Text(Rc::from("")).to_sql_literal(), "''");
+        assert_eq!(
+            OwnedValue::Text(Rc::from("abc\0def")).to_sql_literal(),
+            "'abc'"
+        );
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(vec![0x00, 0xff])).to_sql_literal(),
+            "X'00FF'"
+        );
+        assert_eq!(OwnedValue::Zeroblob(2).to_sql_literal(), "X'0000'");
+        let max = OwnedValue::Agg(Box::new(AggContext::Max(Some(OwnedValue::Integer(7)))));
+        assert_eq!(max.to_sql_literal(), "7");
+    }
+
+    #[test]
+    fn test_scalar_min_max() {
+        let args = [
+            OwnedValue::Integer(3),
+            OwnedValue::Float(-1.5),
+            OwnedValue::Text(Rc::from("a")),
+        ];
+        assert_eq!(scalar_min(&args), OwnedValue::Float(-1.5));
+        assert_eq!(scalar_max(&args), OwnedValue::Text(Rc::from("a")));
+
+        let with_null = [OwnedValue::Integer(1), OwnedValue::Null];
+        assert_eq!(scalar_min(&with_null), OwnedValue::Null);
+        assert_eq!(scalar_max(&with_null), OwnedValue::Null);
+        assert_eq!(scalar_min(&[]), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_take_and_reset() {
+        let mut value = OwnedValue::Text(Rc::from("hello"));
+        assert_eq!(value.take(), OwnedValue::Text(Rc::from("hello")));
+        assert_eq!(value, OwnedValue::Null);
+        assert_eq!(value.take(), OwnedValue::Null);
+
+        let mut value = OwnedValue::Integer(1);
+        value.reset();
+        assert_eq!(value, OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_assign_ops_in_place() {
+        let mut value = OwnedValue::Integer(1);
+        value += OwnedValue::Integer(2);
+        assert_eq!(value, OwnedValue::Integer(3));
+        value += OwnedValue::Float(0.5);
+        assert_eq!(value, OwnedValue::Float(3.5));
+        value += 1i64;
+        assert_eq!(value, OwnedValue::Float(4.5));
+        value += 0.5;
+        assert_eq!(value, OwnedValue::Float(5.0));
+
+        let mut value = OwnedValue::Integer(i64::MAX);
+        value += OwnedValue::Integer(1);
+        assert_eq!(value, OwnedValue::Float(i64::MAX as f64 + 1.0));
+        let mut value = OwnedValue::Integer(i64::MAX);
+        value += 1i64;
+        assert_eq!(value, OwnedValue::Float(i64::MAX as f64 + 1.0));
+
+        let mut value = OwnedValue::Text(Rc::from("2"));
+        value += OwnedValue::Integer(3);
+        assert_eq!(value, OwnedValue::Integer(5));
+        let mut value = OwnedValue::Null;
+        value += OwnedValue::Integer(3);
+        assert_eq!(value, OwnedValue::Integer(3));
+
+        let mut value = OwnedValue::Integer(7);
+        value /= OwnedValue::Integer(2);
+        assert_eq!(value, OwnedValue::Integer(3));
+        value /= OwnedValue::Integer(0);
+        assert_eq!(value, OwnedValue::Null);
+        let mut value = OwnedValue::Float(7.0);
+        value /= OwnedValue::Integer(2);
+        assert_eq!(value, OwnedValue::Float(3.5));
+        value /= OwnedValue::Float(0.0);
+        assert_eq!(value, OwnedValue::Null);
+        let mut value = OwnedValue::Integer(i64::MIN);
+        value /= OwnedValue::Integer(-1);
+        assert_eq!(value, OwnedValue::Float(-(i64::MIN as f64)));
+    }
+
+    #[test]
+    fn test_from_value_coerced_lossy() {
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Integer(3)).unwrap(),
+            (3, false)
+        );
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Float(2.0)).unwrap(),
+            (2, false)
+        );
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Float(1.5)).unwrap(),
+            (1, true)
+        );
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Float(9223372036854775808.0)).unwrap(),
+            (i64::MAX, true)
+        );
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Text("4.5e1")).unwrap(),
+            (45, false)
+        );
+        assert_eq!(
+            i64::from_value_coerced_lossy(&Value::Text("4.5")).unwrap(),
+            (4, true)
+        );
+
+        assert_eq!(
+            f64::from_value_coerced_lossy(&Value::Integer(1 << 53)).unwrap(),
+            (9007199254740992.0, false)
+        );
+        assert!(
+            f64::from_value_coerced_lossy(&Value::Integer((1 << 53) + 1))
+                .unwrap()
+                .1
+        );
+        assert!(
+            f64::from_value_coerced_lossy(&Value::Integer(i64::MAX))
+                .unwrap()
+                .1
+        );
+
+        assert_eq!(
+            String::from_value_coerced_lossy(&Value::Float(0.5)).unwrap(),
+            ("0.5".to_string(), false)
+        );
+        assert!(
+            String::from_value_coerced_lossy(&Value::Float(0.1 + 0.2))
+                .unwrap()
+                .1
+        );
+        assert_eq!(
+            String::from_value_coerced_lossy(&Value::Blob(b"abc")).unwrap(),
+            ("abc".to_string(), false)
+        );
+        assert!(
+            String::from_value_coerced_lossy(&Value::Blob(&[0xff]))
+                .unwrap()
+                .1
+        );
+
+        assert_eq!(
+            Option::<i64>::from_value_coerced_lossy(&Value::Null).unwrap(),
+            (None, false)
+        );
+        assert_eq!(
+            Option::<i64>::from_value_coerced_lossy(&Value::Float(-0.5)).unwrap(),
+            (Some(0), true)
+        );
+
+        let record = Record::new(vec![Value::Float(1.25)]);
+        assert_eq!(record.get_coerced_lossy::<i64>(0).unwrap(), (1, true));
+        assert_eq!(record.get_coerced_lossy::<f64>(0).unwrap(), (1.25, false));
+    }
+
+    #[test]
+    fn test_debug_elides_long_values() {
+        assert_eq!(format!("{:?}", OwnedValue::Integer(7)), "Integer(7)");
+        assert_eq!(
+            format!("{:?}", OwnedValue::Text(Rc::from("short"))),
+            "Text(\"short\")"
+        );
+        assert_eq!(
+            format!("{:?}", OwnedValue::Blob(Rc::from(vec![1, 2]))),
+            "Blob([1, 2])"
+        );
+
+        let text = OwnedValue::Text(Rc::from("é".repeat(20).as_str()));
+        assert_eq!(
+            format!("{:?}", text),
+            format!("Text(40 bytes: {:?}...)", "é".repeat(16))
+        );
+        assert_eq!(
+            format!("{:#?}", text),
+            format!("Text(\n    {:?},\n)", "é".repeat(20))
+        );
+
+        let blob = OwnedValue::Blob(Rc::from(vec![0xde; 4096]));
+        assert_eq!(
+            format!("{:?}", blob),
+            format!("Blob(4096 bytes: [{}...])", "de, ".repeat(16))
+        );
+        assert!(format!("{:#?}", blob).matches("222").count() == 4096);
+
+        let record = OwnedRecord::new(vec![blob]);
+        assert!(format!("{:?}", record).contains("Blob(4096 bytes: [de, "));
+    }
+
+    #[test]
+    fn test_normalize_numeric() {
+        assert_eq!(
+            OwnedValue::Float(3.0).normalize_numeric(),
+            OwnedValue::Integer(3)
+        );
+        assert_eq!(
+            OwnedValue::Float(-0.0).normalize_numeric(),
+            OwnedValue::Integer(0)
+        );
+        assert_eq!(
+            OwnedValue::Float(3.5).normalize_numeric(),
+            OwnedValue::Float(3.5)
+        );
+        assert_eq!(
+            OwnedValue::Float(1e20).normalize_numeric(),
+            OwnedValue::Float(1e20)
+        );
+        assert_eq!(
+            OwnedValue::Float(9223372036854775808.0).normalize_numeric(),
+            OwnedValue::Float(9223372036854775808.0)
+        );
+        assert!(matches!(
+            OwnedValue::Float(f64::NAN).normalize_numeric(),
+            OwnedValue::Float(f) if f.is_nan()
+        ));
+        assert_eq!(
+            OwnedValue::Integer(7).normalize_numeric(),
+            OwnedValue::Integer(7)
+        );
+        assert_eq!(
+            OwnedValue::Text(Rc::from("3.0")).normalize_numeric(),
+            OwnedValue::Text(Rc::from("3.0"))
+        );
+    }
+
+    #[test]
+    fn test_compare_with_borrowed_value() {
+        let record = OwnedRecord::new(vec![OwnedValue::Integer(1)]);
+        let values = [
+            OwnedValue::Null,
+            OwnedValue::Integer(-3),
+            OwnedValue::Integer(2),
+            OwnedValue::Float(2.0),
+            OwnedValue::Float(2.5),
+            OwnedValue::Float(f64::NAN),
+            OwnedValue::Text(Rc::from("")),
+            OwnedValue::Text(Rc::from("abc")),
+            OwnedValue::Blob(Rc::from(vec![])),
+            OwnedValue::Blob(Rc::from(vec![0, 0])),
+            OwnedValue::Blob(Rc::from(vec![1])),
+            OwnedValue::Zeroblob(2),
+            OwnedValue::Agg(Box::new(AggContext::Count(OwnedValue::Integer(2)))),
+            OwnedValue::Record(record.clone()),
+            OwnedValue::Record(OwnedRecord::new(vec![])),
+        ];
+        for left in &values {
+            for right in &values {
+                let right_owned = right.materialize();
+                let right_borrowed = to_value(&right_owned).unwrap();
+                assert_eq!(
+                    left.partial_cmp(&right_borrowed),
+                    left.partial_cmp(right),
+                    "{:?} vs {:?}",
+                    left,
+                    right
+                );
+                assert_eq!(
+                    *left == right_borrowed,
+                    left == right,
+                    "{:?} vs {:?}",
+                    left,
+                    right
+                );
+            }
+        }
+        assert!(OwnedValue::Integer(2) == Value::Float(2.0));
+        assert!(OwnedValue::Text(Rc::from("b")) > Value::Text("abc"));
+    }
+
+    #[test]
+    fn test_instr() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let blob = |b: &[u8]| OwnedValue::Blob(Rc::from(b));
+        assert_eq!(text("héllo").instr(&text("l")), OwnedValue::Integer(3));
+        assert_eq!(text("abc").instr(&text("d")), OwnedValue::Integer(0));
+        assert_eq!(text("abc").instr(&text("")), OwnedValue::Integer(1));
+        assert_eq!(text("").instr(&text("")), OwnedValue::Integer(1));
+        assert_eq!(
+            blob("héllo".as_bytes()).instr(&blob(b"l")),
+            OwnedValue::Integer(4)
+        );
+        assert_eq!(blob(&[1, 2]).instr(&blob(&[])), OwnedValue::Integer(1));
+        assert_eq!(blob(&[1, 2]).instr(&blob(&[3])), OwnedValue::Integer(0));
+        assert_eq!(
+            OwnedValue::Zeroblob(3).instr(&blob(&[0])),
+            OwnedValue::Integer(1)
+        );
+        assert_eq!(text("abc").instr(&blob(b"c")), OwnedValue::Integer(3));
+        assert_eq!(
+            OwnedValue::Integer(123).instr(&OwnedValue::Integer(2)),
+            OwnedValue::Integer(2)
+        );
+        assert_eq!(OwnedValue::Null.instr(&text("")), OwnedValue::Null);
+        assert_eq!(text("a").instr(&OwnedValue::Null), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_replace() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(text("aaa").replace(&text("a"), &text("bb")), text("bbbbbb"));
+        assert_eq!(
+            OwnedValue::Integer(123).replace(&OwnedValue::Integer(2), &text("x")),
+            text("1x3")
+        );
+        assert_eq!(
+            text("ab").replace(&text("b"), &OwnedValue::Float(1.5)),
+            text("a1.5")
+        );
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"abc".as_slice())).replace(&text("b"), &text("x")),
+            text("axc")
+        );
+        assert_eq!(
+            OwnedValue::Integer(123).replace(&text(""), &text("x")),
+            text("123")
+        );
+        assert_eq!(
+            text("abc").replace(&text(""), &OwnedValue::Null),
+            text("abc")
+        );
+        assert_eq!(
+            text("abc").replace(&text("b"), &OwnedValue::Null),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            text("abc").replace(&OwnedValue::Null, &text("x")),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            OwnedValue::Null.replace(&text("a"), &text("x")),
+            OwnedValue::Null
+        );
+    }
+
+    #[test]
+    fn test_trim_matches() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(
+            text("  a\t  ").trim_matches(TrimMode::Both, None),
+            text("a\t")
+        );
+        assert_eq!(
+            text("  a  ").trim_matches(TrimMode::Leading, None),
+            text("a  ")
+        );
+        assert_eq!(
+            text("  a  ").trim_matches(TrimMode::Trailing, None),
+            text("  a")
+        );
+        assert_eq!(
+            text("ééaéé").trim_matches(TrimMode::Both, Some("é")),
+            text("a")
+        );
+        assert_eq!(
+            text("abcba").trim_matches(TrimMode::Leading, Some("ab")),
+            text("cba")
+        );
+        assert_eq!(
+            text("abc").trim_matches(TrimMode::Both, Some("")),
+            text("abc")
+        );
+        assert_eq!(
+            OwnedValue::Float(12.5).trim_matches(TrimMode::Trailing, Some("5")),
+            text("12.")
+        );
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b" a".as_slice())).trim_matches(TrimMode::Both, None),
+            text("a")
+        );
+        assert_eq!(
+            OwnedValue::Null.trim_matches(TrimMode::Both, None),
+            OwnedValue::Null
+        );
+    }
+
+    #[test]
+    fn test_ascii_case() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(text("héllo wörld").to_upper_ascii(), text("HéLLO WöRLD"));
+        assert_eq!(text("ÉCOLE Été").to_lower_ascii(), text("École Été"));
+        assert_eq!(OwnedValue::Float(1.5e20).to_upper_ascii(), text("1.5E+20"));
+        assert_eq!(OwnedValue::Integer(10).to_lower_ascii(), text("10"));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"ab".as_slice())).to_upper_ascii(),
+            text("AB")
+        );
+        assert_eq!(OwnedValue::Null.to_upper_ascii(), OwnedValue::Null);
+        assert_eq!(OwnedValue::Null.to_lower_ascii(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_hex_unhex() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let blob = |b: &[u8]| OwnedValue::Blob(Rc::from(b));
+        assert_eq!(text("é").hex(), text("C3A9"));
+        assert_eq!(OwnedValue::Integer(100).hex(), text("313030"));
+        assert_eq!(blob(&[0xde, 0xad]).hex(), text("DEAD"));
+        assert_eq!(OwnedValue::Zeroblob(2).hex(), text("0000"));
+        assert_eq!(OwnedValue::Null.hex(), text(""));
+
+        assert_eq!(text("DEad").unhex(), blob(&[0xde, 0xad]));
+        assert_eq!(OwnedValue::Integer(6162).unhex(), blob(b"ab"));
+        assert_eq!(text("").unhex(), blob(&[]));
+        assert_eq!(text("abc").unhex(), OwnedValue::Null);
+        assert_eq!(text("zz").unhex(), OwnedValue::Null);
+        assert_eq!(text("de ad").unhex(), OwnedValue::Null);
+        assert_eq!(OwnedValue::Null.unhex(), OwnedValue::Null);
+
+        assert_eq!(text(" de-ad ").unhex_ignoring(" -"), blob(&[0xde, 0xad]));
+        assert_eq!(text("d e").unhex_ignoring(" "), OwnedValue::Null);
+        assert_eq!(text("de:ad").unhex_ignoring(" "), OwnedValue::Null);
+        assert_eq!(text("ééde").unhex_ignoring("é"), blob(&[0xde]));
+    }
+
+    #[test]
+    fn test_from_serial() {
+        let cases: [(u64, &[u8], OwnedValue); 9] = [
+            (0, &[], OwnedValue::Null),
+            (1, &[0xff], OwnedValue::Integer(-1)),
+            (
+                5,
+                &[0x80, 0, 0, 0, 0, 1],
+                OwnedValue::Integer(-(1 << 47) + 1),
+            ),
+            (6, &[0, 0, 0, 0, 0, 0, 1, 0], OwnedValue::Integer(256)),
+            (7, &1.5f64.to_be_bytes(), OwnedValue::Float(1.5)),
+            (8, &[], OwnedValue::Integer(0)),
+            (9, &[], OwnedValue::Integer(1)),
+            (16, &[1, 2], OwnedValue::Blob(Rc::from(vec![1, 2]))),
+            (19, b"abc", OwnedValue::Text(Rc::from("abc"))),
+        ];
+        for (serial_type, bytes, expected) in cases {
+            // trailing bytes belong to the next value
+            let buf = [bytes, &[0xaa]].concat();
+            assert_eq!(
+                OwnedValue::from_serial(serial_type, &buf).unwrap(),
+                (expected, bytes.len())
+            );
+        }
+        assert!(OwnedValue::from_serial(10, &[]).is_err());
+        assert!(OwnedValue::from_serial(11, &[]).is_err());
+        assert!(OwnedValue::from_serial(6, &[0; 7]).is_err());
+        assert!(OwnedValue::from_serial(19, b"ab").is_err());
+    }
+
+    #[test]
+    fn test_sign_and_trunc() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(OwnedValue::Integer(-7).sign(), OwnedValue::Integer(-1));
+        assert_eq!(OwnedValue::Integer(0).sign(), OwnedValue::Integer(0));
+        assert_eq!(OwnedValue::Float(0.5).sign(), OwnedValue::Integer(1));
+        assert_eq!(text(" 42 ").sign(), OwnedValue::Integer(1));
+        assert_eq!(text("-0.0").sign(), OwnedValue::Integer(0));
+        assert_eq!(text("42abc").sign(), OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"42".to_vec())).sign(),
+            OwnedValue::Null
+        );
+        assert_eq!(OwnedValue::Null.sign(), OwnedValue::Null);
+
+        assert_eq!(OwnedValue::Float(2.7).trunc(), OwnedValue::Float(2.0));
+        assert_eq!(OwnedValue::Float(-2.7).trunc(), OwnedValue::Float(-2.0));
+        assert_eq!(OwnedValue::Float(1e300).trunc(), OwnedValue::Float(1e300));
+        assert_eq!(OwnedValue::Integer(2).trunc(), OwnedValue::Integer(2));
+        assert_eq!(text("2").trunc(), OwnedValue::Integer(2));
+        assert_eq!(text("2.7").trunc(), OwnedValue::Float(2.0));
+        assert_eq!(text("abc").trunc(), OwnedValue::Null);
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"42".to_vec())).trunc(),
+            OwnedValue::Null
+        );
+        assert_eq!(OwnedValue::Null.trunc(), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let int = OwnedValue::Integer;
+        let real = OwnedValue::Float;
+        assert_eq!(int(2).pow(&int(3)), real(8.0));
+        assert_eq!(int(0).pow(&int(-1)), real(f64::INFINITY));
+        assert_eq!(int(-8).pow(&real(0.5)), OwnedValue::Null);
+        assert_eq!(int(2).pow(&OwnedValue::Null), OwnedValue::Null);
+
+        assert_eq!(int(4).sqrt(), real(2.0));
+        assert_eq!(OwnedValue::Text(Rc::from("4")).sqrt(), real(2.0));
+        assert_eq!(
+            OwnedValue::Blob(Rc::from(b"4".to_vec())).sqrt(),
+            OwnedValue::Null
+        );
+        assert_eq!(int(-1).sqrt(), OwnedValue::Null);
+
+        assert_eq!(int(1).ln(), real(0.0));
+        assert_eq!(int(0).ln(), OwnedValue::Null);
+        assert_eq!(int(-1).ln(), OwnedValue::Null);
+
+        assert_eq!(int(8).log(&int(2)), real(3.0));
+        assert_eq!(int(100).log(&int(10)), real(2.0));
+        assert_eq!(int(8).log(&int(1)), OwnedValue::Null);
+        assert_eq!(int(8).log(&int(-2)), OwnedValue::Null);
+        assert_eq!(int(0).log(&int(10)), OwnedValue::Null);
+        assert_eq!(OwnedValue::Null.log(&int(10)), OwnedValue::Null);
+    }
+
+    #[test]
+    fn test_text_from_bytes() {
+        assert_eq!(
+            OwnedValue::text_from_bytes("héllo".as_bytes()),
+            OwnedValue::Text(Rc::from("héllo"))
+        );
+        assert_eq!(
+            OwnedValue::text_from_bytes(&[]),
+            OwnedValue::Text(Rc::from(""))
+        );
+        assert_eq!(
+            OwnedValue::text_from_bytes(&[b'a', 0xc0, b'b']),
+            OwnedValue::Blob(Rc::from(vec![b'a', 0xc0, b'b']))
+        );
+    }
+
+    #[test]
+    fn test_sql_comparison_operators() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        let (yes, no) = (OwnedValue::Integer(1), OwnedValue::Integer(0));
+        let blob = Affinity::Blob;
+        let binary = Collation::Binary;
+        let one = OwnedValue::Integer(1);
+        let two = OwnedValue::Integer(2);
+
+        assert_eq!(one.sql_eq(&OwnedValue::Float(1.0), blob, binary), yes);
+        assert_eq!(one.sql_ne(&two, blob, binary), yes);
+        assert_eq!(one.sql_lt(&two, blob, binary), yes);
+        assert_eq!(two.sql_le(&two, blob, binary), yes);
+        assert_eq!(one.sql_gt(&two, blob, binary), no);
+        assert_eq!(one.sql_ge(&two, blob, binary), no);
+
+        // NULL makes every comparison unknown, even NULL = NULL
+        for null_cmp in [
+            OwnedValue::Null.sql_eq(&OwnedValue::Null, blob, binary),
+            OwnedValue::Null.sql_ne(&one, blob, binary),
+            one.sql_lt(&OwnedValue::Null, blob, binary),
+            one.sql_ge(&OwnedValue::Null, Affinity::Numeric, binary),
+        ] {
+            assert_eq!(null_cmp, OwnedValue::Null);
+        }
+
+        // without affinity text sorts after numbers, with it '10' is the number 10
+        assert_eq!(text("10").sql_gt(&two, blob, binary), yes);
+        assert_eq!(
+            text("10").sql_eq(&OwnedValue::Integer(10), blob, binary),
+            no
+        );
+        assert_eq!(
+            text("10").sql_eq(&OwnedValue::Integer(10), Affinity::Numeric, binary),
+            yes
+        );
+        assert_eq!(text("10").sql_lt(&text("9"), Affinity::Text, binary), yes);
+        assert_eq!(
+            OwnedValue::Integer(10).sql_lt(&text("9"), Affinity::Text, binary),
+            yes
+        );
+
+        assert_eq!(text("abc").sql_eq(&text("ABC"), blob, binary), no);
+        assert_eq!(
+            text("abc").sql_eq(&text("ABC"), blob, Collation::NoCase),
+            yes
+        );
+        assert_eq!(
+            text("abc  ").sql_le(&text("abc"), blob, Collation::RTrim),
+            yes
+        );
+    }
+
+    #[test]
+    fn test_sql_logic_operators() {
+        let (t, f, null) = (
+            OwnedValue::Integer(1),
+            OwnedValue::Integer(0),
+            OwnedValue::Null,
+        );
+        let cases = [
+            (&t, &t, &t, &t),
+            (&t, &f, &f, &t),
+            (&t, &null, &null, &t),
+            (&f, &f, &f, &f),
+            (&f, &null, &f, &null),
+            (&null, &null, &null, &null),
+        ];
+        for (lhs, rhs, and, or) in cases {
+            assert_eq!(&lhs.sql_and(rhs), and, "{lhs:?} AND {rhs:?}");
+            assert_eq!(&rhs.sql_and(lhs), and, "{rhs:?} AND {lhs:?}");
+            assert_eq!(&lhs.sql_or(rhs), or, "{lhs:?} OR {rhs:?}");
+            assert_eq!(&rhs.sql_or(lhs), or, "{rhs:?} OR {lhs:?}");
+        }
+        assert_eq!(t.sql_not(), f);
+        assert_eq!(f.sql_not(), t);
+        assert_eq!(null.sql_not(), null);
+
+        let text = |s: &str| OwnedValue::Text(Rc::from(s));
+        assert_eq!(OwnedValue::Integer(-3).sql_not(), f);
+        assert_eq!(OwnedValue::Float(0.1).sql_not(), f);
+        assert_eq!(OwnedValue::Float(-0.0).sql_not(), t);
+        assert_eq!(text("abc").sql_not(), t);
+        assert_eq!(text("0.5").sql_not(), f);
+        assert_eq!(text("-0.0").sql_not(), t);
+        assert_eq!(text(" 2x").sql_and(&t), t);
+        assert_eq!(OwnedValue::Blob(Rc::from(b"1".to_vec())).sql_and(&t), t);
+        assert_eq!(OwnedValue::Zeroblob(2).sql_or(&f), f);
+    }
+
+    #[test]
+    fn test_params_positional() {
+        let mut params = Params::new();
+        assert!(params.is_empty());
+        params
+            .bind(42)
+            .unwrap()
+            .bind("text")
+            .unwrap()
+            .bind(None::<f64>)
+            .unwrap()
+            .bind(vec![1u8, 2])
+            .unwrap();
+        assert_eq!(
+            params.positional(),
+            &[
+                OwnedValue::Integer(42),
+                OwnedValue::Text(Rc::from("text")),
+                OwnedValue::Null,
+                OwnedValue::Blob(Rc::from(vec![1, 2])),
+            ]
+        );
+        assert_eq!(params.len(), 4);
+        assert!(matches!(
+            params.bind_named(":x", 1),
+            Err(LimboError::ParameterError(_))
+        ));
+    }
+
+    #[test]
+    fn test_params_named() {
+        let mut params = Params::new();
+        params
+            .bind_named(":a", 1.5)
+            .unwrap()
+            .bind_named("$b", true)
+            .unwrap()
+            .bind_named(":a", "again")
+            .unwrap();
+        assert_eq!(
+            params.named(),
+            &[
+                (":a".to_string(), OwnedValue::Text(Rc::from("again"))),
+                ("$b".to_string(), OwnedValue::Integer(1)),
+            ]
+        );
+        assert_eq!(params.get_named("$b"), Some(&OwnedValue::Integer(1)));
+        assert_eq!(params.get_named("@c"), None);
+        for name in ["a", ":", "", "?1"] {
+            assert!(
+                matches!(
+                    params.bind_named(name, 1),
+                    Err(LimboError::ParameterError(_))
+                ),
+                "{name:?}"
+            );
+        }
+        assert!(matches!(params.bind(1), Err(LimboError::ParameterError(_))));
+        assert_eq!(params.len(), 2);
+    }
 }
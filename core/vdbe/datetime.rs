@@ -10,12 +10,12 @@ use crate::Result;
 /// Implementation of the date() SQL function.
 pub fn exec_date(values: &[OwnedValue]) -> OwnedValue {
     let maybe_dt = match values.first() {
-        None => parse_naive_date_time(&OwnedValue::Text(Rc::new("now".to_string()))),
+        None => parse_naive_date_time(&OwnedValue::Text(Rc::from("now"))),
         Some(value) => parse_naive_date_time(value),
     };
     // early return, no need to look at modifiers if result invalid
     if maybe_dt.is_none() {
-        return OwnedValue::Text(Rc::new(String::new()));
+        return OwnedValue::Text(Rc::from(String::new()));
     }
 
     // apply modifiers if result is valid
@@ -23,25 +23,25 @@ pub fn exec_date(values: &[OwnedValue]) -> OwnedValue {
     for modifier in values.iter().skip(1) {
         if let OwnedValue::Text(modifier_str) = modifier {
             if apply_modifier(&mut dt, modifier_str).is_err() {
-                return OwnedValue::Text(Rc::new(String::new()));
+                return OwnedValue::Text(Rc::from(String::new()));
             }
         } else {
-            return OwnedValue::Text(Rc::new(String::new()));
+            return OwnedValue::Text(Rc::from(String::new()));
         }
     }
 
-    OwnedValue::Text(Rc::new(get_date_from_naive_datetime(dt)))
+    OwnedValue::Text(Rc::from(get_date_from_naive_datetime(dt)))
 }
 
 /// Implementation of the time() SQL function.
 pub fn exec_time(time_value: &[OwnedValue]) -> OwnedValue {
     let maybe_dt = match time_value.first() {
-        None => parse_naive_date_time(&OwnedValue::Text(Rc::new("now".to_string()))),
+        None => parse_naive_date_time(&OwnedValue::Text(Rc::from("now"))),
         Some(value) => parse_naive_date_time(value),
     };
     // early return, no need to look at modifiers if result invalid
     if maybe_dt.is_none() {
-        return OwnedValue::Text(Rc::new(String::new()));
+        return OwnedValue::Text(Rc::from(String::new()));
     }
 
     // apply modifiers if result is valid
@@ -49,14 +49,14 @@ pub fn exec_time(time_value: &[OwnedValue]) -> OwnedValue {
     for modifier in time_value.iter().skip(1) {
         if let OwnedValue::Text(modifier_str) = modifier {
             if apply_modifier(&mut dt, modifier_str).is_err() {
-                return OwnedValue::Text(Rc::new(String::new()));
+                return OwnedValue::Text(Rc::from(String::new()));
             }
         } else {
-            return OwnedValue::Text(Rc::new(String::new()));
+            return OwnedValue::Text(Rc::from(String::new()));
         }
     }
 
-    OwnedValue::Text(Rc::new(get_time_from_naive_datetime(dt)))
+    OwnedValue::Text(Rc::from(get_time_from_naive_datetime(dt)))
 }
 
 fn apply_modifier(dt: &mut NaiveDateTime, modifier: &str) -> Result<()> {
@@ -223,6 +223,40 @@ fn get_date_time_from_time_value_float(value: f64) -> Option<NaiveDateTime> {
     }
 }
 
+/// Milliseconds from Julian day 0 to the unix epoch, Julian day 2440587.5.
+const UNIX_EPOCH_JULIAN_DAY_MS: i64 = 210_866_760_000_000;
+
+const MS_PER_DAY: f64 = 86_400_000.0;
+
+fn is_valid_julian_day(jd: f64) -> bool {
+    jd.is_finite() && (0.0..5373484.5).contains(&jd)
+}
+
+/// Unlike `parse_naive_date_time`, integers are unix epoch seconds here.
+pub(crate) fn julian_day_from_time_value(time_value: &OwnedValue) -> Option<f64> {
+    let dt = match time_value {
+        OwnedValue::Text(s) => get_date_time_from_time_value_string(s)?,
+        OwnedValue::Integer(i) => DateTime::from_timestamp(*i, 0)?.naive_utc(),
+        OwnedValue::Float(f) => return is_valid_julian_day(*f).then_some(*f),
+        _ => return None,
+    };
+    let ms = dt
+        .and_utc()
+        .timestamp_millis()
+        .checked_add(UNIX_EPOCH_JULIAN_DAY_MS)?;
+    let jd = ms as f64 / MS_PER_DAY;
+    is_valid_julian_day(jd).then_some(jd)
+}
+
+pub(crate) fn naive_datetime_from_julian_day(jd: f64) -> Option<NaiveDateTime> {
+    if !is_valid_julian_day(jd) {
+        return None;
+    }
+    // rounded to the millisecond like setRawDateNumber() in SQLite's date.c
+    let ms = (jd * MS_PER_DAY + 0.5) as i64 - UNIX_EPOCH_JULIAN_DAY_MS;
+    Some(DateTime::from_timestamp_millis(ms)?.naive_utc())
+}
+
 fn is_leap_second(dt: &NaiveDateTime) -> bool {
     // The range from 1,000,000,000 to 1,999,999,999 represents the leap second.
     dt.nanosecond() >= 1_000_000_000 && dt.nanosecond() <= 1_999_999_999
@@ -409,198 +443,162 @@ mod tests {
 
         let test_cases = vec![
             // Format 1: YYYY-MM-DD (no timezone applicable)
-            (
-                OwnedValue::Text(Rc::new("2024-07-21".to_string())),
-                test_date_str,
-            ),
+            (OwnedValue::Text(Rc::from("2024-07-21")), test_date_str),
             // Format 2: YYYY-MM-DD HH:MM
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 01:30+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 01:30+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30Z")),
                 test_date_str,
             ),
             // Format 3: YYYY-MM-DD HH:MM:SS
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 01:30:45+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 01:30:45+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45Z")),
                 test_date_str,
             ),
             // Format 4: YYYY-MM-DD HH:MM:SS.SSS
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 01:30:45.123+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 01:30:45.123+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123Z")),
                 test_date_str,
             ),
             // Format 5: YYYY-MM-DDTHH:MM
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T01:30+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T01:30+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30Z")),
                 test_date_str,
             ),
             // Format 6: YYYY-MM-DDTHH:MM:SS
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T01:30:45+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T01:30:45+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45Z")),
                 test_date_str,
             ),
             // Format 7: YYYY-MM-DDTHH:MM:SS.SSS
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123+02:00")),
                 test_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123-05:00")),
                 next_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T01:30:45.123+05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T01:30:45.123+05:00")),
                 prev_date_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123Z")),
                 test_date_str,
             ),
             // Format 8: HH:MM
-            (OwnedValue::Text(Rc::new("22:30".to_string())), "2000-01-01"),
-            (
-                OwnedValue::Text(Rc::new("22:30+02:00".to_string())),
-                "2000-01-01",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30-05:00".to_string())),
-                "2000-01-02",
-            ),
-            (
-                OwnedValue::Text(Rc::new("01:30+05:00".to_string())),
-                "1999-12-31",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30Z".to_string())),
-                "2000-01-01",
-            ),
+            (OwnedValue::Text(Rc::from("22:30")), "2000-01-01"),
+            (OwnedValue::Text(Rc::from("22:30+02:00")), "2000-01-01"),
+            (OwnedValue::Text(Rc::from("22:30-05:00")), "2000-01-02"),
+            (OwnedValue::Text(Rc::from("01:30+05:00")), "1999-12-31"),
+            (OwnedValue::Text(Rc::from("22:30Z")), "2000-01-01"),
             // Format 9: HH:MM:SS
-            (
-                OwnedValue::Text(Rc::new("22:30:45".to_string())),
-                "2000-01-01",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45+02:00".to_string())),
-                "2000-01-01",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45-05:00".to_string())),
-                "2000-01-02",
-            ),
-            (
-                OwnedValue::Text(Rc::new("01:30:45+05:00".to_string())),
-                "1999-12-31",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45Z".to_string())),
-                "2000-01-01",
-            ),
+            (OwnedValue::Text(Rc::from("22:30:45")), "2000-01-01"),
+            (OwnedValue::Text(Rc::from("22:30:45+02:00")), "2000-01-01"),
+            (OwnedValue::Text(Rc::from("22:30:45-05:00")), "2000-01-02"),
+            (OwnedValue::Text(Rc::from("01:30:45+05:00")), "1999-12-31"),
+            (OwnedValue::Text(Rc::from("22:30:45Z")), "2000-01-01"),
             // Format 10: HH:MM:SS.SSS
+            (OwnedValue::Text(Rc::from("22:30:45.123")), "2000-01-01"),
             (
-                OwnedValue::Text(Rc::new("22:30:45.123".to_string())),
-                "2000-01-01",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("22:30:45.123+02:00")),
                 "2000-01-01",
             ),
             (
-                OwnedValue::Text(Rc::new("22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("22:30:45.123-05:00")),
                 "2000-01-02",
             ),
             (
-                OwnedValue::Text(Rc::new("01:30:45.123+05:00".to_string())),
+                OwnedValue::Text(Rc::from("01:30:45.123+05:00")),
                 "1999-12-31",
             ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45.123Z".to_string())),
-                "2000-01-01",
-            ),
+            (OwnedValue::Text(Rc::from("22:30:45.123Z")), "2000-01-01"),
             // Test Format 11: 'now'
-            (OwnedValue::Text(Rc::new("now".to_string())), &now),
+            (OwnedValue::Text(Rc::from("now")), &now),
             // Format 12: DDDDDDDDDD (Julian date as float or integer)
             (OwnedValue::Float(2460512.5), test_date_str),
             (OwnedValue::Integer(2460513), test_date_str),
@@ -610,7 +608,7 @@ mod tests {
             let result = exec_date(&[input.clone()]);
             assert_eq!(
                 result,
-                OwnedValue::Text(Rc::new(expected.to_string())),
+                OwnedValue::Text(Rc::from(expected.to_string())),
                 "Failed for input: {:?}",
                 input
             );
@@ -620,31 +618,31 @@ mod tests {
     #[test]
     fn test_invalid_get_date_from_time_value() {
         let invalid_cases = vec![
-            OwnedValue::Text(Rc::new("2024-07-21 25:00".to_string())), // Invalid hour
-            OwnedValue::Text(Rc::new("2024-07-21 24:00:00".to_string())), // Invalid hour
-            OwnedValue::Text(Rc::new("2024-07-21 23:60:00".to_string())), // Invalid minute
-            OwnedValue::Text(Rc::new("2024-07-21 22:58:60".to_string())), // Invalid second
-            OwnedValue::Text(Rc::new("2024-07-32".to_string())),       // Invalid day
-            OwnedValue::Text(Rc::new("2024-13-01".to_string())),       // Invalid month
-            OwnedValue::Text(Rc::new("invalid_date".to_string())),     // Completely invalid string
-            OwnedValue::Text(Rc::new("".to_string())),                 // Empty string
-            OwnedValue::Integer(i64::MAX),                             // Large Julian day
-            OwnedValue::Integer(-1),                                   // Negative Julian day
-            OwnedValue::Float(f64::MAX),                               // Large float
-            OwnedValue::Float(-1.0),          // Negative Julian day as float
-            OwnedValue::Float(f64::NAN),      // NaN
-            OwnedValue::Float(f64::INFINITY), // Infinity
-            OwnedValue::Null,                 // Null value
-            OwnedValue::Blob(vec![1, 2, 3].into()), // Blob (unsupported type)
+            OwnedValue::Text(Rc::from("2024-07-21 25:00")), // Invalid hour
+            OwnedValue::Text(Rc::from("2024-07-21 24:00:00")), // Invalid hour
+            OwnedValue::Text(Rc::from("2024-07-21 23:60:00")), // Invalid minute
+            OwnedValue::Text(Rc::from("2024-07-21 22:58:60")), // Invalid second
+            OwnedValue::Text(Rc::from("2024-07-32")),       // Invalid day
+            OwnedValue::Text(Rc::from("2024-13-01")),       // Invalid month
+            OwnedValue::Text(Rc::from("invalid_date")),     // Completely invalid string
+            OwnedValue::Text(Rc::from("")),                 // Empty string
+            OwnedValue::Integer(i64::MAX),                  // Large Julian day
+            OwnedValue::Integer(-1),                        // Negative Julian day
+            OwnedValue::Float(f64::MAX),                    // Large float
+            OwnedValue::Float(-1.0),                        // Negative Julian day as float
+            OwnedValue::Float(f64::NAN),                    // NaN
+            OwnedValue::Float(f64::INFINITY),               // Infinity
+            OwnedValue::Null,                               // Null value
+            OwnedValue::Blob(vec![1, 2, 3].into()),         // Blob (unsupported type)
             // Invalid timezone tests
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+24:00".to_string())), // Invalid timezone offset (too large)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00-24:00".to_string())), // Invalid timezone offset (too small)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:60".to_string())), // Invalid timezone minutes
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:00:00".to_string())), // Invalid timezone format (extra seconds)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+".to_string())), // Incomplete timezone
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+Z".to_string())), // Invalid timezone format
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:00Z".to_string())), // Mixing offset and Z
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00UTC".to_string())), // Named timezone (not supported)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+24:00")), // Invalid timezone offset (too large)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00-24:00")), // Invalid timezone offset (too small)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:60")), // Invalid timezone minutes
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:00:00")), // Invalid timezone format (extra seconds)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+")),         // Incomplete timezone
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+Z")),        // Invalid timezone format
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:00Z")),   // Mixing offset and Z
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00UTC")), // Named timezone (not supported)
         ];
 
         for case in invalid_cases.iter() {
@@ -669,159 +667,120 @@ mod tests {
 
         let test_cases = vec![
             // Format 1: YYYY-MM-DD (no timezone applicable)
-            (
-                OwnedValue::Text(Rc::new("2024-07-21".to_string())),
-                "00:00:00",
-            ),
+            (OwnedValue::Text(Rc::from("2024-07-21")), "00:00:00"),
             // Format 2: YYYY-MM-DD HH:MM
+            (OwnedValue::Text(Rc::from("2024-07-21 22:30")), "22:30:00"),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30".to_string())),
-                "22:30:00",
-            ),
-            (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30+02:00")),
                 "20:30:00",
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30-05:00")),
                 "03:30:00",
             ),
-            (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30Z".to_string())),
-                "22:30:00",
-            ),
+            (OwnedValue::Text(Rc::from("2024-07-21 22:30Z")), "22:30:00"),
             // Format 3: YYYY-MM-DD HH:MM:SS
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45")),
                 test_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45+02:00")),
                 prev_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45-05:00")),
                 next_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45Z")),
                 test_time_str,
             ),
             // Format 4: YYYY-MM-DD HH:MM:SS.SSS
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123")),
                 test_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123+02:00")),
                 prev_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123-05:00")),
                 next_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21 22:30:45.123Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21 22:30:45.123Z")),
                 test_time_str,
             ),
             // Format 5: YYYY-MM-DDTHH:MM
+            (OwnedValue::Text(Rc::from("2024-07-21T22:30")), "22:30:00"),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30".to_string())),
-                "22:30:00",
-            ),
-            (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30+02:00")),
                 "20:30:00",
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30-05:00")),
                 "03:30:00",
             ),
-            (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30Z".to_string())),
-                "22:30:00",
-            ),
+            (OwnedValue::Text(Rc::from("2024-07-21T22:30Z")), "22:30:00"),
             // Format 6: YYYY-MM-DDTHH:MM:SS
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45")),
                 test_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45+02:00")),
                 prev_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45-05:00")),
                 next_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45Z")),
                 test_time_str,
             ),
             // Format 7: YYYY-MM-DDTHH:MM:SS.SSS
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123")),
                 test_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123+02:00")),
                 prev_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123-05:00")),
                 next_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("2024-07-21T22:30:45.123Z".to_string())),
+                OwnedValue::Text(Rc::from("2024-07-21T22:30:45.123Z")),
                 test_time_str,
             ),
             // Format 8: HH:MM
-            (OwnedValue::Text(Rc::new("22:30".to_string())), "22:30:00"),
-            (
-                OwnedValue::Text(Rc::new("22:30+02:00".to_string())),
-                "20:30:00",
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30-05:00".to_string())),
-                "03:30:00",
-            ),
-            (OwnedValue::Text(Rc::new("22:30Z".to_string())), "22:30:00"),
+            (OwnedValue::Text(Rc::from("22:30")), "22:30:00"),
+            (OwnedValue::Text(Rc::from("22:30+02:00")), "20:30:00"),
+            (OwnedValue::Text(Rc::from("22:30-05:00")), "03:30:00"),
+            (OwnedValue::Text(Rc::from("22:30Z")), "22:30:00"),
             // Format 9: HH:MM:SS
-            (
-                OwnedValue::Text(Rc::new("22:30:45".to_string())),
-                test_time_str,
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45+02:00".to_string())),
-                prev_time_str,
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45-05:00".to_string())),
-                next_time_str,
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45Z".to_string())),
-                test_time_str,
-            ),
+            (OwnedValue::Text(Rc::from("22:30:45")), test_time_str),
+            (OwnedValue::Text(Rc::from("22:30:45+02:00")), prev_time_str),
+            (OwnedValue::Text(Rc::from("22:30:45-05:00")), next_time_str),
+            (OwnedValue::Text(Rc::from("22:30:45Z")), test_time_str),
             // Format 10: HH:MM:SS.SSS
+            (OwnedValue::Text(Rc::from("22:30:45.123")), test_time_str),
             (
-                OwnedValue::Text(Rc::new("22:30:45.123".to_string())),
-                test_time_str,
-            ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45.123+02:00".to_string())),
+                OwnedValue::Text(Rc::from("22:30:45.123+02:00")),
                 prev_time_str,
             ),
             (
-                OwnedValue::Text(Rc::new("22:30:45.123-05:00".to_string())),
+                OwnedValue::Text(Rc::from("22:30:45.123-05:00")),
                 next_time_str,
             ),
-            (
-                OwnedValue::Text(Rc::new("22:30:45.123Z".to_string())),
-                test_time_str,
-            ),
+            (OwnedValue::Text(Rc::from("22:30:45.123Z")), test_time_str),
             // Test Format 11: 'now'
-            (OwnedValue::Text(Rc::new("now".to_string())), &now),
+            (OwnedValue::Text(Rc::from("now")), &now),
             // Format 12: DDDDDDDDDD (Julian date as float or integer)
             (OwnedValue::Float(2460082.1), "14:24:00"),
             (OwnedValue::Integer(2460082), "12:00:00"),
@@ -830,7 +789,7 @@ mod tests {
         for (input, expected) in test_cases {
             let result = exec_time(&[input]);
             if let OwnedValue::Text(result_str) = result {
-                assert_eq!(result_str.as_str(), expected);
+                assert_eq!(&*result_str, expected);
             } else {
                 panic!("Expected OwnedValue::Text, but got: {:?}", result);
             }
@@ -840,31 +799,31 @@ mod tests {
     #[test]
     fn test_invalid_get_time_from_datetime_value() {
         let invalid_cases = vec![
-            OwnedValue::Text(Rc::new("2024-07-21 25:00".to_string())), // Invalid hour
-            OwnedValue::Text(Rc::new("2024-07-21 24:00:00".to_string())), // Invalid hour
-            OwnedValue::Text(Rc::new("2024-07-21 23:60:00".to_string())), // Invalid minute
-            OwnedValue::Text(Rc::new("2024-07-21 22:58:60".to_string())), // Invalid second
-            OwnedValue::Text(Rc::new("2024-07-32".to_string())),       // Invalid day
-            OwnedValue::Text(Rc::new("2024-13-01".to_string())),       // Invalid month
-            OwnedValue::Text(Rc::new("invalid_date".to_string())),     // Completely invalid string
-            OwnedValue::Text(Rc::new("".to_string())),                 // Empty string
-            OwnedValue::Integer(i64::MAX),                             // Large Julian day
-            OwnedValue::Integer(-1),                                   // Negative Julian day
-            OwnedValue::Float(f64::MAX),                               // Large float
-            OwnedValue::Float(-1.0),          // Negative Julian day as float
-            OwnedValue::Float(f64::NAN),      // NaN
-            OwnedValue::Float(f64::INFINITY), // Infinity
-            OwnedValue::Null,                 // Null value
-            OwnedValue::Blob(vec![1, 2, 3].into()), // Blob (unsupported type)
+            OwnedValue::Text(Rc::from("2024-07-21 25:00")), // Invalid hour
+            OwnedValue::Text(Rc::from("2024-07-21 24:00:00")), // Invalid hour
+            OwnedValue::Text(Rc::from("2024-07-21 23:60:00")), // Invalid minute
+            OwnedValue::Text(Rc::from("2024-07-21 22:58:60")), // Invalid second
+            OwnedValue::Text(Rc::from("2024-07-32")),       // Invalid day
+            OwnedValue::Text(Rc::from("2024-13-01")),       // Invalid month
+            OwnedValue::Text(Rc::from("invalid_date")),     // Completely invalid string
+            OwnedValue::Text(Rc::from("")),                 // Empty string
+            OwnedValue::Integer(i64::MAX),                  // Large Julian day
+            OwnedValue::Integer(-1),                        // Negative Julian day
+            OwnedValue::Float(f64::MAX),                    // Large float
+            OwnedValue::Float(-1.0),                        // Negative Julian day as float
+            OwnedValue::Float(f64::NAN),                    // NaN
+            OwnedValue::Float(f64::INFINITY),               // Infinity
+            OwnedValue::Null,                               // Null value
+            OwnedValue::Blob(vec![1, 2, 3].into()),         // Blob (unsupported type)
             // Invalid timezone tests
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+24:00".to_string())), // Invalid timezone offset (too large)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00-24:00".to_string())), // Invalid timezone offset (too small)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:60".to_string())), // Invalid timezone minutes
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:00:00".to_string())), // Invalid timezone format (extra seconds)
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+".to_string())), // Incomplete timezone
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+Z".to_string())), // Invalid timezone format
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00+00:00Z".to_string())), // Mixing offset and Z
-            OwnedValue::Text(Rc::new("2024-07-21T12:00:00UTC".to_string())), // Named timezone (not supported)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+24:00")), // Invalid timezone offset (too large)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00-24:00")), // Invalid timezone offset (too small)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:60")), // Invalid timezone minutes
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:00:00")), // Invalid timezone format (extra seconds)
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+")),         // Incomplete timezone
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+Z")),        // Invalid timezone format
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00+00:00Z")),   // Mixing offset and Z
+            OwnedValue::Text(Rc::from("2024-07-21T12:00:00UTC")), // Named timezone (not supported)
         ];
 
         for case in invalid_cases {
@@ -1167,4 +1126,45 @@ mod tests {
         apply_modifier(&mut dt, "start of day").unwrap();
         assert_eq!(dt, create_datetime(2023, 6, 15, 0, 0, 0));
     }
+
+    #[test]
+    fn test_to_julian_day() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s.to_string()));
+        assert_eq!(text("2000-01-01 12:00:00").to_julian_day(), Some(2451545.0));
+        assert_eq!(text("2000-01-01T12:00").to_julian_day(), Some(2451545.0));
+        assert_eq!(text("1970-01-01").to_julian_day(), Some(2440587.5));
+        assert_eq!(text("2451545.0").to_julian_day(), Some(2451545.0));
+        assert_eq!(OwnedValue::Integer(0).to_julian_day(), Some(2440587.5));
+        assert_eq!(OwnedValue::Integer(86400).to_julian_day(), Some(2440588.5));
+        assert_eq!(
+            OwnedValue::Float(2451545.25).to_julian_day(),
+            Some(2451545.25)
+        );
+        assert_eq!(OwnedValue::Float(-1.0).to_julian_day(), None);
+        assert_eq!(OwnedValue::Float(f64::NAN).to_julian_day(), None);
+        assert_eq!(OwnedValue::Integer(i64::MAX).to_julian_day(), None);
+        assert_eq!(text("not a date").to_julian_day(), None);
+        assert_eq!(OwnedValue::Null.to_julian_day(), None);
+    }
+
+    #[test]
+    fn test_from_julian_day() {
+        let text = |s: &str| OwnedValue::Text(Rc::from(s.to_string()));
+        assert_eq!(
+            OwnedValue::from_julian_day(2451545.0),
+            text("2000-01-01 12:00:00")
+        );
+        assert_eq!(
+            OwnedValue::from_julian_day(2440587.5),
+            text("1970-01-01 00:00:00")
+        );
+        assert_eq!(
+            OwnedValue::from_julian_day(2460000.123456),
+            text("2023-02-24 14:57:46")
+        );
+        assert_eq!(OwnedValue::from_julian_day(-1.0), OwnedValue::Null);
+        assert_eq!(OwnedValue::from_julian_day(5373484.5), OwnedValue::Null);
+        let jd = text("2024-07-21 08:30:15").to_julian_day().unwrap();
+        assert_eq!(OwnedValue::from_julian_day(jd), text("2024-07-21 08:30:15"));
+    }
 }
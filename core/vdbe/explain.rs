@@ -15,7 +15,7 @@ pub fn insn_to_str(
                 0,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("Start at {}", target_pc),
             ),
@@ -24,7 +24,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *dest as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=r[{}]+r[{}]", dest, lhs, rhs),
             ),
@@ -33,7 +33,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *dest as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=r[{}]-r[{}]", dest, lhs, rhs),
             ),
@@ -42,7 +42,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *dest as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=r[{}]*r[{}]", dest, lhs, rhs),
             ),
@@ -51,16 +51,25 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *dest as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=r[{}]/r[{}]", dest, lhs, rhs),
             ),
+            Insn::Concat { lhs, rhs, dest } => (
+                "Concat",
+                *lhs as i32,
+                *rhs as i32,
+                *dest as i32,
+                OwnedValue::Text(Rc::from("")),
+                0,
+                format!("r[{}]=r[{}]||r[{}]", dest, lhs, rhs),
+            ),
             Insn::Null { dest, dest_end } => (
                 "Null",
                 0,
                 *dest as i32,
                 dest_end.map_or(0, |end| end as i32),
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 dest_end.map_or(format!("r[{}]=NULL", dest), |end| {
                     format!("r[{}..{}]=NULL", dest, end)
@@ -71,7 +80,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("Set cursor {} to a (pseudo) NULL row", cursor_id),
             ),
@@ -80,7 +89,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]!=NULL -> goto {}", reg, target_pc),
             ),
@@ -93,7 +102,7 @@ pub fn insn_to_str(
                 *start_reg_a as i32,
                 *start_reg_b as i32,
                 *count as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "r[{}..{}]==r[{}..{}]",
@@ -112,7 +121,7 @@ pub fn insn_to_str(
                 *target_pc_lt as i32,
                 *target_pc_eq as i32,
                 *target_pc_gt as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -125,7 +134,7 @@ pub fn insn_to_str(
                 *source_reg as i32,
                 *dest_reg as i32,
                 *count as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "r[{}..{}]=r[{}..{}]",
@@ -144,7 +153,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "r[{}]>0 -> r[{}]-={}, goto {}",
@@ -160,7 +169,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]==r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -173,7 +182,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]!=r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -186,7 +195,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]<r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -199,7 +208,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]<=r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -212,7 +221,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]>r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -225,7 +234,7 @@ pub fn insn_to_str(
                 *lhs as i32,
                 *rhs as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}]>=r[{}] goto {}", lhs, rhs, target_pc),
             ),
@@ -238,7 +247,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 *target_pc as i32,
                 *null_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if r[{}] goto {}", reg, target_pc),
             ),
@@ -251,7 +260,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 *target_pc as i32,
                 *null_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if !r[{}] goto {}", reg, target_pc),
             ),
@@ -263,7 +272,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *root_page as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "table={}, root={}",
@@ -279,7 +288,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -292,7 +301,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *content_reg as i32,
                 *num_fields as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("{} columns in r[{}]", num_fields, content_reg),
             ),
@@ -301,7 +310,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -313,7 +322,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *pc_if_empty as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "Rewind table {}",
@@ -334,7 +343,7 @@ pub fn insn_to_str(
                     *cursor_id as i32,
                     *column as i32,
                     *dest as i32,
-                    OwnedValue::Text(Rc::new("".to_string())),
+                    OwnedValue::Text(Rc::from("")),
                     0,
                     format!(
                         "r[{}]={}.{}",
@@ -358,7 +367,7 @@ pub fn insn_to_str(
                 *start_reg as i32,
                 *count as i32,
                 *dest_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "r[{}]=mkrec(r[{}..{}])",
@@ -372,7 +381,7 @@ pub fn insn_to_str(
                 *start_reg as i32,
                 *count as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 if *count == 1 {
                     format!("output=r[{}]", start_reg)
@@ -385,7 +394,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -397,7 +406,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *pc_if_next as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -409,7 +418,7 @@ pub fn insn_to_str(
                 *err_code as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -418,7 +427,7 @@ pub fn insn_to_str(
                 0,
                 *write as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -427,7 +436,7 @@ pub fn insn_to_str(
                 0,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -439,7 +448,7 @@ pub fn insn_to_str(
                 *return_reg as i32,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -448,7 +457,7 @@ pub fn insn_to_str(
                 *return_reg as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -457,7 +466,7 @@ pub fn insn_to_str(
                 *value as i32,
                 *dest as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]={}", dest, value),
             ),
@@ -475,7 +484,7 @@ pub fn insn_to_str(
                 *register as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -484,7 +493,7 @@ pub fn insn_to_str(
                 0,
                 *dest as i32,
                 0,
-                OwnedValue::Text(Rc::new(value.clone())),
+                OwnedValue::Text(Rc::from(value.clone())),
                 0,
                 format!("r[{}]='{}'", dest, value),
             ),
@@ -493,7 +502,7 @@ pub fn insn_to_str(
                 0,
                 *dest as i32,
                 0,
-                OwnedValue::Blob(Rc::new(value.clone())),
+                OwnedValue::Blob(Rc::from(value.as_slice())),
                 0,
                 format!(
                     "r[{}]={} (len={})",
@@ -507,7 +516,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *dest as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "r[{}]={}.rowid",
@@ -527,7 +536,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *src_reg as i32,
                 *target_pc as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!(
                     "if (r[{}]!={}.rowid) goto {}",
@@ -547,7 +556,7 @@ pub fn insn_to_str(
                 *index_cursor_id as i32,
                 *table_cursor_id as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -562,7 +571,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *target_pc as i32,
                 *start_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -577,7 +586,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *target_pc as i32,
                 *start_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -591,7 +600,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *target_pc as i32,
                 *start_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -605,7 +614,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *target_pc as i32,
                 *start_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -614,7 +623,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if (--r[{}]==0) goto {}", reg, target_pc),
             ),
@@ -623,12 +632,13 @@ pub fn insn_to_str(
                 acc_reg,
                 delimiter: _,
                 col,
+                distinct,
             } => (
                 "AggStep",
-                0,
+                *distinct as i32,
                 *col as i32,
                 *acc_reg as i32,
-                OwnedValue::Text(Rc::new(func.to_string().into())),
+                OwnedValue::Text(Rc::from(func.to_string())),
                 0,
                 format!("accum=r[{}] step(r[{}])", *acc_reg, *col),
             ),
@@ -637,7 +647,7 @@ pub fn insn_to_str(
                 0,
                 *register as i32,
                 0,
-                OwnedValue::Text(Rc::new(func.to_string().into())),
+                OwnedValue::Text(Rc::from(func.to_string())),
                 0,
                 format!("accum=r[{}]", *register),
             ),
@@ -666,9 +676,9 @@ pub fn insn_to_str(
                     *cursor_id as i32,
                     *columns as i32,
                     0,
-                    OwnedValue::Text(Rc::new(format!(
+                    OwnedValue::Text(Rc::from(format!(
                         "k({},{})",
-                        order.values.len(),
+                        order.len(),
                         to_print.join(",")
                     ))),
                     0,
@@ -684,7 +694,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *dest_reg as i32,
                 *pseudo_cursor as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=data", dest_reg),
             ),
@@ -708,7 +718,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *pc_if_empty as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -720,7 +730,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *pc_if_next as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -734,7 +744,7 @@ pub fn insn_to_str(
                 *constant_mask,
                 *start_reg as i32,
                 *dest as i32,
-                OwnedValue::Text(Rc::new(func.func.to_string())),
+                OwnedValue::Text(Rc::from(func.func.to_string())),
                 0,
                 if func.arg_count == 0 {
                     format!("r[{}]=func()", dest)
@@ -758,7 +768,7 @@ pub fn insn_to_str(
                 *yield_reg as i32,
                 *jump_on_definition as i32,
                 *start_offset as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -767,7 +777,7 @@ pub fn insn_to_str(
                 *yield_reg as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -779,7 +789,7 @@ pub fn insn_to_str(
                 *yield_reg as i32,
                 *end_offset as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -793,7 +803,7 @@ pub fn insn_to_str(
                 *cursor as i32,
                 *record_reg as i32,
                 *key_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 *flag as u16,
                 "".to_string(),
             ),
@@ -802,7 +812,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -815,7 +825,7 @@ pub fn insn_to_str(
                 *cursor as i32,
                 *rowid_reg as i32,
                 *prev_largest_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -824,7 +834,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -833,7 +843,7 @@ pub fn insn_to_str(
                 *reg as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -846,7 +856,7 @@ pub fn insn_to_str(
                 *cursor as i32,
                 *target_pc as i32,
                 *rowid_reg as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -858,7 +868,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 *root_page as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -867,7 +877,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -880,7 +890,7 @@ pub fn insn_to_str(
                 *src_reg as i32,
                 *dst_reg as i32,
                 *amount as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=r[{}]", dst_reg, src_reg),
             ),
@@ -889,7 +899,7 @@ pub fn insn_to_str(
                 *db as i32,
                 *root as i32,
                 *flags as i32,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("r[{}]=root iDb={} flags={}", root, db, flags),
             ),
@@ -898,7 +908,7 @@ pub fn insn_to_str(
                 *cursor_id as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -907,7 +917,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -916,7 +926,7 @@ pub fn insn_to_str(
                 *src as i32,
                 *target_pc as i32,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 format!("if (r[{}]==NULL) goto {}", src, target_pc),
             ),
@@ -925,7 +935,7 @@ pub fn insn_to_str(
                 *db as i32,
                 0,
                 0,
-                OwnedValue::Text(Rc::new(where_clause.clone())),
+                OwnedValue::Text(Rc::from(where_clause.clone())),
                 0,
                 where_clause.clone(),
             ),
@@ -934,7 +944,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -943,7 +953,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -952,7 +962,7 @@ pub fn insn_to_str(
                 0,
                 0,
                 0,
-                OwnedValue::Text(Rc::new("".to_string())),
+                OwnedValue::Text(Rc::from("")),
                 0,
                 "".to_string(),
             ),
@@ -21,7 +21,7 @@ pub mod builder;
 pub mod explain;
 pub mod sorter;
 
-mod datetime;
+pub(crate) mod datetime;
 
 use crate::error::{LimboError, SQLITE_CONSTRAINT_PRIMARYKEY};
 use crate::function::{AggFunc, FuncCtx, ScalarFunc};
@@ -30,7 +30,8 @@ use crate::schema::Table;
 use crate::storage::sqlite3_ondisk::DatabaseHeader;
 use crate::storage::{btree::BTreeCursor, pager::Pager};
 use crate::types::{
-    AggContext, Cursor, CursorResult, OwnedRecord, OwnedValue, Record, SeekKey, SeekOp,
+    format_float, scalar_max, scalar_min, Affinity, AggContext, CastType, Cursor, CursorResult,
+    OwnedRecord, OwnedValue, Record, SeekKey, SeekOp, TrimMode, DEFAULT_GROUP_CONCAT_SEPARATOR,
 };
 use crate::util::parse_schema_rows;
 #[cfg(feature = "json")]
@@ -42,10 +43,9 @@ use datetime::{exec_date, exec_time, exec_unixepoch};
 
 use rand::distributions::{Distribution, Uniform};
 use rand::{thread_rng, Rng};
-use regex::Regex;
 use std::borrow::BorrowMut;
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
 use std::rc::{Rc, Weak};
 
@@ -113,6 +113,12 @@ pub enum Insn {
         rhs: usize,
         dest: usize,
     },
+    // Append the text of rhs to the text of lhs and store the result in dest. NULL if either is NULL.
+    Concat {
+        lhs: usize,
+        rhs: usize,
+        dest: usize,
+    },
     // Compare two vectors of registers in reg(P1)..reg(P1+P3-1) (call this vector "A") and in reg(P2)..reg(P2+P3-1) ("B"). Save the result of the comparison for use by the next Jump instruct.
     Compare {
         start_reg_a: usize,
@@ -395,6 +401,8 @@ pub enum Insn {
         col: usize,
         delimiter: usize,
         func: AggFunc,
+        // Only count distinct values of col
+        distinct: bool,
     },
 
     AggFinal {
@@ -545,24 +553,11 @@ macro_rules! return_if_io {
     ($expr:expr) => {
         match $expr? {
             CursorResult::Ok(v) => v,
-            CursorResult::IO => return Ok(StepResult::IO),
+            CursorResult::IO(_) => return Ok(StepResult::IO),
         }
     };
 }
 
-struct RegexCache {
-    like: HashMap<String, Regex>,
-    glob: HashMap<String, Regex>,
-}
-impl RegexCache {
-    fn new() -> Self {
-        RegexCache {
-            like: HashMap::new(),
-            glob: HashMap::new(),
-        }
-    }
-}
-
 /// The program state describes the environment in which the program executes.
 pub struct ProgramState {
     pub pc: BranchOffset,
@@ -571,7 +566,6 @@ pub struct ProgramState {
     last_compare: Option<std::cmp::Ordering>,
     deferred_seek: Option<(CursorID, CursorID)>,
     ended_coroutine: bool, // flag to notify yield coroutine finished
-    regex_cache: RegexCache,
 }
 
 impl ProgramState {
@@ -586,7 +580,6 @@ impl ProgramState {
             last_compare: None,
             deferred_seek: None,
             ended_coroutine: false,
-            regex_cache: RegexCache::new(),
         }
     }
 
@@ -665,7 +658,7 @@ impl Program {
                                 OwnedValue::Null => {
                                     state.registers[dest] = OwnedValue::Null;
                                 }
-                                OwnedValue::Integer(i) => match aggctx.final_value() {
+                                OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                     OwnedValue::Float(acc) => {
                                         state.registers[dest] = OwnedValue::Float(acc + *i as f64);
                                     }
@@ -676,7 +669,7 @@ impl Program {
                                         todo!("{:?}", aggctx);
                                     }
                                 },
-                                OwnedValue::Float(f) => match aggctx.final_value() {
+                                OwnedValue::Float(f) => match &*aggctx.final_value() {
                                     OwnedValue::Float(acc) => {
                                         state.registers[dest] = OwnedValue::Float(acc + f);
                                     }
@@ -690,7 +683,7 @@ impl Program {
                                 OwnedValue::Agg(aggctx2) => {
                                     let acc = aggctx.final_value();
                                     let acc2 = aggctx2.final_value();
-                                    match (acc, acc2) {
+                                    match (&*acc, &*acc2) {
                                         (OwnedValue::Integer(acc), OwnedValue::Integer(acc2)) => {
                                             state.registers[dest] = OwnedValue::Integer(acc + acc2);
                                         }
@@ -743,7 +736,7 @@ impl Program {
                             OwnedValue::Null => {
                                 state.registers[dest] = OwnedValue::Null;
                             }
-                            OwnedValue::Integer(i) => match aggctx.final_value() {
+                            OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(acc - *i as f64);
                                 }
@@ -754,7 +747,7 @@ impl Program {
                                     todo!("{:?}", aggctx);
                                 }
                             },
-                            OwnedValue::Float(f) => match aggctx.final_value() {
+                            OwnedValue::Float(f) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(acc - f);
                                 }
@@ -768,7 +761,7 @@ impl Program {
                             OwnedValue::Agg(aggctx2) => {
                                 let acc = aggctx.final_value();
                                 let acc2 = aggctx2.final_value();
-                                match (acc, acc2) {
+                                match (&*acc, &*acc2) {
                                     (OwnedValue::Integer(acc), OwnedValue::Integer(acc2)) => {
                                         state.registers[dest] = OwnedValue::Integer(acc - acc2);
                                     }
@@ -794,7 +787,7 @@ impl Program {
                             OwnedValue::Null => {
                                 state.registers[dest] = OwnedValue::Null;
                             }
-                            OwnedValue::Integer(i) => match aggctx.final_value() {
+                            OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(*i as f64 - acc);
                                 }
@@ -805,7 +798,7 @@ impl Program {
                                     todo!("{:?}", aggctx);
                                 }
                             },
-                            OwnedValue::Float(f) => match aggctx.final_value() {
+                            OwnedValue::Float(f) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(f - acc);
                                 }
@@ -847,7 +840,7 @@ impl Program {
                                 OwnedValue::Null => {
                                     state.registers[dest] = OwnedValue::Null;
                                 }
-                                OwnedValue::Integer(i) => match aggctx.final_value() {
+                                OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                     OwnedValue::Float(acc) => {
                                         state.registers[dest] = OwnedValue::Float(acc * *i as f64);
                                     }
@@ -858,7 +851,7 @@ impl Program {
                                         todo!("{:?}", aggctx);
                                     }
                                 },
-                                OwnedValue::Float(f) => match aggctx.final_value() {
+                                OwnedValue::Float(f) => match &*aggctx.final_value() {
                                     OwnedValue::Float(acc) => {
                                         state.registers[dest] = OwnedValue::Float(acc * f);
                                     }
@@ -872,7 +865,7 @@ impl Program {
                                 OwnedValue::Agg(aggctx2) => {
                                     let acc = aggctx.final_value();
                                     let acc2 = aggctx2.final_value();
-                                    match (acc, acc2) {
+                                    match (&*acc, &*acc2) {
                                         (OwnedValue::Integer(acc), OwnedValue::Integer(acc2)) => {
                                             state.registers[dest] = OwnedValue::Integer(acc * acc2);
                                         }
@@ -929,7 +922,7 @@ impl Program {
                             OwnedValue::Null => {
                                 state.registers[dest] = OwnedValue::Null;
                             }
-                            OwnedValue::Integer(i) => match aggctx.final_value() {
+                            OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(acc / *i as f64);
                                 }
@@ -940,7 +933,7 @@ impl Program {
                                     todo!("{:?}", aggctx);
                                 }
                             },
-                            OwnedValue::Float(f) => match aggctx.final_value() {
+                            OwnedValue::Float(f) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(acc / f);
                                 }
@@ -954,7 +947,7 @@ impl Program {
                             OwnedValue::Agg(aggctx2) => {
                                 let acc = aggctx.final_value();
                                 let acc2 = aggctx2.final_value();
-                                match (acc, acc2) {
+                                match (&*acc, &*acc2) {
                                     (OwnedValue::Integer(acc), OwnedValue::Integer(acc2)) => {
                                         state.registers[dest] = OwnedValue::Integer(acc / acc2);
                                     }
@@ -980,7 +973,7 @@ impl Program {
                             OwnedValue::Null => {
                                 state.registers[dest] = OwnedValue::Null;
                             }
-                            OwnedValue::Integer(i) => match aggctx.final_value() {
+                            OwnedValue::Integer(i) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(*i as f64 / acc);
                                 }
@@ -991,7 +984,7 @@ impl Program {
                                     todo!("{:?}", aggctx);
                                 }
                             },
-                            OwnedValue::Float(f) => match aggctx.final_value() {
+                            OwnedValue::Float(f) => match &*aggctx.final_value() {
                                 OwnedValue::Float(acc) => {
                                     state.registers[dest] = OwnedValue::Float(f / acc);
                                 }
@@ -1010,13 +1003,17 @@ impl Program {
                     }
                     state.pc += 1;
                 }
+                Insn::Concat { lhs, rhs, dest } => {
+                    state.registers[*dest] = state.registers[*lhs].concat(&state.registers[*rhs]);
+                    state.pc += 1;
+                }
                 Insn::Null { dest, dest_end } => {
                     if let Some(dest_end) = dest_end {
                         for i in *dest..=*dest_end {
-                            state.registers[i] = OwnedValue::Null;
+                            state.registers[i].reset();
                         }
                     } else {
-                        state.registers[*dest] = OwnedValue::Null;
+                        state.registers[*dest].reset();
                     }
                     state.pc += 1;
                 }
@@ -1080,10 +1077,7 @@ impl Program {
                     let dest_reg = *dest_reg;
                     let count = *count;
                     for i in 0..count {
-                        state.registers[dest_reg + i] = std::mem::replace(
-                            &mut state.registers[source_reg + i],
-                            OwnedValue::Null,
-                        );
+                        state.registers[dest_reg + i] = state.registers[source_reg + i].take();
                     }
                     state.pc += 1;
                 }
@@ -1114,13 +1108,10 @@ impl Program {
                     assert!(*target_pc >= 0);
                     let reg = *reg;
                     let target_pc = *target_pc;
-                    match &state.registers[reg] {
-                        OwnedValue::Null => {
-                            state.pc += 1;
-                        }
-                        _ => {
-                            state.pc = target_pc;
-                        }
+                    if state.registers[reg].is_null() {
+                        state.pc += 1;
+                    } else {
+                        state.pc = target_pc;
                     }
                 }
 
@@ -1284,11 +1275,14 @@ impl Program {
                     cursor_id,
                     root_page,
                 } => {
-                    let cursor = Box::new(BTreeCursor::new(
+                    let mut cursor = Box::new(BTreeCursor::new(
                         pager.clone(),
                         *root_page,
                         self.database_header.clone(),
                     ));
+                    if let Some((_, Some(Table::Index(index)))) = self.cursor_ref.get(*cursor_id) {
+                        cursor.set_key_info(index.key_info());
+                    }
                     cursors.insert(*cursor_id, cursor);
                     state.pc += 1;
                 }
@@ -1347,12 +1341,12 @@ impl Program {
                         let index_cursor = cursors.get_mut(&index_cursor_id).unwrap();
                         let rowid = index_cursor.rowid()?;
                         let table_cursor = cursors.get_mut(&table_cursor_id).unwrap();
-                        match table_cursor.seek(SeekKey::TableRowId(rowid.unwrap()), SeekOp::EQ)? {
-                            CursorResult::Ok(_) => {}
-                            CursorResult::IO => {
-                                state.deferred_seek = Some((index_cursor_id, table_cursor_id));
-                                return Ok(StepResult::IO);
-                            }
+                        if table_cursor
+                            .seek(SeekKey::TableRowId(rowid.unwrap()), SeekOp::EQ)?
+                            .is_io()
+                        {
+                            state.deferred_seek = Some((index_cursor_id, table_cursor_id));
+                            return Ok(StepResult::IO);
                         }
                     }
 
@@ -1379,7 +1373,8 @@ impl Program {
                     state.pc += 1;
                 }
                 Insn::ResultRow { start_reg, count } => {
-                    let record = make_record(&state.registers, start_reg, count);
+                    materialize_zeroblobs(&mut state.registers[*start_reg..*start_reg + *count]);
+                    let record = make_record(&state.registers, start_reg, count)?;
                     state.pc += 1;
                     return Ok(StepResult::Row(record));
                 }
@@ -1519,11 +1514,11 @@ impl Program {
                     state.pc += 1;
                 }
                 Insn::String8 { value, dest } => {
-                    state.registers[*dest] = OwnedValue::Text(Rc::new(value.into()));
+                    state.registers[*dest] = OwnedValue::Text(Rc::from(value.as_str()));
                     state.pc += 1;
                 }
                 Insn::Blob { value, dest } => {
-                    state.registers[*dest] = OwnedValue::Blob(Rc::new(value.clone()));
+                    state.registers[*dest] = OwnedValue::Blob(Rc::from(value.as_slice()));
                     state.pc += 1;
                 }
                 Insn::RowId { cursor_id, dest } => {
@@ -1531,12 +1526,12 @@ impl Program {
                         let index_cursor = cursors.get_mut(&index_cursor_id).unwrap();
                         let rowid = index_cursor.rowid()?;
                         let table_cursor = cursors.get_mut(&table_cursor_id).unwrap();
-                        match table_cursor.seek(SeekKey::TableRowId(rowid.unwrap()), SeekOp::EQ)? {
-                            CursorResult::Ok(_) => {}
-                            CursorResult::IO => {
-                                state.deferred_seek = Some((index_cursor_id, table_cursor_id));
-                                return Ok(StepResult::IO);
-                            }
+                        if table_cursor
+                            .seek(SeekKey::TableRowId(rowid.unwrap()), SeekOp::EQ)?
+                            .is_io()
+                        {
+                            state.deferred_seek = Some((index_cursor_id, table_cursor_id));
+                            return Ok(StepResult::IO);
                         }
                     }
 
@@ -1592,7 +1587,7 @@ impl Program {
                         let record_from_regs: OwnedRecord =
                             make_owned_record(&state.registers, start_reg, num_regs);
                         let found = return_if_io!(
-                            cursor.seek(SeekKey::IndexKey(&record_from_regs), SeekOp::GE)
+                            cursor.seek(SeekKey::IndexPrefix(&record_from_regs), SeekOp::GE)
                         );
                         if !found {
                             state.pc = *target_pc;
@@ -1636,7 +1631,7 @@ impl Program {
                         let record_from_regs: OwnedRecord =
                             make_owned_record(&state.registers, start_reg, num_regs);
                         let found = return_if_io!(
-                            cursor.seek(SeekKey::IndexKey(&record_from_regs), SeekOp::GT)
+                            cursor.seek(SeekKey::IndexPrefix(&record_from_regs), SeekOp::GT)
                         );
                         if !found {
                             state.pc = *target_pc;
@@ -1680,9 +1675,7 @@ impl Program {
                         make_owned_record(&state.registers, start_reg, num_regs);
                     if let Some(ref idx_record) = *cursor.record()? {
                         // omit the rowid from the idx_record, which is the last value
-                        if idx_record.values[..idx_record.values.len() - 1]
-                            >= *record_from_regs.values
-                        {
+                        if idx_record.values[..idx_record.len() - 1] >= *record_from_regs.values {
                             state.pc = *target_pc;
                         } else {
                             state.pc += 1;
@@ -1702,9 +1695,7 @@ impl Program {
                         make_owned_record(&state.registers, start_reg, num_regs);
                     if let Some(ref idx_record) = *cursor.record()? {
                         // omit the rowid from the idx_record, which is the last value
-                        if idx_record.values[..idx_record.values.len() - 1]
-                            > *record_from_regs.values
-                        {
+                        if idx_record.values[..idx_record.len() - 1] > *record_from_regs.values {
                             state.pc = *target_pc;
                         } else {
                             state.pc += 1;
@@ -1733,7 +1724,9 @@ impl Program {
                     col,
                     delimiter,
                     func,
+                    distinct,
                 } => {
+                    materialize_zeroblobs(&mut state.registers[*col..=*col]);
                     if let OwnedValue::Null = &state.registers[*acc_reg] {
                         state.registers[*acc_reg] = match func {
                             AggFunc::Avg => OwnedValue::Agg(Box::new(AggContext::Avg(
@@ -1747,48 +1740,37 @@ impl Program {
                                 // The result of total() is always a floating point value.
                                 // No overflow error is ever raised if any prior input was a floating point value.
                                 // Total() never throws an integer overflow.
-                                OwnedValue::Agg(Box::new(AggContext::Sum(OwnedValue::Float(0.0))))
+                                OwnedValue::Agg(Box::new(AggContext::Total(OwnedValue::Float(0.0))))
+                            }
+                            AggFunc::Variance(kind) => {
+                                OwnedValue::Agg(Box::new(AggContext::Variance {
+                                    count: 0,
+                                    mean: 0.0,
+                                    m2: 0.0,
+                                    kind: *kind,
+                                }))
                             }
+                            AggFunc::Count if *distinct => OwnedValue::Agg(Box::new(
+                                AggContext::CountDistinct(OwnedValue::Integer(0), HashSet::new()),
+                            )),
                             AggFunc::Count => {
                                 OwnedValue::Agg(Box::new(AggContext::Count(OwnedValue::Integer(0))))
                             }
-                            AggFunc::Max => {
-                                let col = state.registers[*col].clone();
-                                match col {
-                                    OwnedValue::Integer(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Max(None)))
-                                    }
-                                    OwnedValue::Float(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Max(None)))
-                                    }
-                                    OwnedValue::Text(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Max(None)))
-                                    }
-                                    _ => {
-                                        unreachable!();
-                                    }
-                                }
+                            AggFunc::Max => OwnedValue::Agg(Box::new(AggContext::Max(None))),
+                            AggFunc::Min => OwnedValue::Agg(Box::new(AggContext::Min(None))),
+                            AggFunc::GroupConcat if *distinct => {
+                                OwnedValue::Agg(Box::new(AggContext::GroupConcatDistinct {
+                                    acc: None,
+                                    sep: DEFAULT_GROUP_CONCAT_SEPARATOR.to_string(),
+                                    seen: HashSet::new(),
+                                }))
                             }
-                            AggFunc::Min => {
-                                let col = state.registers[*col].clone();
-                                match col {
-                                    OwnedValue::Integer(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Min(None)))
-                                    }
-                                    OwnedValue::Float(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Min(None)))
-                                    }
-                                    OwnedValue::Text(_) => {
-                                        OwnedValue::Agg(Box::new(AggContext::Min(None)))
-                                    }
-                                    _ => {
-                                        unreachable!();
-                                    }
-                                }
+                            AggFunc::GroupConcat | AggFunc::StringAgg => {
+                                OwnedValue::Agg(Box::new(AggContext::GroupConcat {
+                                    acc: None,
+                                    sep: DEFAULT_GROUP_CONCAT_SEPARATOR.to_string(),
+                                }))
                             }
-                            AggFunc::GroupConcat | AggFunc::StringAgg => OwnedValue::Agg(Box::new(
-                                AggContext::GroupConcat(OwnedValue::Text(Rc::new("".to_string()))),
-                            )),
                         };
                     }
                     match func {
@@ -1801,29 +1783,45 @@ impl Program {
                             let AggContext::Avg(acc, count) = agg.borrow_mut() else {
                                 unreachable!();
                             };
-                            *acc += col;
-                            *count += 1;
+                            if !col.is_null() {
+                                *acc += col;
+                                *count += 1;
+                            }
                         }
-                        AggFunc::Sum | AggFunc::Total => {
+                        AggFunc::Sum => {
                             let col = state.registers[*col].clone();
                             let OwnedValue::Agg(agg) = state.registers[*acc_reg].borrow_mut()
                             else {
                                 unreachable!();
                             };
-                            let AggContext::Sum(acc) = agg.borrow_mut() else {
+                            agg.step_sum(&col);
+                        }
+                        AggFunc::Total => {
+                            let col = state.registers[*col].clone();
+                            let OwnedValue::Agg(agg) = state.registers[*acc_reg].borrow_mut()
+                            else {
                                 unreachable!();
                             };
-                            *acc += col;
+                            let AggContext::Total(acc) = agg.borrow_mut() else {
+                                unreachable!();
+                            };
+                            *acc += col.into_numeric();
                         }
                         AggFunc::Count => {
+                            let col = state.registers[*col].clone();
                             let OwnedValue::Agg(agg) = state.registers[*acc_reg].borrow_mut()
                             else {
                                 unreachable!();
                             };
-                            let AggContext::Count(count) = agg.borrow_mut() else {
-                                unreachable!();
-                            };
-                            *count += 1;
+                            match agg.borrow_mut() {
+                                AggContext::Count(count) => *count += 1,
+                                AggContext::CountDistinct(count, seen) => {
+                                    if !matches!(col, OwnedValue::Null) && seen.insert(col) {
+                                        *count += 1;
+                                    }
+                                }
+                                _ => unreachable!(),
+                            }
                         }
                         AggFunc::Max => {
                             let col = state.registers[*col].clone();
@@ -1834,38 +1832,14 @@ impl Program {
                             let AggContext::Max(acc) = agg.borrow_mut() else {
                                 unreachable!();
                             };
-
-                            match (acc.as_mut(), col) {
-                                (None, value) => {
-                                    *acc = Some(value);
-                                }
-                                (
-                                    Some(OwnedValue::Integer(ref mut current_max)),
-                                    OwnedValue::Integer(value),
-                                ) => {
-                                    if value > *current_max {
-                                        *current_max = value;
-                                    }
-                                }
-                                (
-                                    Some(OwnedValue::Float(ref mut current_max)),
-                                    OwnedValue::Float(value),
-                                ) => {
-                                    if value > *current_max {
-                                        *current_max = value;
-                                    }
-                                }
-                                (
-                                    Some(OwnedValue::Text(ref mut current_max)),
-                                    OwnedValue::Text(value),
-                                ) => {
-                                    if value > *current_max {
-                                        *current_max = value;
-                                    }
-                                }
-                                _ => {
-                                    eprintln!("Unexpected types in max aggregation");
-                                }
+                            // NULLs don't take part, values of different types compare
+                            // in SQLite's order: numbers, then text, then blobs
+                            let is_larger = match acc {
+                                Some(current_max) => col > *current_max,
+                                None => true,
+                            };
+                            if !col.is_null() && is_larger {
+                                *acc = Some(col);
                             }
                         }
                         AggFunc::Min => {
@@ -1877,75 +1851,47 @@ impl Program {
                             let AggContext::Min(acc) = agg.borrow_mut() else {
                                 unreachable!();
                             };
-
-                            match (acc.as_mut(), col) {
-                                (None, value) => {
-                                    *acc.borrow_mut() = Some(value);
-                                }
-                                (
-                                    Some(OwnedValue::Integer(ref mut current_min)),
-                                    OwnedValue::Integer(value),
-                                ) => {
-                                    if value < *current_min {
-                                        *current_min = value;
-                                    }
-                                }
-                                (
-                                    Some(OwnedValue::Float(ref mut current_min)),
-                                    OwnedValue::Float(value),
-                                ) => {
-                                    if value < *current_min {
-                                        *current_min = value;
-                                    }
-                                }
-                                (
-                                    Some(OwnedValue::Text(ref mut current_min)),
-                                    OwnedValue::Text(value),
-                                ) => {
-                                    if value < *current_min {
-                                        *current_min = value;
-                                    }
-                                }
-                                _ => {
-                                    eprintln!("Unexpected types in min aggregation");
-                                }
+                            // NULLs don't take part, values of different types compare
+                            // in SQLite's order: numbers, then text, then blobs
+                            let is_smaller = match acc {
+                                Some(current_min) => col < *current_min,
+                                None => true,
+                            };
+                            if !col.is_null() && is_smaller {
+                                *acc = Some(col);
                             }
                         }
-                        AggFunc::GroupConcat | AggFunc::StringAgg => {
+                        AggFunc::Variance(_) => {
                             let col = state.registers[*col].clone();
-                            let delimiter = state.registers[*delimiter].clone();
                             let OwnedValue::Agg(agg) = state.registers[*acc_reg].borrow_mut()
                             else {
                                 unreachable!();
                             };
-                            let AggContext::GroupConcat(acc) = agg.borrow_mut() else {
+                            agg.step_variance(&col);
+                        }
+                        AggFunc::GroupConcat | AggFunc::StringAgg => {
+                            let col = state.registers[*col].clone();
+                            let delimiter = state.registers[*delimiter].clone();
+                            let OwnedValue::Agg(agg) = state.registers[*acc_reg].borrow_mut()
+                            else {
                                 unreachable!();
                             };
-                            if acc.to_string().is_empty() {
-                                *acc = col;
-                            } else {
-                                *acc += delimiter;
-                                *acc += col;
-                            }
+                            agg.step_group_concat(&col, &delimiter);
                         }
                     };
                     state.pc += 1;
                 }
                 Insn::AggFinal { register, func } => {
                     match state.registers[*register].borrow_mut() {
-                        OwnedValue::Agg(agg) => {
+                        OwnedValue::Agg(_) => {
+                            // aggregates compute their result in AggContext::final_value
                             match func {
-                                AggFunc::Avg => {
-                                    let AggContext::Avg(acc, count) = agg.borrow_mut() else {
-                                        unreachable!();
-                                    };
-                                    *acc /= count.clone();
-                                }
-                                AggFunc::Sum | AggFunc::Total => {}
+                                AggFunc::Avg | AggFunc::Sum | AggFunc::Total => {}
                                 AggFunc::Count => {}
                                 AggFunc::Max => {}
                                 AggFunc::Min => {}
                                 AggFunc::GroupConcat | AggFunc::StringAgg => {}
+                                AggFunc::Variance(_) => {}
                             };
                         }
                         OwnedValue::Null => {
@@ -2039,12 +1985,13 @@ impl Program {
                     }
                 }
                 Insn::Function {
-                    constant_mask,
                     func,
                     start_reg,
                     dest,
+                    ..
                 } => {
                     let arg_count = func.arg_count;
+                    materialize_zeroblobs(&mut state.registers[*start_reg..*start_reg + arg_count]);
                     match &func.func {
                         #[cfg(feature = "json")]
                         crate::function::Func::Json(JsonFunc::Json) => {
@@ -2090,13 +2037,8 @@ impl Program {
                                 let pattern = &state.registers[*start_reg];
                                 let text = &state.registers[*start_reg + 1];
                                 let result = match (pattern, text) {
-                                    (OwnedValue::Text(pattern), OwnedValue::Text(text)) => {
-                                        let cache = if *constant_mask > 0 {
-                                            Some(&mut state.regex_cache.glob)
-                                        } else {
-                                            None
-                                        };
-                                        OwnedValue::Integer(exec_glob(cache, pattern, text) as i64)
+                                    (OwnedValue::Text(pattern), OwnedValue::Text(_)) => {
+                                        OwnedValue::Integer(text.glob(pattern) as i64)
                                     }
                                     _ => {
                                         unreachable!("Like on non-text registers");
@@ -2115,13 +2057,8 @@ impl Program {
                                 let pattern = &state.registers[*start_reg];
                                 let text = &state.registers[*start_reg + 1];
                                 let result = match (pattern, text) {
-                                    (OwnedValue::Text(pattern), OwnedValue::Text(text)) => {
-                                        let cache = if *constant_mask > 0 {
-                                            Some(&mut state.regex_cache.like)
-                                        } else {
-                                            None
-                                        };
-                                        OwnedValue::Integer(exec_like(cache, pattern, text) as i64)
+                                    (OwnedValue::Text(pattern), OwnedValue::Text(_)) => {
+                                        OwnedValue::Integer(text.like(pattern, None) as i64)
                                     }
                                     _ => {
                                         unreachable!("Like on non-text registers");
@@ -2138,10 +2075,12 @@ impl Program {
                             | ScalarFunc::Quote
                             | ScalarFunc::RandomBlob
                             | ScalarFunc::Sign
+                            | ScalarFunc::Trunc
                             | ScalarFunc::ZeroBlob => {
                                 let reg_value = state.registers[*start_reg].borrow_mut();
                                 let result = match scalar_func {
                                     ScalarFunc::Sign => exec_sign(reg_value),
+                                    ScalarFunc::Trunc => Some(reg_value.trunc()),
                                     ScalarFunc::Abs => exec_abs(reg_value),
                                     ScalarFunc::Lower => exec_lower(reg_value),
                                     ScalarFunc::Upper => exec_upper(reg_value),
@@ -2161,31 +2100,37 @@ impl Program {
                                 state.registers[*dest] = result;
                             }
                             ScalarFunc::Unhex => {
-                                let reg_value = state.registers[*start_reg].clone();
-                                let ignored_chars = state.registers.get(*start_reg + 1);
-                                let result = exec_unhex(&reg_value, ignored_chars);
+                                let reg_value = &state.registers[*start_reg];
+                                let ignored_chars = if arg_count > 1 {
+                                    Some(&state.registers[*start_reg + 1])
+                                } else {
+                                    None
+                                };
+                                let result = exec_unhex(reg_value, ignored_chars);
                                 state.registers[*dest] = result;
                             }
                             ScalarFunc::Random => {
                                 state.registers[*dest] = exec_random();
                             }
-                            ScalarFunc::Trim => {
-                                let reg_value = state.registers[*start_reg].clone();
-                                let pattern_value = state.registers.get(*start_reg + 1).cloned();
-                                let result = exec_trim(&reg_value, pattern_value);
-                                state.registers[*dest] = result;
-                            }
-                            ScalarFunc::LTrim => {
-                                let reg_value = state.registers[*start_reg].clone();
-                                let pattern_value = state.registers.get(*start_reg + 1).cloned();
-                                let result = exec_ltrim(&reg_value, pattern_value);
-                                state.registers[*dest] = result;
+                            ScalarFunc::Trim | ScalarFunc::LTrim | ScalarFunc::RTrim => {
+                                let mode = match scalar_func {
+                                    ScalarFunc::Trim => TrimMode::Both,
+                                    ScalarFunc::LTrim => TrimMode::Leading,
+                                    _ => TrimMode::Trailing,
+                                };
+                                let reg_value = &state.registers[*start_reg];
+                                let pattern_value = if arg_count > 1 {
+                                    Some(&state.registers[*start_reg + 1])
+                                } else {
+                                    None
+                                };
+                                state.registers[*dest] = exec_trim(reg_value, pattern_value, mode);
                             }
-                            ScalarFunc::RTrim => {
-                                let reg_value = state.registers[*start_reg].clone();
-                                let pattern_value = state.registers.get(*start_reg + 1).cloned();
-                                let result = exec_rtrim(&reg_value, pattern_value);
-                                state.registers[*dest] = result;
+                            ScalarFunc::Replace => {
+                                let reg_value = &state.registers[*start_reg];
+                                let find_value = &state.registers[*start_reg + 1];
+                                let repl_value = &state.registers[*start_reg + 2];
+                                state.registers[*dest] = reg_value.replace(find_value, repl_value);
                             }
                             ScalarFunc::Round => {
                                 let reg_value = state.registers[*start_reg].clone();
@@ -2199,18 +2144,14 @@ impl Program {
                                 state.registers[*dest] = result;
                             }
                             ScalarFunc::Min => {
-                                let reg_values = state.registers
-                                    [*start_reg..*start_reg + arg_count]
-                                    .iter()
-                                    .collect();
-                                state.registers[*dest] = exec_min(reg_values);
+                                state.registers[*dest] = scalar_min(
+                                    &state.registers[*start_reg..*start_reg + arg_count],
+                                );
                             }
                             ScalarFunc::Max => {
-                                let reg_values = state.registers
-                                    [*start_reg..*start_reg + arg_count]
-                                    .iter()
-                                    .collect();
-                                state.registers[*dest] = exec_max(reg_values);
+                                state.registers[*dest] = scalar_max(
+                                    &state.registers[*start_reg..*start_reg + arg_count],
+                                );
                             }
                             ScalarFunc::Nullif => {
                                 let first_value = &state.registers[*start_reg];
@@ -2220,7 +2161,11 @@ impl Program {
                             ScalarFunc::Substr | ScalarFunc::Substring => {
                                 let str_value = &state.registers[*start_reg];
                                 let start_value = &state.registers[*start_reg + 1];
-                                let length_value = &state.registers[*start_reg + 2];
+                                let length_value = if arg_count == 3 {
+                                    Some(&state.registers[*start_reg + 2])
+                                } else {
+                                    None
+                                };
                                 let result = exec_substring(str_value, start_value, length_value);
                                 state.registers[*dest] = result;
                             }
@@ -2236,16 +2181,16 @@ impl Program {
                             }
                             ScalarFunc::UnixEpoch => {
                                 if *start_reg == 0 {
-                                    let unixepoch: String = exec_unixepoch(&OwnedValue::Text(
-                                        Rc::new("now".to_string()),
-                                    ))?;
-                                    state.registers[*dest] = OwnedValue::Text(Rc::new(unixepoch));
+                                    let unixepoch: String =
+                                        exec_unixepoch(&OwnedValue::Text(Rc::from("now")))?;
+                                    state.registers[*dest] = OwnedValue::Text(Rc::from(unixepoch));
                                 } else {
                                     let datetime_value = &state.registers[*start_reg];
                                     let unixepoch = exec_unixepoch(datetime_value);
                                     match unixepoch {
                                         Ok(time) => {
-                                            state.registers[*dest] = OwnedValue::Text(Rc::new(time))
+                                            state.registers[*dest] =
+                                                OwnedValue::Text(Rc::from(time))
                                         }
                                         Err(e) => {
                                             return Err(LimboError::ParseError(format!(
@@ -2260,7 +2205,7 @@ impl Program {
                                 let version_integer: i64 =
                                     DATABASE_VERSION.get().unwrap().parse()?;
                                 let version = execute_sqlite_version(version_integer);
-                                state.registers[*dest] = OwnedValue::Text(Rc::new(version));
+                                state.registers[*dest] = OwnedValue::Text(Rc::from(version));
                             }
                         },
                         crate::function::Func::Agg(_) => {
@@ -2342,7 +2287,7 @@ impl Program {
                     state.pc += 1;
                 }
                 Insn::SoftNull { reg } => {
-                    state.registers[*reg] = OwnedValue::Null;
+                    state.registers[*reg].reset();
                     state.pc += 1;
                 }
                 Insn::NotExists {
@@ -2365,11 +2310,14 @@ impl Program {
                     cursor_id,
                     root_page,
                 } => {
-                    let cursor = Box::new(BTreeCursor::new(
+                    let mut cursor = Box::new(BTreeCursor::new(
                         pager.clone(),
                         *root_page,
                         self.database_header.clone(),
                     ));
+                    if let Some((_, Some(Table::Index(index)))) = self.cursor_ref.get(*cursor_id) {
+                        cursor.set_key_info(index.key_info());
+                    }
                     cursors.insert(*cursor_id, cursor);
                     state.pc += 1;
                 }
@@ -2406,7 +2354,7 @@ impl Program {
                     state.pc += 1;
                 }
                 Insn::IsNull { src, target_pc } => {
-                    if matches!(state.registers[*src], OwnedValue::Null) {
+                    if state.registers[*src].is_null() {
                         state.pc = *target_pc;
                     } else {
                         state.pc += 1;
@@ -2434,9 +2382,8 @@ impl Program {
 }
 
 fn get_new_rowid<R: Rng>(cursor: &mut Box<dyn Cursor>, mut rng: R) -> Result<CursorResult<i64>> {
-    match cursor.seek_to_last()? {
-        CursorResult::Ok(()) => {}
-        CursorResult::IO => return Ok(CursorResult::IO),
+    if let Some(pending) = cursor.seek_to_last()?.pending_io() {
+        return Ok(CursorResult::IO(pending));
     }
     let mut rowid = cursor.rowid()?.unwrap_or(0) + 1;
     if rowid > i64::MAX.try_into().unwrap() {
@@ -2455,27 +2402,39 @@ fn get_new_rowid<R: Rng>(cursor: &mut Box<dyn Cursor>, mut rng: R) -> Result<Cur
                         continue; // Try next random rowid
                     }
                 }
-                CursorResult::IO => return Ok(CursorResult::IO),
+                CursorResult::IO(pending) => return Ok(CursorResult::IO(pending)),
             }
         }
     }
     Ok(CursorResult::Ok(rowid.try_into().unwrap()))
 }
 
-fn make_record<'a>(registers: &'a [OwnedValue], start_reg: &usize, count: &usize) -> Record<'a> {
+fn make_record<'a>(
+    registers: &'a [OwnedValue],
+    start_reg: &usize,
+    count: &usize,
+) -> Result<Record<'a>> {
     let mut values = Vec::with_capacity(*count);
     for r in registers.iter().skip(*start_reg).take(*count) {
-        values.push(crate::types::to_value(r))
+        values.push(crate::types::to_value(r)?)
     }
-    Record::new(values)
+    Ok(Record::new(values))
 }
 
-fn make_owned_record(registers: &[OwnedValue], start_reg: &usize, count: &usize) -> OwnedRecord {
-    let mut values = Vec::with_capacity(*count);
-    for r in registers.iter().skip(*start_reg).take(*count) {
-        values.push(r.clone())
+/// Zeroblobs are kept unallocated in registers and records, but functions,
+/// aggregates and result rows read blob contents, so they get ordinary blobs.
+fn materialize_zeroblobs(registers: &mut [OwnedValue]) {
+    for register in registers {
+        if let OwnedValue::Zeroblob(len) = register {
+            *register = OwnedValue::Blob(Rc::from(vec![0; *len]));
+        }
     }
-    OwnedRecord::new(values)
+}
+
+fn make_owned_record(registers: &[OwnedValue], start_reg: &usize, count: &usize) -> OwnedRecord {
+    let mut record = OwnedRecord::with_capacity(*count);
+    record.extend_from_slice(&registers[*start_reg..*start_reg + *count]);
+    record
 }
 
 fn trace_insn(program: &Program, addr: InsnReference, insn: &Insn) {
@@ -2528,28 +2487,15 @@ fn get_indent_count(indent_count: usize, curr_insn: &Insn, prev_insn: Option<&In
 }
 
 fn exec_lower(reg: &OwnedValue) -> Option<OwnedValue> {
-    match reg {
-        OwnedValue::Text(t) => Some(OwnedValue::Text(Rc::new(t.to_lowercase()))),
-        t => Some(t.to_owned()),
-    }
+    Some(reg.to_lower_ascii())
 }
 
 fn exec_length(reg: &OwnedValue) -> OwnedValue {
-    match reg {
-        OwnedValue::Text(_) | OwnedValue::Integer(_) | OwnedValue::Float(_) => {
-            OwnedValue::Integer(reg.to_string().len() as i64)
-        }
-        OwnedValue::Blob(blob) => OwnedValue::Integer(blob.len() as i64),
-        OwnedValue::Agg(aggctx) => exec_length(aggctx.final_value()),
-        _ => reg.to_owned(),
-    }
+    reg.char_length()
 }
 
 fn exec_upper(reg: &OwnedValue) -> Option<OwnedValue> {
-    match reg {
-        OwnedValue::Text(t) => Some(OwnedValue::Text(Rc::new(t.to_uppercase()))),
-        t => Some(t.to_owned()),
-    }
+    Some(reg.to_upper_ascii())
 }
 
 fn exec_concat(registers: &[OwnedValue]) -> OwnedValue {
@@ -2558,14 +2504,14 @@ fn exec_concat(registers: &[OwnedValue]) -> OwnedValue {
         match reg {
             OwnedValue::Text(text) => result.push_str(text),
             OwnedValue::Integer(i) => result.push_str(&i.to_string()),
-            OwnedValue::Float(f) => result.push_str(&f.to_string()),
+            OwnedValue::Float(f) => result.push_str(&format_float(*f)),
             OwnedValue::Agg(aggctx) => result.push_str(&aggctx.final_value().to_string()),
             OwnedValue::Null => continue,
-            OwnedValue::Blob(_) => todo!("TODO concat blob"),
+            OwnedValue::Blob(_) | OwnedValue::Zeroblob(_) => todo!("TODO concat blob"),
             OwnedValue::Record(_) => unreachable!(),
         }
     }
-    OwnedValue::Text(Rc::new(result))
+    OwnedValue::Text(Rc::from(result))
 }
 
 fn exec_concat_ws(registers: &[OwnedValue]) -> OwnedValue {
@@ -2575,8 +2521,8 @@ fn exec_concat_ws(registers: &[OwnedValue]) -> OwnedValue {
 
     let separator = match &registers[0] {
         OwnedValue::Text(text) => text.clone(),
-        OwnedValue::Integer(i) => Rc::new(i.to_string()),
-        OwnedValue::Float(f) => Rc::new(f.to_string()),
+        OwnedValue::Integer(i) => Rc::from(i.to_string()),
+        OwnedValue::Float(f) => Rc::from(format_float(*f)),
         _ => return OwnedValue::Null,
     };
 
@@ -2588,72 +2534,20 @@ fn exec_concat_ws(registers: &[OwnedValue]) -> OwnedValue {
         match reg {
             OwnedValue::Text(text) => result.push_str(text),
             OwnedValue::Integer(i) => result.push_str(&i.to_string()),
-            OwnedValue::Float(f) => result.push_str(&f.to_string()),
+            OwnedValue::Float(f) => result.push_str(&format_float(*f)),
             _ => continue,
         }
     }
 
-    OwnedValue::Text(Rc::new(result))
+    OwnedValue::Text(Rc::from(result))
 }
 
 fn exec_sign(reg: &OwnedValue) -> Option<OwnedValue> {
-    let num = match reg {
-        OwnedValue::Integer(i) => *i as f64,
-        OwnedValue::Float(f) => *f,
-        OwnedValue::Text(s) => {
-            if let Ok(i) = s.parse::<i64>() {
-                i as f64
-            } else if let Ok(f) = s.parse::<f64>() {
-                f
-            } else {
-                return Some(OwnedValue::Null);
-            }
-        }
-        OwnedValue::Blob(b) => match std::str::from_utf8(b) {
-            Ok(s) => {
-                if let Ok(i) = s.parse::<i64>() {
-                    i as f64
-                } else if let Ok(f) = s.parse::<f64>() {
-                    f
-                } else {
-                    return Some(OwnedValue::Null);
-                }
-            }
-            Err(_) => return Some(OwnedValue::Null),
-        },
-        _ => return Some(OwnedValue::Null),
-    };
-
-    let sign = if num > 0.0 {
-        1
-    } else if num < 0.0 {
-        -1
-    } else {
-        0
-    };
-
-    Some(OwnedValue::Integer(sign))
+    Some(reg.sign())
 }
 
 fn exec_abs(reg: &OwnedValue) -> Option<OwnedValue> {
-    match reg {
-        OwnedValue::Integer(x) => {
-            if x < &0 {
-                Some(OwnedValue::Integer(-x))
-            } else {
-                Some(OwnedValue::Integer(*x))
-            }
-        }
-        OwnedValue::Float(x) => {
-            if x < &0.0 {
-                Some(OwnedValue::Float(-x))
-            } else {
-                Some(OwnedValue::Float(*x))
-            }
-        }
-        OwnedValue::Null => Some(OwnedValue::Null),
-        _ => Some(OwnedValue::Float(0.0)),
-    }
+    Some(reg.abs())
 }
 
 fn exec_random() -> OwnedValue {
@@ -2674,29 +2568,11 @@ fn exec_randomblob(reg: &OwnedValue) -> OwnedValue {
 
     let mut blob: Vec<u8> = vec![0; length];
     getrandom::getrandom(&mut blob).expect("Failed to generate random blob");
-    OwnedValue::Blob(Rc::new(blob))
+    OwnedValue::Blob(Rc::from(blob))
 }
 
 fn exec_quote(value: &OwnedValue) -> OwnedValue {
-    match value {
-        OwnedValue::Null => OwnedValue::Text(OwnedValue::Null.to_string().into()),
-        OwnedValue::Integer(_) | OwnedValue::Float(_) => value.to_owned(),
-        OwnedValue::Blob(_) => todo!(),
-        OwnedValue::Text(s) => {
-            let mut quoted = String::with_capacity(s.len() + 2);
-            quoted.push('\'');
-            for c in s.chars() {
-                if c == '\0' {
-                    break;
-                } else {
-                    quoted.push(c);
-                }
-            }
-            quoted.push('\'');
-            OwnedValue::Text(Rc::new(quoted))
-        }
-        _ => OwnedValue::Null, // For unsupported types, return NULL
-    }
+    OwnedValue::Text(Rc::from(value.to_sql_literal()))
 }
 
 fn exec_char(values: Vec<OwnedValue>) -> OwnedValue {
@@ -2710,71 +2586,7 @@ fn exec_char(values: Vec<OwnedValue>) -> OwnedValue {
             }
         })
         .collect();
-    OwnedValue::Text(Rc::new(result))
-}
-
-fn construct_like_regex(pattern: &str) -> Regex {
-    let mut regex_pattern = String::from("(?i)^");
-    regex_pattern.push_str(&pattern.replace('%', ".*").replace('_', "."));
-    regex_pattern.push('$');
-    Regex::new(&regex_pattern).unwrap()
-}
-
-// Implements LIKE pattern matching. Caches the constructed regex if a cache is provided
-fn exec_like(regex_cache: Option<&mut HashMap<String, Regex>>, pattern: &str, text: &str) -> bool {
-    if let Some(cache) = regex_cache {
-        match cache.get(pattern) {
-            Some(re) => re.is_match(text),
-            None => {
-                let re = construct_like_regex(pattern);
-                let res = re.is_match(text);
-                cache.insert(pattern.to_string(), re);
-                res
-            }
-        }
-    } else {
-        let re = construct_like_regex(pattern);
-        re.is_match(text)
-    }
-}
-
-fn construct_glob_regex(pattern: &str) -> Regex {
-    let mut regex_pattern = String::from("^");
-    regex_pattern.push_str(&pattern.replace('*', ".*").replace("?", "."));
-    regex_pattern.push('$');
-    Regex::new(&regex_pattern).unwrap()
-}
-
-// Implements GLOB pattern matching. Caches the constructed regex if a cache is provided
-fn exec_glob(regex_cache: Option<&mut HashMap<String, Regex>>, pattern: &str, text: &str) -> bool {
-    if let Some(cache) = regex_cache {
-        match cache.get(pattern) {
-            Some(re) => re.is_match(text),
-            None => {
-                let re = construct_glob_regex(pattern);
-                let res = re.is_match(text);
-                cache.insert(pattern.to_string(), re);
-                res
-            }
-        }
-    } else {
-        let re = construct_glob_regex(pattern);
-        re.is_match(text)
-    }
-}
-
-fn exec_min(regs: Vec<&OwnedValue>) -> OwnedValue {
-    regs.iter()
-        .min()
-        .map(|&v| v.to_owned())
-        .unwrap_or(OwnedValue::Null)
-}
-
-fn exec_max(regs: Vec<&OwnedValue>) -> OwnedValue {
-    regs.iter()
-        .max()
-        .map(|&v| v.to_owned())
-        .unwrap_or(OwnedValue::Null)
+    OwnedValue::Text(Rc::from(result))
 }
 
 fn exec_nullif(first_value: &OwnedValue, second_value: &OwnedValue) -> OwnedValue {
@@ -2788,115 +2600,36 @@ fn exec_nullif(first_value: &OwnedValue, second_value: &OwnedValue) -> OwnedValu
 fn exec_substring(
     str_value: &OwnedValue,
     start_value: &OwnedValue,
-    length_value: &OwnedValue,
+    length_value: Option<&OwnedValue>,
 ) -> OwnedValue {
-    if let (OwnedValue::Text(str), OwnedValue::Integer(start), OwnedValue::Integer(length)) =
-        (str_value, start_value, length_value)
-    {
-        let start = *start as usize;
-        if start > str.len() {
-            return OwnedValue::Text(Rc::new("".to_string()));
-        }
-
-        let start_idx = start - 1;
-        let str_len = str.len();
-        let end = if *length != -1 {
-            start_idx + *length as usize
-        } else {
-            str_len
-        };
-        let substring = &str[start_idx..end.min(str_len)];
-
-        OwnedValue::Text(Rc::new(substring.to_string()))
-    } else if let (OwnedValue::Text(str), OwnedValue::Integer(start)) = (str_value, start_value) {
-        let start = *start as usize;
-        if start > str.len() {
-            return OwnedValue::Text(Rc::new("".to_string()));
-        }
-
-        let start_idx = start - 1;
-        let str_len = str.len();
-        let substring = &str[start_idx..str_len];
-
-        OwnedValue::Text(Rc::new(substring.to_string()))
-    } else {
-        OwnedValue::Null
+    let length = match length_value.map(|length| length.cast_to(CastType::Integer)) {
+        None => None,
+        Some(OwnedValue::Integer(length)) => Some(length),
+        Some(_) => return OwnedValue::Null,
+    };
+    match start_value.cast_to(CastType::Integer) {
+        OwnedValue::Integer(start) => str_value.substr(start, length),
+        _ => OwnedValue::Null,
     }
 }
 
 fn exec_instr(reg: &OwnedValue, pattern: &OwnedValue) -> OwnedValue {
-    if reg == &OwnedValue::Null || pattern == &OwnedValue::Null {
-        return OwnedValue::Null;
-    }
-
-    if let (OwnedValue::Blob(reg), OwnedValue::Blob(pattern)) = (reg, pattern) {
-        let result = reg
-            .windows(pattern.len())
-            .position(|window| window == **pattern)
-            .map_or(0, |i| i + 1);
-        return OwnedValue::Integer(result as i64);
-    }
-
-    let reg_str;
-    let reg = match reg {
-        OwnedValue::Text(s) => s.as_str(),
-        _ => {
-            reg_str = reg.to_string();
-            reg_str.as_str()
-        }
-    };
-
-    let pattern_str;
-    let pattern = match pattern {
-        OwnedValue::Text(s) => s.as_str(),
-        _ => {
-            pattern_str = pattern.to_string();
-            pattern_str.as_str()
-        }
-    };
-
-    match reg.find(pattern) {
-        Some(position) => OwnedValue::Integer(position as i64 + 1),
-        None => OwnedValue::Integer(0),
-    }
+    reg.instr(pattern)
 }
 
 fn exec_typeof(reg: &OwnedValue) -> OwnedValue {
-    match reg {
-        OwnedValue::Null => OwnedValue::Text(Rc::new("null".to_string())),
-        OwnedValue::Integer(_) => OwnedValue::Text(Rc::new("integer".to_string())),
-        OwnedValue::Float(_) => OwnedValue::Text(Rc::new("real".to_string())),
-        OwnedValue::Text(_) => OwnedValue::Text(Rc::new("text".to_string())),
-        OwnedValue::Blob(_) => OwnedValue::Text(Rc::new("blob".to_string())),
-        OwnedValue::Agg(ctx) => exec_typeof(ctx.final_value()),
-        OwnedValue::Record(_) => unimplemented!(),
-    }
+    OwnedValue::Text(Rc::from(reg.type_name()))
 }
 
 fn exec_hex(reg: &OwnedValue) -> OwnedValue {
-    match reg {
-        OwnedValue::Text(_)
-        | OwnedValue::Integer(_)
-        | OwnedValue::Float(_)
-        | OwnedValue::Blob(_) => {
-            let text = reg.to_string();
-            OwnedValue::Text(Rc::new(hex::encode_upper(text)))
-        }
-        _ => OwnedValue::Null,
-    }
+    reg.hex()
 }
 
 fn exec_unhex(reg: &OwnedValue, ignored_chars: Option<&OwnedValue>) -> OwnedValue {
-    if ignored_chars.is_some() {
-        unimplemented!("unhex(X,Y) is not implemented");
-    }
-
-    match reg {
-        OwnedValue::Null => OwnedValue::Null,
-        _ => match hex::decode(reg.to_string()) {
-            Ok(bytes) => OwnedValue::Blob(Rc::new(bytes)),
-            Err(_) => OwnedValue::Null,
-        },
+    match ignored_chars {
+        None => reg.unhex(),
+        Some(ignored_chars) if ignored_chars.is_null() => OwnedValue::Null,
+        Some(ignored_chars) => reg.unhex_ignoring(&ignored_chars.text_lossy()),
     }
 }
 
@@ -2906,7 +2639,7 @@ fn exec_unicode(reg: &OwnedValue) -> OwnedValue {
         | OwnedValue::Integer(_)
         | OwnedValue::Float(_)
         | OwnedValue::Blob(_) => {
-            let text = reg.to_string();
+            let text = reg.text_lossy();
             if let Some(first_char) = text.chars().next() {
                 OwnedValue::Integer(first_char as u32 as i64)
             } else {
@@ -2917,86 +2650,21 @@ fn exec_unicode(reg: &OwnedValue) -> OwnedValue {
     }
 }
 
-fn _to_float(reg: &OwnedValue) -> f64 {
-    match reg {
-        OwnedValue::Text(x) => x.parse().unwrap_or(0.0),
-        OwnedValue::Integer(x) => *x as f64,
-        OwnedValue::Float(x) => *x,
-        _ => 0.0,
-    }
-}
-
 fn exec_round(reg: &OwnedValue, precision: Option<OwnedValue>) -> OwnedValue {
-    let precision = match precision {
-        Some(OwnedValue::Text(x)) => x.parse().unwrap_or(0.0),
-        Some(OwnedValue::Integer(x)) => x as f64,
-        Some(OwnedValue::Float(x)) => x,
-        Some(OwnedValue::Null) => return OwnedValue::Null,
-        _ => 0.0,
+    let digits = match precision.map(|precision| precision.cast_to(CastType::Integer)) {
+        None => 0,
+        Some(OwnedValue::Integer(digits)) => digits,
+        Some(_) => return OwnedValue::Null,
     };
-
-    let reg = match reg {
-        OwnedValue::Agg(ctx) => _to_float(ctx.final_value()),
-        _ => _to_float(reg),
-    };
-
-    let precision = if precision < 1.0 { 0.0 } else { precision };
-    let multiplier = 10f64.powi(precision as i32);
-    OwnedValue::Float(((reg * multiplier).round()) / multiplier)
+    reg.round(digits)
 }
 
-// Implements TRIM pattern matching.
-fn exec_trim(reg: &OwnedValue, pattern: Option<OwnedValue>) -> OwnedValue {
-    match (reg, pattern) {
-        (reg, Some(pattern)) => match reg {
-            OwnedValue::Text(_) | OwnedValue::Integer(_) | OwnedValue::Float(_) => {
-                let pattern_chars: Vec<char> = pattern.to_string().chars().collect();
-                OwnedValue::Text(Rc::new(
-                    reg.to_string().trim_matches(&pattern_chars[..]).to_string(),
-                ))
-            }
-            _ => reg.to_owned(),
-        },
-        (OwnedValue::Text(t), None) => OwnedValue::Text(Rc::new(t.trim().to_string())),
-        (reg, _) => reg.to_owned(),
-    }
-}
-
-// Implements LTRIM pattern matching.
-fn exec_ltrim(reg: &OwnedValue, pattern: Option<OwnedValue>) -> OwnedValue {
-    match (reg, pattern) {
-        (reg, Some(pattern)) => match reg {
-            OwnedValue::Text(_) | OwnedValue::Integer(_) | OwnedValue::Float(_) => {
-                let pattern_chars: Vec<char> = pattern.to_string().chars().collect();
-                OwnedValue::Text(Rc::new(
-                    reg.to_string()
-                        .trim_start_matches(&pattern_chars[..])
-                        .to_string(),
-                ))
-            }
-            _ => reg.to_owned(),
-        },
-        (OwnedValue::Text(t), None) => OwnedValue::Text(Rc::new(t.trim_start().to_string())),
-        (reg, _) => reg.to_owned(),
-    }
-}
-
-// Implements RTRIM pattern matching.
-fn exec_rtrim(reg: &OwnedValue, pattern: Option<OwnedValue>) -> OwnedValue {
-    match (reg, pattern) {
-        (reg, Some(pattern)) => match reg {
-            OwnedValue::Text(_) | OwnedValue::Integer(_) | OwnedValue::Float(_) => {
-                let pattern_chars: Vec<char> = pattern.to_string().chars().collect();
-                OwnedValue::Text(Rc::new(
-                    reg.to_string()
-                        .trim_end_matches(&pattern_chars[..])
-                        .to_string(),
-                ))
-            }
-            _ => reg.to_owned(),
-        },
-        (OwnedValue::Text(t), None) => OwnedValue::Text(Rc::new(t.trim_end().to_string())),
-        (reg, _) => reg.to_owned(),
+// Implements TRIM, LTRIM and RTRIM pattern matching.
+fn exec_trim(reg: &OwnedValue, pattern: Option<&OwnedValue>, mode: TrimMode) -> OwnedValue {
+    match pattern {
+        Some(pattern) if pattern.is_null() => OwnedValue::Null,
+        Some(pattern) => reg.trim_matches(mode, Some(&pattern.text_lossy())),
+        None => reg.trim_matches(mode, None),
     }
 }
 
@@ -3007,7 +2675,7 @@ fn exec_zeroblob(req: &OwnedValue) -> OwnedValue {
         OwnedValue::Text(s) => s.parse().unwrap_or(0),
         _ => 0,
     };
-    OwnedValue::Blob(Rc::new(vec![0; length.max(0) as usize]))
+    OwnedValue::Zeroblob(length.max(0) as usize)
 }
 
 // exec_if returns whether you should jump
@@ -3025,79 +2693,7 @@ fn exec_if(reg: &OwnedValue, null_reg: &OwnedValue, not: bool) -> bool {
 }
 
 fn exec_cast(value: &OwnedValue, datatype: &str) -> OwnedValue {
-    if matches!(value, OwnedValue::Null) {
-        return OwnedValue::Null;
-    }
-    match affinity(datatype) {
-        // NONE	Casting a value to a type-name with no affinity causes the value to be converted into a BLOB. Casting to a BLOB consists of first casting the value to TEXT in the encoding of the database connection, then interpreting the resulting byte sequence as a BLOB instead of as TEXT.
-        // Historically called NONE, but it's the same as BLOB
-        Affinity::Blob => {
-            // Convert to TEXT first, then interpret as BLOB
-            // TODO: handle encoding
-            let text = value.to_string();
-            OwnedValue::Blob(Rc::new(text.into_bytes()))
-        }
-        // TEXT To cast a BLOB value to TEXT, the sequence of bytes that make up the BLOB is interpreted as text encoded using the database encoding.
-        // Casting an INTEGER or REAL value into TEXT renders the value as if via sqlite3_snprintf() except that the resulting TEXT uses the encoding of the database connection.
-        Affinity::Text => {
-            // Convert everything to text representation
-            // TODO: handle encoding and whatever sqlite3_snprintf does
-            OwnedValue::Text(Rc::new(value.to_string()))
-        }
-        Affinity::Real => match value {
-            OwnedValue::Blob(b) => {
-                // Convert BLOB to TEXT first
-                let text = String::from_utf8_lossy(b);
-                cast_text_to_real(&text)
-            }
-            OwnedValue::Text(t) => cast_text_to_real(t),
-            OwnedValue::Integer(i) => OwnedValue::Float(*i as f64),
-            OwnedValue::Float(f) => OwnedValue::Float(*f),
-            _ => OwnedValue::Float(0.0),
-        },
-        Affinity::Integer => match value {
-            OwnedValue::Blob(b) => {
-                // Convert BLOB to TEXT first
-                let text = String::from_utf8_lossy(b);
-                cast_text_to_integer(&text)
-            }
-            OwnedValue::Text(t) => cast_text_to_integer(t),
-            OwnedValue::Integer(i) => OwnedValue::Integer(*i),
-            // A cast of a REAL value into an INTEGER results in the integer between the REAL value and zero
-            // that is closest to the REAL value. If a REAL is greater than the greatest possible signed integer (+9223372036854775807)
-            // then the result is the greatest possible signed integer and if the REAL is less than the least possible signed integer (-9223372036854775808)
-            // then the result is the least possible signed integer.
-            OwnedValue::Float(f) => {
-                let i = f.floor() as i128;
-                if i > i64::MAX as i128 {
-                    OwnedValue::Integer(i64::MAX)
-                } else if i < i64::MIN as i128 {
-                    OwnedValue::Integer(i64::MIN)
-                } else {
-                    OwnedValue::Integer(i as i64)
-                }
-            }
-            _ => OwnedValue::Integer(0),
-        },
-        Affinity::Numeric => match value {
-            OwnedValue::Blob(b) => {
-                let text = String::from_utf8_lossy(b);
-                cast_text_to_numeric(&text)
-            }
-            OwnedValue::Text(t) => cast_text_to_numeric(t),
-            OwnedValue::Integer(i) => OwnedValue::Integer(*i),
-            OwnedValue::Float(f) => OwnedValue::Float(*f),
-            _ => value.clone(), // TODO probably wrong
-        },
-    }
-}
-
-enum Affinity {
-    Integer,
-    Text,
-    Blob,
-    Real,
-    Numeric,
+    value.cast_to(affinity(datatype).into())
 }
 
 /// For tables not declared as STRICT, the affinity of a column is determined by the declared type of the column, according to the following rules in the order shown:
@@ -3133,81 +2729,6 @@ fn affinity(datatype: &str) -> Affinity {
     Affinity::Numeric
 }
 
-/// When casting a TEXT value to INTEGER, the longest possible prefix of the value that can be interpreted as an integer number
-/// is extracted from the TEXT value and the remainder ignored. Any leading spaces in the TEXT value when converting from TEXT to INTEGER are ignored.
-/// If there is no prefix that can be interpreted as an integer number, the result of the conversion is 0.
-/// If the prefix integer is greater than +9223372036854775807 then the result of the cast is exactly +9223372036854775807.
-/// Similarly, if the prefix integer is less than -9223372036854775808 then the result of the cast is exactly -9223372036854775808.
-/// When casting to INTEGER, if the text looks like a floating point value with an exponent, the exponent will be ignored
-/// because it is no part of the integer prefix. For example, "CAST('123e+5' AS INTEGER)" results in 123, not in 12300000.
-/// The CAST operator understands decimal integers only — conversion of hexadecimal integers stops at the "x" in the "0x" prefix of the hexadecimal integer string and thus result of the CAST is always zero.
-fn cast_text_to_integer(text: &str) -> OwnedValue {
-    let text = text.trim();
-    if let Ok(i) = text.parse::<i64>() {
-        return OwnedValue::Integer(i);
-    }
-    // Try to find longest valid prefix that parses as an integer
-    // TODO: inefficient
-    let mut end_index = text.len() - 1;
-    while end_index > 0 {
-        if let Ok(i) = text[..=end_index].parse::<i64>() {
-            return OwnedValue::Integer(i);
-        }
-        end_index -= 1;
-    }
-    OwnedValue::Integer(0)
-}
-
-/// When casting a TEXT value to REAL, the longest possible prefix of the value that can be interpreted
-/// as a real number is extracted from the TEXT value and the remainder ignored. Any leading spaces in
-/// the TEXT value are ignored when converging from TEXT to REAL.
-/// If there is no prefix that can be interpreted as a real number, the result of the conversion is 0.0.
-fn cast_text_to_real(text: &str) -> OwnedValue {
-    let trimmed = text.trim_start();
-    if let Ok(num) = trimmed.parse::<f64>() {
-        return OwnedValue::Float(num);
-    }
-    // Try to find longest valid prefix that parses as a float
-    // TODO: inefficient
-    let mut end_index = trimmed.len() - 1;
-    while end_index > 0 {
-        if let Ok(num) = trimmed[..=end_index].parse::<f64>() {
-            return OwnedValue::Float(num);
-        }
-        end_index -= 1;
-    }
-    OwnedValue::Float(0.0)
-}
-
-/// NUMERIC Casting a TEXT or BLOB value into NUMERIC yields either an INTEGER or a REAL result.
-/// If the input text looks like an integer (there is no decimal point nor exponent) and the value
-/// is small enough to fit in a 64-bit signed integer, then the result will be INTEGER.
-/// Input text that looks like floating point (there is a decimal point and/or an exponent)
-/// and the text describes a value that can be losslessly converted back and forth between IEEE 754
-/// 64-bit float and a 51-bit signed integer, then the result is INTEGER. (In the previous sentence,
-/// a 51-bit integer is specified since that is one bit less than the length of the mantissa of an
-/// IEEE 754 64-bit float and thus provides a 1-bit of margin for the text-to-float conversion operation.)
-/// Any text input that describes a value outside the range of a 64-bit signed integer yields a REAL result.
-/// Casting a REAL or INTEGER value to NUMERIC is a no-op, even if a real value could be losslessly converted to an integer.
-fn cast_text_to_numeric(text: &str) -> OwnedValue {
-    if !text.contains('.') && !text.contains('e') && !text.contains('E') {
-        // Looks like an integer
-        if let Ok(i) = text.parse::<i64>() {
-            return OwnedValue::Integer(i);
-        }
-    }
-    // Try as float
-    if let Ok(f) = text.parse::<f64>() {
-        // Check if can be losslessly converted to 51-bit integer
-        let i = f as i64;
-        if f == i as f64 && i.abs() < (1i64 << 51) {
-            return OwnedValue::Integer(i);
-        }
-        return OwnedValue::Float(f);
-    }
-    OwnedValue::Integer(0)
-}
-
 fn execute_sqlite_version(version_integer: i64) -> String {
     let major = version_integer / 1_000_000;
     let minor = (version_integer % 1_000_000) / 1_000;
@@ -3219,18 +2740,18 @@ fn execute_sqlite_version(version_integer: i64) -> String {
 #[cfg(test)]
 mod tests {
 
-    use crate::types::{SeekKey, SeekOp};
+    use crate::types::{PendingIo, SeekKey, SeekOp};
 
     use super::{
-        exec_abs, exec_char, exec_hex, exec_if, exec_instr, exec_length, exec_like, exec_lower,
-        exec_ltrim, exec_max, exec_min, exec_nullif, exec_quote, exec_random, exec_randomblob,
-        exec_round, exec_rtrim, exec_sign, exec_substring, exec_trim, exec_typeof, exec_unhex,
-        exec_unicode, exec_upper, exec_zeroblob, execute_sqlite_version, get_new_rowid, AggContext,
-        Cursor, CursorResult, LimboError, OwnedRecord, OwnedValue, Result,
+        exec_abs, exec_char, exec_hex, exec_if, exec_instr, exec_length, exec_lower, exec_nullif,
+        exec_quote, exec_random, exec_randomblob, exec_round, exec_sign, exec_substring, exec_trim,
+        exec_typeof, exec_unhex, exec_unicode, exec_upper, exec_zeroblob, execute_sqlite_version,
+        get_new_rowid, scalar_max, scalar_min, AggContext, Cursor, CursorResult, LimboError,
+        OwnedRecord, OwnedValue, Result, TrimMode,
     };
     use mockall::{mock, predicate};
     use rand::{rngs::mock::StepRng, thread_rng};
-    use std::{cell::Ref, collections::HashMap, rc::Rc};
+    use std::{cell::Ref, rc::Rc};
 
     mock! {
         Cursor {
@@ -3291,6 +2812,25 @@ mod tests {
             unimplemented!()
         }
 
+        fn insert_batch(
+            &mut self,
+            _rows: &[(OwnedValue, OwnedRecord)],
+        ) -> Result<CursorResult<()>> {
+            Err(LimboError::InternalError("not mocked".to_string()))
+        }
+
+        fn delete(&mut self) -> Result<CursorResult<()>> {
+            Err(LimboError::InternalError("not mocked".to_string()))
+        }
+
+        fn update(&mut self, _record: &OwnedRecord) -> Result<CursorResult<()>> {
+            Err(LimboError::InternalError("not mocked".to_string()))
+        }
+
+        fn count(&mut self) -> Result<CursorResult<u64>> {
+            Err(LimboError::InternalError("not mocked".to_string()))
+        }
+
         fn exists(&mut self, _key: &OwnedValue) -> Result<CursorResult<bool>> {
             unimplemented!()
         }
@@ -3361,10 +2901,13 @@ mod tests {
             .return_once(|| Ok(Some(i64::MAX as u64)));
         mock.expect_seek()
             .with(predicate::always(), predicate::always())
-            .return_once(|_, _| Ok(CursorResult::IO));
+            .return_once(|_, _| Ok(CursorResult::IO(PendingIo::page(7))));
 
         let result = get_new_rowid(&mut (Box::new(mock) as Box<dyn Cursor>), thread_rng());
-        assert!(matches!(result, Ok(CursorResult::IO)));
+        assert!(matches!(
+            result,
+            Ok(CursorResult::IO(PendingIo { page_id: 7 }))
+        ));
 
         // Test case 4: Failure to generate new rowid
         let mut mock = MockCursor::new();
@@ -3385,7 +2928,7 @@ mod tests {
 
     #[test]
     fn test_length() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("bob")));
+        let input_str = OwnedValue::Text(Rc::from("bob"));
         let expected_len = OwnedValue::Integer(3);
         assert_eq!(exec_length(&input_str), expected_len);
 
@@ -3397,65 +2940,69 @@ mod tests {
         let expected_len = OwnedValue::Integer(7);
         assert_eq!(exec_length(&input_float), expected_len);
 
-        let expected_blob = OwnedValue::Blob(Rc::new("example".as_bytes().to_vec()));
+        let expected_blob = OwnedValue::Blob(Rc::from("example".as_bytes().to_vec()));
         let expected_len = OwnedValue::Integer(7);
         assert_eq!(exec_length(&expected_blob), expected_len);
     }
 
     #[test]
     fn test_quote() {
-        let input = OwnedValue::Text(Rc::new(String::from("abc\0edf")));
-        let expected = OwnedValue::Text(Rc::new(String::from("'abc'")));
+        let input = OwnedValue::Text(Rc::from("abc\0edf"));
+        let expected = OwnedValue::Text(Rc::from("'abc'"));
         assert_eq!(exec_quote(&input), expected);
 
         let input = OwnedValue::Integer(123);
-        let expected = OwnedValue::Integer(123);
+        let expected = OwnedValue::Text(Rc::from("123"));
         assert_eq!(exec_quote(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("hello''world")));
-        let expected = OwnedValue::Text(Rc::new(String::from("'hello''world'")));
+        let input = OwnedValue::Text(Rc::from("hello''world"));
+        let expected = OwnedValue::Text(Rc::from("'hello''''world'"));
+        assert_eq!(exec_quote(&input), expected);
+
+        let input = OwnedValue::Blob(Rc::from(vec![0x00, 0xab, 0x1f]));
+        let expected = OwnedValue::Text(Rc::from("X'00AB1F'"));
         assert_eq!(exec_quote(&input), expected);
     }
 
     #[test]
     fn test_typeof() {
         let input = OwnedValue::Null;
-        let expected: OwnedValue = OwnedValue::Text(Rc::new("null".to_string()));
+        let expected: OwnedValue = OwnedValue::Text(Rc::from("null"));
         assert_eq!(exec_typeof(&input), expected);
 
         let input = OwnedValue::Integer(123);
-        let expected: OwnedValue = OwnedValue::Text(Rc::new("integer".to_string()));
+        let expected: OwnedValue = OwnedValue::Text(Rc::from("integer"));
         assert_eq!(exec_typeof(&input), expected);
 
         let input = OwnedValue::Float(123.456);
-        let expected: OwnedValue = OwnedValue::Text(Rc::new("real".to_string()));
+        let expected: OwnedValue = OwnedValue::Text(Rc::from("real"));
         assert_eq!(exec_typeof(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("hello".to_string()));
-        let expected: OwnedValue = OwnedValue::Text(Rc::new("text".to_string()));
+        let input = OwnedValue::Text(Rc::from("hello"));
+        let expected: OwnedValue = OwnedValue::Text(Rc::from("text"));
         assert_eq!(exec_typeof(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new("limbo".as_bytes().to_vec()));
-        let expected: OwnedValue = OwnedValue::Text(Rc::new("blob".to_string()));
+        let input = OwnedValue::Blob(Rc::from("limbo".as_bytes().to_vec()));
+        let expected: OwnedValue = OwnedValue::Text(Rc::from("blob"));
         assert_eq!(exec_typeof(&input), expected);
 
         let input = OwnedValue::Agg(Box::new(AggContext::Sum(OwnedValue::Integer(123))));
-        let expected = OwnedValue::Text(Rc::new("integer".to_string()));
+        let expected = OwnedValue::Text(Rc::from("integer"));
         assert_eq!(exec_typeof(&input), expected);
     }
 
     #[test]
     fn test_unicode() {
         assert_eq!(
-            exec_unicode(&OwnedValue::Text(Rc::new("a".to_string()))),
+            exec_unicode(&OwnedValue::Text(Rc::from("a"))),
             OwnedValue::Integer(97)
         );
         assert_eq!(
-            exec_unicode(&OwnedValue::Text(Rc::new("😊".to_string()))),
+            exec_unicode(&OwnedValue::Text(Rc::from("😊"))),
             OwnedValue::Integer(128522)
         );
         assert_eq!(
-            exec_unicode(&OwnedValue::Text(Rc::new("".to_string()))),
+            exec_unicode(&OwnedValue::Text(Rc::from(""))),
             OwnedValue::Null
         );
         assert_eq!(
@@ -3476,138 +3023,153 @@ mod tests {
         );
         assert_eq!(exec_unicode(&OwnedValue::Null), OwnedValue::Null);
         assert_eq!(
-            exec_unicode(&OwnedValue::Blob(Rc::new("example".as_bytes().to_vec()))),
+            exec_unicode(&OwnedValue::Blob(Rc::from("example".as_bytes().to_vec()))),
             OwnedValue::Integer(101)
         );
     }
 
     #[test]
     fn test_min_max() {
-        let input_int_vec = vec![&OwnedValue::Integer(-1), &OwnedValue::Integer(10)];
-        assert_eq!(exec_min(input_int_vec.clone()), OwnedValue::Integer(-1));
-        assert_eq!(exec_max(input_int_vec.clone()), OwnedValue::Integer(10));
-
-        let str1 = OwnedValue::Text(Rc::new(String::from("A")));
-        let str2 = OwnedValue::Text(Rc::new(String::from("z")));
-        let input_str_vec = vec![&str2, &str1];
+        let input_int_vec = [OwnedValue::Integer(-1), OwnedValue::Integer(10)];
+        assert_eq!(scalar_min(&input_int_vec), OwnedValue::Integer(-1));
+        assert_eq!(scalar_max(&input_int_vec), OwnedValue::Integer(10));
+
+        let str1 = OwnedValue::Text(Rc::from("A"));
+        let str2 = OwnedValue::Text(Rc::from("z"));
+        let input_str_vec = [str2.clone(), str1.clone()];
+        assert_eq!(scalar_min(&input_str_vec), OwnedValue::Text(Rc::from("A")));
+        assert_eq!(scalar_max(&input_str_vec), OwnedValue::Text(Rc::from("z")));
+
+        let input_null_vec = [OwnedValue::Null, OwnedValue::Null];
+        assert_eq!(scalar_min(&input_null_vec), OwnedValue::Null);
+        assert_eq!(scalar_max(&input_null_vec), OwnedValue::Null);
+
+        let input_mixed_vec = [OwnedValue::Integer(10), str1.clone()];
+        assert_eq!(scalar_min(&input_mixed_vec), OwnedValue::Integer(10));
         assert_eq!(
-            exec_min(input_str_vec.clone()),
-            OwnedValue::Text(Rc::new(String::from("A")))
-        );
-        assert_eq!(
-            exec_max(input_str_vec.clone()),
-            OwnedValue::Text(Rc::new(String::from("z")))
-        );
-
-        let input_null_vec = vec![&OwnedValue::Null, &OwnedValue::Null];
-        assert_eq!(exec_min(input_null_vec.clone()), OwnedValue::Null);
-        assert_eq!(exec_max(input_null_vec.clone()), OwnedValue::Null);
-
-        let input_mixed_vec = vec![&OwnedValue::Integer(10), &str1];
-        assert_eq!(exec_min(input_mixed_vec.clone()), OwnedValue::Integer(10));
-        assert_eq!(
-            exec_max(input_mixed_vec.clone()),
-            OwnedValue::Text(Rc::new(String::from("A")))
+            scalar_max(&input_mixed_vec),
+            OwnedValue::Text(Rc::from("A"))
         );
     }
 
     #[test]
     fn test_trim() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("Bob and Alice")));
-        assert_eq!(exec_trim(&input_str, None), expected_str);
-
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let pattern_str = OwnedValue::Text(Rc::new(String::from("Bob and")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("Alice")));
-        assert_eq!(exec_trim(&input_str, Some(pattern_str)), expected_str);
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let expected_str = OwnedValue::Text(Rc::from("Bob and Alice"));
+        assert_eq!(exec_trim(&input_str, None, TrimMode::Both), expected_str);
+
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let pattern_str = OwnedValue::Text(Rc::from("Bob and"));
+        let expected_str = OwnedValue::Text(Rc::from("Alice"));
+        assert_eq!(
+            exec_trim(&input_str, Some(&pattern_str), TrimMode::Both),
+            expected_str
+        );
     }
 
     #[test]
     fn test_ltrim() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("Bob and Alice     ")));
-        assert_eq!(exec_ltrim(&input_str, None), expected_str);
-
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let pattern_str = OwnedValue::Text(Rc::new(String::from("Bob and")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("Alice     ")));
-        assert_eq!(exec_ltrim(&input_str, Some(pattern_str)), expected_str);
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let expected_str = OwnedValue::Text(Rc::from("Bob and Alice     "));
+        assert_eq!(exec_trim(&input_str, None, TrimMode::Leading), expected_str);
+
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let pattern_str = OwnedValue::Text(Rc::from("Bob and"));
+        let expected_str = OwnedValue::Text(Rc::from("Alice     "));
+        assert_eq!(
+            exec_trim(&input_str, Some(&pattern_str), TrimMode::Leading),
+            expected_str
+        );
     }
 
     #[test]
     fn test_rtrim() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice")));
-        assert_eq!(exec_rtrim(&input_str, None), expected_str);
-
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let pattern_str = OwnedValue::Text(Rc::new(String::from("Bob and")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice")));
-        assert_eq!(exec_rtrim(&input_str, Some(pattern_str)), expected_str);
-
-        let input_str = OwnedValue::Text(Rc::new(String::from("     Bob and Alice     ")));
-        let pattern_str = OwnedValue::Text(Rc::new(String::from("and Alice")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("     Bob")));
-        assert_eq!(exec_rtrim(&input_str, Some(pattern_str)), expected_str);
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let expected_str = OwnedValue::Text(Rc::from("     Bob and Alice"));
+        assert_eq!(
+            exec_trim(&input_str, None, TrimMode::Trailing),
+            expected_str
+        );
+
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let pattern_str = OwnedValue::Text(Rc::from("Bob and"));
+        let expected_str = OwnedValue::Text(Rc::from("     Bob and Alice"));
+        assert_eq!(
+            exec_trim(&input_str, Some(&pattern_str), TrimMode::Trailing),
+            expected_str
+        );
+
+        let input_str = OwnedValue::Text(Rc::from("     Bob and Alice     "));
+        let pattern_str = OwnedValue::Text(Rc::from("and Alice"));
+        let expected_str = OwnedValue::Text(Rc::from("     Bob"));
+        assert_eq!(
+            exec_trim(&input_str, Some(&pattern_str), TrimMode::Trailing),
+            expected_str
+        );
     }
 
     #[test]
     fn test_upper_case() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("Limbo")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("LIMBO")));
+        let input_str = OwnedValue::Text(Rc::from("Limbo"));
+        let expected_str = OwnedValue::Text(Rc::from("LIMBO"));
         assert_eq!(exec_upper(&input_str).unwrap(), expected_str);
 
         let input_int = OwnedValue::Integer(10);
-        assert_eq!(exec_upper(&input_int).unwrap(), input_int);
+        let expected_str = OwnedValue::Text(Rc::from("10"));
+        assert_eq!(exec_upper(&input_int).unwrap(), expected_str);
         assert_eq!(exec_upper(&OwnedValue::Null).unwrap(), OwnedValue::Null)
     }
 
     #[test]
     fn test_lower_case() {
-        let input_str = OwnedValue::Text(Rc::new(String::from("Limbo")));
-        let expected_str = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let input_str = OwnedValue::Text(Rc::from("Limbo"));
+        let expected_str = OwnedValue::Text(Rc::from("limbo"));
         assert_eq!(exec_lower(&input_str).unwrap(), expected_str);
 
         let input_int = OwnedValue::Integer(10);
-        assert_eq!(exec_lower(&input_int).unwrap(), input_int);
+        let expected_str = OwnedValue::Text(Rc::from("10"));
+        assert_eq!(exec_lower(&input_int).unwrap(), expected_str);
         assert_eq!(exec_lower(&OwnedValue::Null).unwrap(), OwnedValue::Null)
     }
 
     #[test]
     fn test_hex() {
-        let input_str = OwnedValue::Text(Rc::new("limbo".to_string()));
-        let expected_val = OwnedValue::Text(Rc::new(String::from("6C696D626F")));
+        let input_str = OwnedValue::Text(Rc::from("limbo"));
+        let expected_val = OwnedValue::Text(Rc::from("6C696D626F"));
         assert_eq!(exec_hex(&input_str), expected_val);
 
         let input_int = OwnedValue::Integer(100);
-        let expected_val = OwnedValue::Text(Rc::new(String::from("313030")));
+        let expected_val = OwnedValue::Text(Rc::from("313030"));
         assert_eq!(exec_hex(&input_int), expected_val);
 
         let input_float = OwnedValue::Float(12.34);
-        let expected_val = OwnedValue::Text(Rc::new(String::from("31322E3334")));
+        let expected_val = OwnedValue::Text(Rc::from("31322E3334"));
         assert_eq!(exec_hex(&input_float), expected_val);
+
+        let input_blob = OwnedValue::Blob(Rc::from(vec![0x00, 0xff, 0x80]));
+        let expected_val = OwnedValue::Text(Rc::from("00FF80"));
+        assert_eq!(exec_hex(&input_blob), expected_val);
     }
 
     #[test]
     fn test_unhex() {
-        let input = OwnedValue::Text(Rc::new(String::from("6F")));
-        let expected = OwnedValue::Blob(Rc::new(vec![0x6f]));
+        let input = OwnedValue::Text(Rc::from("6F"));
+        let expected = OwnedValue::Blob(Rc::from(vec![0x6f]));
         assert_eq!(exec_unhex(&input, None), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("6f")));
-        let expected = OwnedValue::Blob(Rc::new(vec![0x6f]));
+        let input = OwnedValue::Text(Rc::from("6f"));
+        let expected = OwnedValue::Blob(Rc::from(vec![0x6f]));
         assert_eq!(exec_unhex(&input, None), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("611")));
+        let input = OwnedValue::Text(Rc::from("611"));
         let expected = OwnedValue::Null;
         assert_eq!(exec_unhex(&input, None), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("")));
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let input = OwnedValue::Text(Rc::from(""));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_unhex(&input, None), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("61x")));
+        let input = OwnedValue::Text(Rc::from("61x"));
         let expected = OwnedValue::Null;
         assert_eq!(exec_unhex(&input, None), expected);
 
@@ -3629,7 +3191,7 @@ mod tests {
         assert_eq!(exec_abs(&float_negative_reg).unwrap(), float_positive_reg);
 
         assert_eq!(
-            exec_abs(&OwnedValue::Text(Rc::new(String::from("a")))).unwrap(),
+            exec_abs(&OwnedValue::Text(Rc::from("a"))).unwrap(),
             OwnedValue::Float(0.0)
         );
         assert_eq!(exec_abs(&OwnedValue::Null).unwrap(), OwnedValue::Null);
@@ -3639,45 +3201,19 @@ mod tests {
     fn test_char() {
         assert_eq!(
             exec_char(vec![OwnedValue::Integer(108), OwnedValue::Integer(105)]),
-            OwnedValue::Text(Rc::new("li".to_string()))
+            OwnedValue::Text(Rc::from("li"))
         );
-        assert_eq!(exec_char(vec![]), OwnedValue::Text(Rc::new("".to_string())));
+        assert_eq!(exec_char(vec![]), OwnedValue::Text(Rc::from("")));
         assert_eq!(
             exec_char(vec![OwnedValue::Null]),
-            OwnedValue::Text(Rc::new("".to_string()))
+            OwnedValue::Text(Rc::from(""))
         );
         assert_eq!(
-            exec_char(vec![OwnedValue::Text(Rc::new("a".to_string()))]),
-            OwnedValue::Text(Rc::new("".to_string()))
+            exec_char(vec![OwnedValue::Text(Rc::from("a"))]),
+            OwnedValue::Text(Rc::from(""))
         );
     }
 
-    #[test]
-    fn test_like_no_cache() {
-        assert!(exec_like(None, "a%", "aaaa"));
-        assert!(exec_like(None, "%a%a", "aaaa"));
-        assert!(exec_like(None, "%a.a", "aaaa"));
-        assert!(exec_like(None, "a.a%", "aaaa"));
-        assert!(!exec_like(None, "%a.ab", "aaaa"));
-    }
-
-    #[test]
-    fn test_like_with_cache() {
-        let mut cache = HashMap::new();
-        assert!(exec_like(Some(&mut cache), "a%", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "%a%a", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "%a.a", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "a.a%", "aaaa"));
-        assert!(!exec_like(Some(&mut cache), "%a.ab", "aaaa"));
-
-        // again after values have been cached
-        assert!(exec_like(Some(&mut cache), "a%", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "%a%a", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "%a.a", "aaaa"));
-        assert!(exec_like(Some(&mut cache), "a.a%", "aaaa"));
-        assert!(!exec_like(Some(&mut cache), "%a.ab", "aaaa"));
-    }
-
     #[test]
     fn test_random() {
         match exec_random() {
@@ -3713,19 +3249,19 @@ mod tests {
                 expected_len: 1,
             },
             TestCase {
-                input: OwnedValue::Text(Rc::new(String::from(""))),
+                input: OwnedValue::Text(Rc::from("")),
                 expected_len: 1,
             },
             TestCase {
-                input: OwnedValue::Text(Rc::new(String::from("5"))),
+                input: OwnedValue::Text(Rc::from("5")),
                 expected_len: 5,
             },
             TestCase {
-                input: OwnedValue::Text(Rc::new(String::from("0"))),
+                input: OwnedValue::Text(Rc::from("0")),
                 expected_len: 1,
             },
             TestCase {
-                input: OwnedValue::Text(Rc::new(String::from("-1"))),
+                input: OwnedValue::Text(Rc::from("-1")),
                 expected_len: 1,
             },
             TestCase {
@@ -3765,11 +3301,11 @@ mod tests {
         assert_eq!(exec_round(&input_val, Some(precision_val)), expected_val);
 
         let input_val = OwnedValue::Float(123.456);
-        let precision_val = OwnedValue::Text(Rc::new(String::from("1")));
+        let precision_val = OwnedValue::Text(Rc::from("1"));
         let expected_val = OwnedValue::Float(123.5);
         assert_eq!(exec_round(&input_val, Some(precision_val)), expected_val);
 
-        let input_val = OwnedValue::Text(Rc::new(String::from("123.456")));
+        let input_val = OwnedValue::Text(Rc::from("123.456"));
         let precision_val = OwnedValue::Integer(2);
         let expected_val = OwnedValue::Float(123.46);
         assert_eq!(exec_round(&input_val, Some(precision_val)), expected_val);
@@ -3828,8 +3364,8 @@ mod tests {
         );
         assert_eq!(
             exec_nullif(
-                &OwnedValue::Text(Rc::new("limbo".to_string())),
-                &OwnedValue::Text(Rc::new("limbo".to_string()))
+                &OwnedValue::Text(Rc::from("limbo")),
+                &OwnedValue::Text(Rc::from("limbo"))
             ),
             OwnedValue::Null
         );
@@ -3844,100 +3380,118 @@ mod tests {
         );
         assert_eq!(
             exec_nullif(
-                &OwnedValue::Text(Rc::new("limbo".to_string())),
-                &OwnedValue::Text(Rc::new("limb".to_string()))
+                &OwnedValue::Text(Rc::from("limbo")),
+                &OwnedValue::Text(Rc::from("limb"))
             ),
-            OwnedValue::Text(Rc::new("limbo".to_string()))
+            OwnedValue::Text(Rc::from("limbo"))
         );
     }
 
     #[test]
     fn test_substring() {
-        let str_value = OwnedValue::Text(Rc::new("limbo".to_string()));
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
         let start_value = OwnedValue::Integer(1);
         let length_value = OwnedValue::Integer(3);
-        let expected_val = OwnedValue::Text(Rc::new(String::from("lim")));
+        let expected_val = OwnedValue::Text(Rc::from("lim"));
         assert_eq!(
-            exec_substring(&str_value, &start_value, &length_value),
+            exec_substring(&str_value, &start_value, Some(&length_value)),
             expected_val
         );
 
-        let str_value = OwnedValue::Text(Rc::new("limbo".to_string()));
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
         let start_value = OwnedValue::Integer(1);
         let length_value = OwnedValue::Integer(10);
-        let expected_val = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let expected_val = OwnedValue::Text(Rc::from("limbo"));
         assert_eq!(
-            exec_substring(&str_value, &start_value, &length_value),
+            exec_substring(&str_value, &start_value, Some(&length_value)),
             expected_val
         );
 
-        let str_value = OwnedValue::Text(Rc::new("limbo".to_string()));
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
         let start_value = OwnedValue::Integer(10);
         let length_value = OwnedValue::Integer(3);
-        let expected_val = OwnedValue::Text(Rc::new(String::from("")));
+        let expected_val = OwnedValue::Text(Rc::from(""));
         assert_eq!(
-            exec_substring(&str_value, &start_value, &length_value),
+            exec_substring(&str_value, &start_value, Some(&length_value)),
             expected_val
         );
 
-        let str_value = OwnedValue::Text(Rc::new("limbo".to_string()));
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
+        let start_value = OwnedValue::Integer(3);
+        let expected_val = OwnedValue::Text(Rc::from("mbo"));
+        assert_eq!(exec_substring(&str_value, &start_value, None), expected_val);
+
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
+        let start_value = OwnedValue::Integer(10);
+        let expected_val = OwnedValue::Text(Rc::from(""));
+        assert_eq!(exec_substring(&str_value, &start_value, None), expected_val);
+
+        let str_value = OwnedValue::Text(Rc::from("limbo"));
         let start_value = OwnedValue::Integer(3);
         let length_value = OwnedValue::Null;
-        let expected_val = OwnedValue::Text(Rc::new(String::from("mbo")));
         assert_eq!(
-            exec_substring(&str_value, &start_value, &length_value),
-            expected_val
+            exec_substring(&str_value, &start_value, Some(&length_value)),
+            OwnedValue::Null
+        );
+        assert_eq!(
+            exec_substring(&str_value, &OwnedValue::Null, None),
+            OwnedValue::Null
         );
 
-        let str_value = OwnedValue::Text(Rc::new("limbo".to_string()));
-        let start_value = OwnedValue::Integer(10);
-        let length_value = OwnedValue::Null;
-        let expected_val = OwnedValue::Text(Rc::new(String::from("")));
+        let str_value = OwnedValue::Text(Rc::from("abcdef"));
+        let start_value = OwnedValue::Text(Rc::from("2"));
+        let length_value = OwnedValue::Integer(2);
+        let expected_val = OwnedValue::Text(Rc::from("bc"));
         assert_eq!(
-            exec_substring(&str_value, &start_value, &length_value),
+            exec_substring(&str_value, &start_value, Some(&length_value)),
             expected_val
         );
+
+        let str_value = OwnedValue::Text(Rc::from("abcdef"));
+        let start_value = OwnedValue::Float(2.0);
+        let expected_val = OwnedValue::Text(Rc::from("bcdef"));
+        assert_eq!(exec_substring(&str_value, &start_value, None), expected_val);
     }
 
     #[test]
     fn test_exec_instr() {
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("im")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
+        let pattern = OwnedValue::Text(Rc::from("im"));
         let expected = OwnedValue::Integer(2);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
+        let pattern = OwnedValue::Text(Rc::from("limbo"));
         let expected = OwnedValue::Integer(1);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("o")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
+        let pattern = OwnedValue::Text(Rc::from("o"));
         let expected = OwnedValue::Integer(5);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("liiiiimbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("ii")));
+        let input = OwnedValue::Text(Rc::from("liiiiimbo"));
+        let pattern = OwnedValue::Text(Rc::from("ii"));
         let expected = OwnedValue::Integer(2);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("limboX")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
+        let pattern = OwnedValue::Text(Rc::from("limboX"));
         let expected = OwnedValue::Integer(0);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
+        let pattern = OwnedValue::Text(Rc::from(""));
         let expected = OwnedValue::Integer(1);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let input = OwnedValue::Text(Rc::from(""));
+        let pattern = OwnedValue::Text(Rc::from("limbo"));
         let expected = OwnedValue::Integer(0);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("")));
-        let pattern = OwnedValue::Text(Rc::new(String::from("")));
+        let input = OwnedValue::Text(Rc::from(""));
+        let pattern = OwnedValue::Text(Rc::from(""));
         let expected = OwnedValue::Integer(1);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
@@ -3946,13 +3500,13 @@ mod tests {
         let expected = OwnedValue::Null;
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let input = OwnedValue::Text(Rc::from("limbo"));
         let pattern = OwnedValue::Null;
         let expected = OwnedValue::Null;
         assert_eq!(exec_instr(&input, &pattern), expected);
 
         let input = OwnedValue::Null;
-        let pattern = OwnedValue::Text(Rc::new(String::from("limbo")));
+        let pattern = OwnedValue::Text(Rc::from("limbo"));
         let expected = OwnedValue::Null;
         assert_eq!(exec_instr(&input, &pattern), expected);
 
@@ -3977,27 +3531,27 @@ mod tests {
         assert_eq!(exec_instr(&input, &pattern), expected);
 
         let input = OwnedValue::Float(12.34);
-        let pattern = OwnedValue::Text(Rc::new(String::from(".")));
+        let pattern = OwnedValue::Text(Rc::from("."));
         let expected = OwnedValue::Integer(3);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Blob(Rc::new(vec![1, 2, 3, 4, 5]));
-        let pattern = OwnedValue::Blob(Rc::new(vec![3, 4]));
+        let input = OwnedValue::Blob(Rc::from(vec![1, 2, 3, 4, 5]));
+        let pattern = OwnedValue::Blob(Rc::from(vec![3, 4]));
         let expected = OwnedValue::Integer(3);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Blob(Rc::new(vec![1, 2, 3, 4, 5]));
-        let pattern = OwnedValue::Blob(Rc::new(vec![3, 2]));
+        let input = OwnedValue::Blob(Rc::from(vec![1, 2, 3, 4, 5]));
+        let pattern = OwnedValue::Blob(Rc::from(vec![3, 2]));
         let expected = OwnedValue::Integer(0);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Blob(Rc::new(vec![0x61, 0x62, 0x63, 0x64, 0x65]));
-        let pattern = OwnedValue::Text(Rc::new(String::from("cd")));
+        let input = OwnedValue::Blob(Rc::from(vec![0x61, 0x62, 0x63, 0x64, 0x65]));
+        let pattern = OwnedValue::Text(Rc::from("cd"));
         let expected = OwnedValue::Integer(3);
         assert_eq!(exec_instr(&input, &pattern), expected);
 
-        let input = OwnedValue::Text(Rc::new(String::from("abcde")));
-        let pattern = OwnedValue::Blob(Rc::new(vec![0x63, 0x64]));
+        let input = OwnedValue::Text(Rc::from("abcde"));
+        let pattern = OwnedValue::Blob(Rc::from(vec![0x63, 0x64]));
         let expected = OwnedValue::Integer(3);
         assert_eq!(exec_instr(&input, &pattern), expected);
     }
@@ -4032,36 +3586,36 @@ mod tests {
         let expected = Some(OwnedValue::Integer(-1));
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("abc".to_string()));
+        let input = OwnedValue::Text(Rc::from("abc"));
         let expected = Some(OwnedValue::Null);
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("42".to_string()));
+        let input = OwnedValue::Text(Rc::from("42"));
         let expected = Some(OwnedValue::Integer(1));
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("-42".to_string()));
+        let input = OwnedValue::Text(Rc::from("-42"));
         let expected = Some(OwnedValue::Integer(-1));
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("0".to_string()));
+        let input = OwnedValue::Text(Rc::from("0"));
         let expected = Some(OwnedValue::Integer(0));
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new(b"abc".to_vec()));
+        let input = OwnedValue::Blob(Rc::from(b"abc".to_vec()));
         let expected = Some(OwnedValue::Null);
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new(b"42".to_vec()));
-        let expected = Some(OwnedValue::Integer(1));
+        let input = OwnedValue::Blob(Rc::from(b"42".to_vec()));
+        let expected = Some(OwnedValue::Null);
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new(b"-42".to_vec()));
-        let expected = Some(OwnedValue::Integer(-1));
+        let input = OwnedValue::Blob(Rc::from(b"-42".to_vec()));
+        let expected = Some(OwnedValue::Null);
         assert_eq!(exec_sign(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new(b"0".to_vec()));
-        let expected = Some(OwnedValue::Integer(0));
+        let input = OwnedValue::Blob(Rc::from(b"0".to_vec()));
+        let expected = Some(OwnedValue::Null);
         assert_eq!(exec_sign(&input), expected);
 
         let input = OwnedValue::Null;
@@ -4072,39 +3626,39 @@ mod tests {
     #[test]
     fn test_exec_zeroblob() {
         let input = OwnedValue::Integer(0);
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
 
         let input = OwnedValue::Null;
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
 
         let input = OwnedValue::Integer(4);
-        let expected = OwnedValue::Blob(Rc::new(vec![0; 4]));
+        let expected = OwnedValue::Blob(Rc::from(vec![0; 4]));
         assert_eq!(exec_zeroblob(&input), expected);
 
         let input = OwnedValue::Integer(-1);
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("5".to_string()));
-        let expected = OwnedValue::Blob(Rc::new(vec![0; 5]));
+        let input = OwnedValue::Text(Rc::from("5"));
+        let expected = OwnedValue::Blob(Rc::from(vec![0; 5]));
         assert_eq!(exec_zeroblob(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("-5".to_string()));
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let input = OwnedValue::Text(Rc::from("-5"));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
 
-        let input = OwnedValue::Text(Rc::new("text".to_string()));
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let input = OwnedValue::Text(Rc::from("text"));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
 
         let input = OwnedValue::Float(2.6);
-        let expected = OwnedValue::Blob(Rc::new(vec![0; 2]));
+        let expected = OwnedValue::Blob(Rc::from(vec![0; 2]));
         assert_eq!(exec_zeroblob(&input), expected);
 
-        let input = OwnedValue::Blob(Rc::new(vec![1]));
-        let expected = OwnedValue::Blob(Rc::new(vec![]));
+        let input = OwnedValue::Blob(Rc::from(vec![1]));
+        let expected = OwnedValue::Blob(Rc::from(vec![]));
         assert_eq!(exec_zeroblob(&input), expected);
     }
 
@@ -1,6 +1,6 @@
 use crate::{
     types::{Cursor, CursorResult, OwnedRecord, OwnedValue, SeekKey, SeekOp},
-    Result,
+    LimboError, Result,
 };
 use std::cell::{Ref, RefCell};
 use std::cmp::Ordering;
@@ -92,6 +92,30 @@ impl Cursor for Sorter {
         Ok(CursorResult::Ok(()))
     }
 
+    fn insert_batch(&mut self, rows: &[(OwnedValue, OwnedRecord)]) -> Result<CursorResult<()>> {
+        self.records
+            .extend(rows.iter().map(|(_, record)| record.clone()));
+        Ok(CursorResult::Ok(()))
+    }
+
+    fn delete(&mut self) -> Result<CursorResult<()>> {
+        Err(LimboError::InternalError(
+            "sorter rows cannot be deleted".to_string(),
+        ))
+    }
+
+    fn update(&mut self, _record: &OwnedRecord) -> Result<CursorResult<()>> {
+        Err(LimboError::InternalError(
+            "sorter rows cannot be updated".to_string(),
+        ))
+    }
+
+    // the rows not read yet, including the current one
+    fn count(&mut self) -> Result<CursorResult<u64>> {
+        let current = self.current.borrow().is_some() as usize;
+        Ok(CursorResult::Ok((self.records.len() + current) as u64))
+    }
+
     fn set_null_flag(&mut self, _flag: bool) {
         todo!();
     }
@@ -342,6 +342,9 @@ fn get_all_rows(
                         limbo_core::Value::Float(f) => Value::Float(*f),
                         limbo_core::Value::Text(t) => Value::Text(t.to_string()),
                         limbo_core::Value::Blob(b) => Value::Blob(b.to_vec()),
+                        limbo_core::Value::Record(r) => {
+                            unreachable!("unexpected record in result row: {:?}", r)
+                        }
                     };
                     r.push(v);
                 }
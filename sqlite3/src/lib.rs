@@ -561,7 +561,7 @@ pub unsafe extern "C" fn sqlite3_value_type(value: *mut std::ffi::c_void) -> ffi
     let value = value as *mut limbo_core::Value;
     let value = &*value;
     match value {
-        limbo_core::Value::Null => 0,
+        limbo_core::Value::Null | limbo_core::Value::Record(_) => 0,
         limbo_core::Value::Integer(_) => 1,
         limbo_core::Value::Float(_) => 2,
         limbo_core::Value::Text(_) => 3,
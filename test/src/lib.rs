@@ -380,7 +380,83 @@ mod tests {
         Ok(())
     }
 
-    fn compare_string(a: &String, b: &String) {
+    #[test]
+    fn test_group_by_negative_zero() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let tmp_db = TempDatabase::new("CREATE TABLE test (x);");
+        {
+            let connection = rusqlite::Connection::open(&tmp_db.path)?;
+            for x in [-0.0, 0.0, 1.5, -0.0] {
+                connection.execute("INSERT INTO test VALUES (?1)", [x])?;
+            }
+        }
+        let conn = tmp_db.connect_limbo();
+
+        let mut groups = vec![];
+        if let Some(ref mut rows) = conn.query("SELECT x, count(*) FROM test GROUP BY x")? {
+            loop {
+                match rows.next_row()? {
+                    RowResult::Row(row) => {
+                        let (Value::Float(x), Value::Integer(count)) =
+                            (&row.values[0], &row.values[1])
+                        else {
+                            unreachable!("unexpected row {:?}", row.values);
+                        };
+                        groups.push((*x, *count));
+                    }
+                    RowResult::IO => {
+                        tmp_db.io.run_once()?;
+                    }
+                    RowResult::Done => break,
+                }
+            }
+        }
+        // -0.0 and 0.0 are the same group, like in SQLite
+        assert_eq!(groups, vec![(0.0, 3), (1.5, 1)]);
+        conn.close()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_max_skip_nulls() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let tmp_db = TempDatabase::new("CREATE TABLE test (x);");
+        {
+            let connection = rusqlite::Connection::open(&tmp_db.path)?;
+            connection.execute_batch(
+                "INSERT INTO test VALUES (NULL), (3), ('abc'), (2.5), (x'00'), (NULL), (-1);",
+            )?;
+        }
+        let conn = tmp_db.connect_limbo();
+
+        let queries = [
+            "SELECT min(x), max(x) FROM test",
+            "SELECT min(x), max(x) FROM test WHERE typeof(x) != 'blob' AND typeof(x) != 'text'",
+            "SELECT min(x), max(x) FROM test WHERE typeof(x) = 'null'",
+        ];
+        let mut results = vec![];
+        for query in queries {
+            if let Some(ref mut rows) = conn.query(query)? {
+                loop {
+                    match rows.next_row()? {
+                        RowResult::Row(row) => {
+                            results.push(format!("{}|{}", row.values[0], row.values[1]));
+                        }
+                        RowResult::IO => {
+                            tmp_db.io.run_once()?;
+                        }
+                        RowResult::Done => break,
+                    }
+                }
+            }
+        }
+        // NULLs are skipped, and mixed types compare as numbers < text < blobs
+        assert_eq!(results, vec!["-1|X'00'", "-1|3", "NULL|NULL"]);
+        conn.close()?;
+        Ok(())
+    }
+
+    fn compare_string(a: &str, b: &str) {
         assert_eq!(a.len(), b.len(), "Strings are not equal in size!");
         let a = a.as_bytes();
         let b = b.as_bytes();